@@ -0,0 +1,337 @@
+use std::collections::hash_map::Entry;
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use crate::models::{
+    Config, DEFAULT_QUEUE, Event, Subscription, Video, VideoMeta, validate_queue_name,
+};
+use crate::store::{Snapshot, Storage};
+
+use anyhow::{Context, Result, bail};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// Version of the archive layout written by `ytq export`.
+pub const ARCHIVE_VERSION: u32 = 1;
+
+/// Everything `ytq export` writes: every queue, the history, the metadata
+/// and category caches, subscriptions and the config without the API key.
+/// The undo journal is left out; it only makes sense for the queues it was
+/// recorded against.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Archive {
+    /// The format version. Its presence also tells an archive apart from
+    /// ytq's other JSON files.
+    pub ytq_archive: u32,
+    pub exported_at: DateTime<Utc>,
+    pub config: Config,
+    pub queues: BTreeMap<String, Vec<Video>>,
+    pub history: Vec<Event>,
+    pub metadata: BTreeMap<String, VideoMeta>,
+    pub categories: BTreeMap<String, String>,
+    #[serde(default)]
+    pub subscriptions: Vec<Subscription>,
+}
+
+impl Archive {
+    pub fn new(mut config: Config, snapshot: Snapshot) -> Self {
+        config.youtube_api_key = None;
+        Self {
+            ytq_archive: ARCHIVE_VERSION,
+            exported_at: Utc::now(),
+            config,
+            queues: snapshot.queues,
+            history: snapshot.history,
+            metadata: snapshot.metadata.into_iter().collect(),
+            categories: snapshot.categories.into_iter().collect(),
+            subscriptions: snapshot.subscriptions,
+        }
+    }
+
+    /// Parses and validates an archive.
+    pub fn parse(text: &str) -> Result<Self> {
+        let value: Value = serde_json::from_str(text).context("not a valid JSON file")?;
+        let Some(version) = value.get("ytq_archive").and_then(Value::as_u64) else {
+            bail!("not a ytq archive: create one with `ytq export`");
+        };
+        if version > u64::from(ARCHIVE_VERSION) {
+            bail!(
+                "this archive was written by a newer version of ytq (archive version {version}, \
+                 this one reads up to {ARCHIVE_VERSION}); upgrade ytq to import it"
+            );
+        }
+
+        let archive: Self = serde_json::from_str(text).context("damaged archive")?;
+        // Queue names become file names, so check them like `ytq queue create`
+        let subscription_queues = archive.subscriptions.iter().map(|sub| &sub.queue);
+        for name in archive.queues.keys().chain(subscription_queues) {
+            validate_queue_name(name)?;
+        }
+        Ok(archive)
+    }
+
+    pub fn video_count(&self) -> usize {
+        self.queues.values().map(Vec::len).sum()
+    }
+
+    /// The data to write when the archive replaces everything.
+    pub fn into_snapshot(self) -> (Config, Snapshot) {
+        let snapshot = Snapshot {
+            queues: self.queues,
+            history: self.history,
+            metadata: self.metadata.into_iter().collect(),
+            categories: self.categories.into_iter().collect(),
+            subscriptions: self.subscriptions,
+            journal: Default::default(),
+        };
+        (self.config, snapshot)
+    }
+}
+
+/// What `ytq import` merged.
+#[derive(Debug, Default, PartialEq)]
+pub struct MergeReport {
+    pub queues_created: usize,
+    pub videos_added: usize,
+    /// Videos in both whose `added_at` moved to the archive's earlier one.
+    pub videos_backdated: usize,
+    pub events_added: usize,
+    pub events_skipped: usize,
+    pub metadata_updated: usize,
+    pub categories_added: usize,
+    pub subscriptions_added: usize,
+}
+
+/// Merges `archive` into `storage`, and the archive's per-queue settings
+/// into `config` for queues without settings of their own. Must be called
+/// while holding the queue lock.
+pub fn merge(storage: &dyn Storage, config: &mut Config, archive: Archive) -> Result<MergeReport> {
+    let mut report = MergeReport::default();
+    let local = Snapshot::read(storage)?;
+
+    for (name, incoming) in archive.queues {
+        let mut videos = match local.queues.get(&name) {
+            Some(videos) => videos.clone(),
+            None if name == DEFAULT_QUEUE => Vec::new(),
+            None => {
+                report.queues_created += 1;
+                Vec::new()
+            }
+        };
+        let (added, backdated) = merge_queue(&mut videos, incoming);
+        report.videos_added += added;
+        report.videos_backdated += backdated;
+        if added > 0 || backdated > 0 || !local.queues.contains_key(&name) {
+            storage.save_queue(&name, &videos)?;
+        }
+    }
+
+    let total = archive.history.len();
+    let new_events = new_events(&local.history, archive.history);
+    report.events_added = new_events.len();
+    report.events_skipped = total - new_events.len();
+    storage.log_events(&new_events)?;
+
+    let newer = newer_metadata(&local.metadata, archive.metadata);
+    report.metadata_updated = newer.len();
+    storage.upsert_metadata(&newer)?;
+
+    let mut categories = local.categories;
+    for (id, name) in archive.categories {
+        if let Entry::Vacant(entry) = categories.entry(id) {
+            entry.insert(name);
+            report.categories_added += 1;
+        }
+    }
+    if report.categories_added > 0 {
+        storage.save_categories(&categories)?;
+    }
+
+    let mut subscriptions = local.subscriptions;
+    for sub in archive.subscriptions {
+        if !subscriptions.iter().any(|s| s.channel_id == sub.channel_id) {
+            subscriptions.push(sub);
+            report.subscriptions_added += 1;
+        }
+    }
+    if report.subscriptions_added > 0 {
+        storage.save_subscriptions(&subscriptions)?;
+    }
+
+    for (name, settings) in archive.config.queues {
+        config.queues.entry(name).or_insert(settings);
+    }
+    Ok(report)
+}
+
+/// Adds the incoming videos missing from `videos` to its end, in archive
+/// order. A video in both keeps the local entry with the earlier of the two
+/// `added_at`s. Returns how many were added and how many backdated.
+pub fn merge_queue(videos: &mut Vec<Video>, incoming: Vec<Video>) -> (usize, usize) {
+    let mut added = 0;
+    let mut backdated = 0;
+    for video in incoming {
+        match videos.iter_mut().find(|v| v.id == video.id) {
+            Some(existing) if video.added_at < existing.added_at => {
+                existing.added_at = video.added_at;
+                backdated += 1;
+            }
+            Some(_) => {}
+            None => {
+                videos.push(video);
+                added += 1;
+            }
+        }
+    }
+    (added, backdated)
+}
+
+/// The incoming events not already in `history`, matched by timestamp,
+/// action and video ID, oldest first.
+pub fn new_events(history: &[Event], incoming: Vec<Event>) -> Vec<Event> {
+    let mut seen: HashSet<_> = history
+        .iter()
+        .map(|e| (e.timestamp, e.action.clone(), e.video_id.clone()))
+        .collect();
+    let mut events: Vec<Event> = incoming
+        .into_iter()
+        .filter(|e| seen.insert((e.timestamp, e.action.clone(), e.video_id.clone())))
+        .collect();
+    events.sort_by_key(|e| e.timestamp);
+    events
+}
+
+/// The incoming metadata entries that are new or fetched more recently
+/// than the cached ones.
+pub fn newer_metadata(
+    metadata: &HashMap<String, VideoMeta>,
+    incoming: BTreeMap<String, VideoMeta>,
+) -> Vec<VideoMeta> {
+    incoming
+        .into_values()
+        .filter(|meta| {
+            metadata
+                .get(&meta.id)
+                .is_none_or(|cached| meta.fetched_at > cached.fetched_at)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Action;
+    use crate::test_util::meta;
+
+    fn video(id: &str, added_at: &str) -> Video {
+        Video::new(id.to_string(), String::new(), added_at.parse().unwrap())
+    }
+
+    fn event(id: &str, action: Action, timestamp: &str) -> Event {
+        Event {
+            timestamp: timestamp.parse().unwrap(),
+            action,
+            video_id: id.to_string(),
+            time_in_queue_sec: None,
+            queue: None,
+        }
+    }
+
+    fn fetched(id: &str, fetched_at: &str) -> VideoMeta {
+        VideoMeta {
+            fetched_at: fetched_at.parse().unwrap(),
+            ..meta(id)
+        }
+    }
+
+    #[test]
+    fn queues_merge_by_id_keeping_the_earliest_added_at() {
+        let mut videos = vec![
+            video("a", "2026-02-01T00:00:00Z"),
+            video("b", "2026-01-01T00:00:00Z"),
+        ];
+        let incoming = vec![
+            video("c", "2026-03-01T00:00:00Z"),
+            video("a", "2026-01-15T00:00:00Z"),
+            video("b", "2026-01-20T00:00:00Z"),
+        ];
+
+        assert_eq!(merge_queue(&mut videos, incoming), (1, 1));
+        let ids: Vec<&str> = videos.iter().map(|v| v.id.as_str()).collect();
+        assert_eq!(ids, vec!["a", "b", "c"]);
+        assert_eq!(
+            videos[0].added_at,
+            "2026-01-15T00:00:00Z".parse::<DateTime<Utc>>().unwrap()
+        );
+        assert_eq!(
+            videos[1].added_at,
+            "2026-01-01T00:00:00Z".parse::<DateTime<Utc>>().unwrap()
+        );
+    }
+
+    #[test]
+    fn history_dedupes_on_timestamp_action_and_id() {
+        let history = vec![event("a", Action::Queued, "2026-01-01T00:00:00Z")];
+        let incoming = vec![
+            event("a", Action::Watched, "2026-01-02T00:00:00Z"),
+            event("a", Action::Queued, "2026-01-01T00:00:00Z"),
+            event("b", Action::Queued, "2026-01-01T00:00:00Z"),
+            event("a", Action::Watched, "2026-01-02T00:00:00Z"),
+        ];
+
+        let added: Vec<(String, Action)> = new_events(&history, incoming)
+            .into_iter()
+            .map(|e| (e.video_id, e.action))
+            .collect();
+        assert_eq!(
+            added,
+            vec![
+                ("b".to_string(), Action::Queued),
+                ("a".to_string(), Action::Watched)
+            ]
+        );
+    }
+
+    #[test]
+    fn metadata_keeps_the_newest_fetch() {
+        let cached = HashMap::from([
+            ("a".to_string(), fetched("a", "2026-02-01T00:00:00Z")),
+            ("b".to_string(), fetched("b", "2026-02-01T00:00:00Z")),
+        ]);
+        let incoming = BTreeMap::from([
+            ("a".to_string(), fetched("a", "2026-01-01T00:00:00Z")),
+            ("b".to_string(), fetched("b", "2026-03-01T00:00:00Z")),
+            ("c".to_string(), fetched("c", "2026-01-01T00:00:00Z")),
+        ]);
+
+        let ids: Vec<String> = newer_metadata(&cached, incoming)
+            .into_iter()
+            .map(|m| m.id)
+            .collect();
+        assert_eq!(ids, vec!["b", "c"]);
+    }
+
+    #[test]
+    fn parse_rejects_other_files_newer_archives_and_bad_queue_names() {
+        let archive = Archive::new(
+            Config {
+                youtube_api_key: Some("secret".to_string()),
+                ..Default::default()
+            },
+            Snapshot::default(),
+        );
+        let text = serde_json::to_string(&archive).unwrap();
+        assert!(!text.contains("secret"));
+        assert!(Archive::parse(&text).is_ok());
+
+        let err = Archive::parse(r#"{"version": 1, "data": []}"#).unwrap_err();
+        assert!(err.to_string().contains("not a ytq archive"));
+
+        let newer = text.replace("\"ytq_archive\":1", "\"ytq_archive\":2");
+        let err = Archive::parse(&newer).unwrap_err();
+        assert!(err.to_string().contains("newer version"));
+
+        let traversal = text.replace("\"queues\":{}", "\"queues\":{\"../x\":[]}");
+        assert!(Archive::parse(&traversal).is_err());
+    }
+}
@@ -0,0 +1,427 @@
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::sync::LazyLock;
+
+use crate::models::{Video, VideoMeta};
+use crate::takeout::{csv_records, parse_time, unescape_html};
+use crate::youtube::{self, VideoLink};
+
+use anyhow::{Result, bail};
+use chrono::{DateTime, Utc};
+use regex::Regex;
+use url::Url;
+
+/// A saved link, as read from a bookmark file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Bookmark {
+    pub url: String,
+    /// When it was saved, if the file records it.
+    pub saved_at: Option<DateTime<Utc>>,
+    pub tags: Vec<String>,
+}
+
+/// A bookmark file format `ytq import --bookmarks` reads. New formats only
+/// need an entry in [`FORMATS`].
+pub trait Format {
+    fn name(&self) -> &'static str;
+    /// Whether `text` looks like this format.
+    fn detect(&self, text: &str) -> bool;
+    fn parse(&self, text: &str) -> Result<Vec<Bookmark>>;
+}
+
+/// Every supported format, in the order they are tried.
+pub const FORMATS: &[&dyn Format] = &[&Netscape, &Opml, &Csv];
+
+/// Detects the format of `text` and reads its bookmarks.
+pub fn parse(text: &str) -> Result<(&'static str, Vec<Bookmark>)> {
+    let text = text.trim_start_matches('\u{feff}');
+    let Some(format) = FORMATS.iter().find(|f| f.detect(text)) else {
+        bail!(
+            "unrecognized bookmark file: expected a browser bookmark export (HTML), \
+             a Pocket or Raindrop CSV, or an OPML file"
+        );
+    };
+    Ok((format.name(), format.parse(text)?))
+}
+
+/// The HTML bookmark file every browser (and Pocket, Raindrop, Pinboard)
+/// exports: `<DT><A HREF="..." ADD_DATE="1673786096" TAGS="a,b">Title</A>`.
+pub struct Netscape;
+
+static ANCHOR_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"(?is)<a\s([^>]*)>").unwrap());
+static ATTRIBUTE_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#"(?i)([a-z_:-]+)\s*=\s*"([^"]*)""#).unwrap());
+
+impl Format for Netscape {
+    fn name(&self) -> &'static str {
+        "browser bookmarks"
+    }
+
+    fn detect(&self, text: &str) -> bool {
+        let start = start_of(text);
+        start.contains("netscape-bookmark-file") || start.contains("<dt><a ")
+    }
+
+    fn parse(&self, text: &str) -> Result<Vec<Bookmark>> {
+        Ok(ANCHOR_RE
+            .captures_iter(text)
+            .filter_map(|anchor| {
+                let attributes = attributes(&anchor[1]);
+                Some(Bookmark {
+                    url: attributes.get("href")?.clone(),
+                    saved_at: attributes.get("add_date").and_then(|d| parse_epoch(d)),
+                    tags: split_tags(attributes.get("tags")),
+                })
+            })
+            .collect())
+    }
+}
+
+/// OPML outlines, as written by read-later and feed tools:
+/// `<outline text="..." url="..." created="Sun, 15 Jan 2023 12:34:56 GMT"/>`.
+pub struct Opml;
+
+static OUTLINE_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?is)<outline\s([^>]*?)/?>").unwrap());
+
+impl Format for Opml {
+    fn name(&self) -> &'static str {
+        "OPML"
+    }
+
+    fn detect(&self, text: &str) -> bool {
+        start_of(text).contains("<opml")
+    }
+
+    fn parse(&self, text: &str) -> Result<Vec<Bookmark>> {
+        Ok(OUTLINE_RE
+            .captures_iter(text)
+            .filter_map(|outline| {
+                let attributes = attributes(&outline[1]);
+                let url = ["url", "htmlurl", "xmlurl"]
+                    .iter()
+                    .find_map(|name| attributes.get(*name))?;
+                let saved_at = attributes.get("created").and_then(|created| {
+                    DateTime::parse_from_rfc2822(created)
+                        .map(|time| time.to_utc())
+                        .ok()
+                        .or_else(|| parse_time(created))
+                });
+                Some(Bookmark {
+                    url: url.clone(),
+                    saved_at,
+                    tags: split_tags(attributes.get("category")),
+                })
+            })
+            .collect())
+    }
+}
+
+/// CSV exports with a `url` column, such as Pocket's
+/// (`title,url,time_added,tags,status`) and Raindrop's
+/// (`id,title,note,excerpt,url,folder,tags,created,...`).
+pub struct Csv;
+
+/// Column names holding the saved time, most specific first.
+const CSV_TIME_COLUMNS: &[&str] = &["time_added", "created", "date_added", "added", "date"];
+
+impl Csv {
+    fn header(text: &str) -> Vec<String> {
+        let first_line = text.lines().next().unwrap_or_default();
+        csv_records(first_line)
+            .into_iter()
+            .next()
+            .unwrap_or_default()
+            .iter()
+            .map(|column| column.trim().to_lowercase())
+            .collect()
+    }
+}
+
+impl Format for Csv {
+    fn name(&self) -> &'static str {
+        "CSV"
+    }
+
+    fn detect(&self, text: &str) -> bool {
+        Self::header(text).iter().any(|column| column == "url")
+    }
+
+    fn parse(&self, text: &str) -> Result<Vec<Bookmark>> {
+        let header = Self::header(text);
+        let column = |name: &str| header.iter().position(|c| c == name);
+        let Some(url_column) = column("url") else {
+            bail!("no 'url' column in the CSV header");
+        };
+        let time_column = CSV_TIME_COLUMNS.iter().find_map(|name| column(name));
+        let tags_column = column("tags");
+
+        Ok(csv_records(text)
+            .iter()
+            .skip(1)
+            .filter_map(|row| {
+                let url = row.get(url_column)?.trim();
+                if url.is_empty() {
+                    return None;
+                }
+                let time = time_column.and_then(|c| row.get(c));
+                Some(Bookmark {
+                    url: url.to_string(),
+                    saved_at: time.and_then(|t| parse_epoch(t).or_else(|| parse_time(t))),
+                    tags: split_tags(tags_column.and_then(|c| row.get(c))),
+                })
+            })
+            .collect())
+    }
+}
+
+/// The lowercased beginning of a file, where formats announce themselves.
+fn start_of(text: &str) -> String {
+    let end = text.char_indices().nth(1024).map_or(text.len(), |(i, _)| i);
+    text[..end].to_lowercase()
+}
+
+fn attributes(tag: &str) -> HashMap<String, String> {
+    ATTRIBUTE_RE
+        .captures_iter(tag)
+        .map(|attribute| (attribute[1].to_lowercase(), unescape_html(&attribute[2])))
+        .collect()
+}
+
+/// Unix time in seconds, or in milli- or microseconds as some tools write.
+fn parse_epoch(text: &str) -> Option<DateTime<Utc>> {
+    let mut value: i64 = text.trim().parse().ok()?;
+    while value > 100_000_000_000 {
+        value /= 1000;
+    }
+    DateTime::from_timestamp(value, 0).filter(|time| time.timestamp() > 0)
+}
+
+/// Pocket separates tags with `|`, everything else with commas.
+fn split_tags(tags: Option<&String>) -> Vec<String> {
+    tags.map(|tags| {
+        tags.split([',', '|'])
+            .map(str::trim)
+            .filter(|tag| !tag.is_empty())
+            .map(String::from)
+            .collect()
+    })
+    .unwrap_or_default()
+}
+
+/// The bookmarks of a file sorted into videos to add and links left out.
+#[derive(Debug, Default)]
+pub struct Scan {
+    pub videos: Vec<(VideoLink, Bookmark)>,
+    /// YouTube links that are not videos (channels, playlists, searches),
+    /// with the reason.
+    pub rejected: Vec<(String, String)>,
+    /// Links to other sites, which bookmark files are mostly made of.
+    pub other_sites: usize,
+}
+
+pub fn scan(bookmarks: Vec<Bookmark>) -> Scan {
+    let mut scan = Scan::default();
+    for bookmark in bookmarks {
+        if !is_youtube(&bookmark.url) {
+            scan.other_sites += 1;
+            continue;
+        }
+        match VideoLink::parse(&bookmark.url) {
+            Ok(link) => scan.videos.push((link, bookmark)),
+            Err(e) => scan
+                .rejected
+                .push((bookmark.url, youtube::rejection_reason(&e))),
+        }
+    }
+    scan
+}
+
+fn is_youtube(url: &str) -> bool {
+    Url::parse(url)
+        .ok()
+        .and_then(|url| url.host_str().map(str::to_lowercase))
+        .is_some_and(|host| host == "youtu.be" || host.ends_with("youtube.com"))
+}
+
+/// Writes `videos` as a Netscape bookmark file, in one folder named after
+/// the queue, that browsers and read-later tools can import.
+pub fn to_netscape(queue: &str, videos: &[Video], metadata: &HashMap<String, VideoMeta>) -> String {
+    let mut html = String::from(
+        "<!DOCTYPE NETSCAPE-Bookmark-file-1>\n\
+         <!-- This is an automatically generated file. -->\n\
+         <META HTTP-EQUIV=\"Content-Type\" CONTENT=\"text/html; charset=UTF-8\">\n\
+         <TITLE>Bookmarks</TITLE>\n\
+         <H1>Bookmarks</H1>\n\
+         <DL><p>\n",
+    );
+    let created = videos
+        .iter()
+        .map(|v| v.added_at)
+        .min()
+        .unwrap_or_else(Utc::now);
+    let _ = writeln!(
+        html,
+        "    <DT><H3 ADD_DATE=\"{}\">ytq: {}</H3>\n    <DL><p>",
+        created.timestamp(),
+        escape_html(queue)
+    );
+    for video in videos {
+        let title = metadata
+            .get(&video.id)
+            .filter(|meta| !meta.unavailable && !meta.title.is_empty())
+            .map_or(video.id.as_str(), |meta| meta.title.as_str());
+        let tags = if video.tags.is_empty() {
+            String::new()
+        } else {
+            format!(" TAGS=\"{}\"", escape_html(&video.tags.join(",")))
+        };
+        let _ = writeln!(
+            html,
+            "        <DT><A HREF=\"{}\" ADD_DATE=\"{}\"{tags}>{}</A>",
+            escape_html(&video.watch_url()),
+            video.added_at.timestamp(),
+            escape_html(title)
+        );
+        if let Some(note) = &video.note {
+            let _ = writeln!(html, "        <DD>{}", escape_html(note));
+        }
+    }
+    html.push_str("    </DL><p>\n</DL><p>\n");
+    html
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn time(text: &str) -> DateTime<Utc> {
+        text.parse().unwrap()
+    }
+
+    fn urls(bookmarks: &[Bookmark]) -> Vec<&str> {
+        bookmarks.iter().map(|b| b.url.as_str()).collect()
+    }
+
+    #[test]
+    fn reads_browser_bookmarks() {
+        let html = r#"<!DOCTYPE NETSCAPE-Bookmark-file-1>
+<TITLE>Bookmarks</TITLE>
+<DL><p>
+    <DT><H3 ADD_DATE="1673000000">Videos</H3>
+    <DL><p>
+        <DT><A HREF="https://www.youtube.com/watch?v=dQw4w9WgXcQ&amp;t=42s" ADD_DATE="1673786096" TAGS="music,80s">Never Gonna Give You Up</A>
+        <DT><A HREF="https://example.com/" ADD_DATE="1673786097000">Example</A>
+    </DL><p>
+</DL><p>"#;
+        let (format, bookmarks) = parse(html).unwrap();
+        assert_eq!(format, "browser bookmarks");
+        assert_eq!(
+            urls(&bookmarks),
+            vec![
+                "https://www.youtube.com/watch?v=dQw4w9WgXcQ&t=42s",
+                "https://example.com/"
+            ]
+        );
+        assert_eq!(bookmarks[0].saved_at, Some(time("2023-01-15T12:34:56Z")));
+        assert_eq!(bookmarks[0].tags, vec!["music", "80s"]);
+        assert_eq!(bookmarks[1].saved_at, Some(time("2023-01-15T12:34:57Z")));
+    }
+
+    #[test]
+    fn reads_pocket_and_raindrop_csvs() {
+        let pocket = "title,url,time_added,tags,status\n\
+                      \"Rick, Astley\",https://youtu.be/dQw4w9WgXcQ,1673786096,music|80s,unread\n";
+        let (format, bookmarks) = parse(pocket).unwrap();
+        assert_eq!(format, "CSV");
+        assert_eq!(urls(&bookmarks), vec!["https://youtu.be/dQw4w9WgXcQ"]);
+        assert_eq!(bookmarks[0].saved_at, Some(time("2023-01-15T12:34:56Z")));
+        assert_eq!(bookmarks[0].tags, vec!["music", "80s"]);
+
+        let raindrop = "id,title,note,excerpt,url,folder,tags,created,cover,highlights,favorite\n\
+                        1,Me at the zoo,,,https://www.youtube.com/watch?v=jNQXAC9IVRw,Unsorted,\"zoo, first\",2023-01-15T12:34:56.000Z,,,false\n";
+        let (_, bookmarks) = parse(raindrop).unwrap();
+        assert_eq!(
+            urls(&bookmarks),
+            vec!["https://www.youtube.com/watch?v=jNQXAC9IVRw"]
+        );
+        assert_eq!(bookmarks[0].saved_at, Some(time("2023-01-15T12:34:56Z")));
+        assert_eq!(bookmarks[0].tags, vec!["zoo", "first"]);
+    }
+
+    #[test]
+    fn reads_opml() {
+        let opml = r#"<?xml version="1.0" encoding="UTF-8"?>
+<opml version="2.0"><body>
+  <outline text="Saved">
+    <outline text="Zoo" type="link" url="https://www.youtube.com/watch?v=jNQXAC9IVRw" created="Sun, 15 Jan 2023 12:34:56 GMT"/>
+    <outline text="Feed" type="rss" xmlUrl="https://www.youtube.com/feeds/videos.xml?channel_id=UC4QobU6STFB0P71PMvOGN5A"/>
+  </outline>
+</body></opml>"#;
+        let (format, bookmarks) = parse(opml).unwrap();
+        assert_eq!(format, "OPML");
+        assert_eq!(bookmarks.len(), 2);
+        assert_eq!(bookmarks[0].saved_at, Some(time("2023-01-15T12:34:56Z")));
+        assert_eq!(bookmarks[1].saved_at, None);
+
+        assert!(parse("just some notes\n").is_err());
+    }
+
+    #[test]
+    fn scan_rejects_youtube_links_that_are_not_videos() {
+        let bookmark = |url: &str| Bookmark {
+            url: url.to_string(),
+            saved_at: None,
+            tags: vec![],
+        };
+        let scan = scan(vec![
+            bookmark("https://www.youtube.com/watch?v=dQw4w9WgXcQ&t=42s"),
+            bookmark("https://www.youtube.com/@RickAstleyYT"),
+            bookmark("https://www.youtube.com/playlist?list=PLFgquLnL59alCl_2TQvOiD5Vgm1hCaGSI"),
+            bookmark("https://example.com/watch?v=dQw4w9WgXcQ"),
+        ]);
+
+        assert_eq!(scan.videos.len(), 1);
+        assert_eq!(scan.videos[0].0.start_seconds, Some(42));
+        let reasons: Vec<&str> = scan.rejected.iter().map(|(_, r)| r.as_str()).collect();
+        assert_eq!(
+            reasons,
+            vec![
+                "Channel URLs are not supported",
+                "Playlist URLs are not supported"
+            ]
+        );
+        assert_eq!(scan.other_sites, 1);
+    }
+
+    #[test]
+    fn exported_queue_reads_back() {
+        let mut video = Video::new(
+            "dQw4w9WgXcQ".to_string(),
+            youtube::build_canonical_url("dQw4w9WgXcQ"),
+            time("2023-01-15T12:34:56Z"),
+        );
+        video.tags = vec!["music".to_string()];
+        video.note = Some("<3 & more".to_string());
+        video.start_seconds = Some(42);
+
+        let html = to_netscape("default", &[video], &HashMap::new());
+        assert!(html.contains("<DD>&lt;3 &amp; more"));
+        let (_, bookmarks) = parse(&html).unwrap();
+        assert_eq!(
+            bookmarks,
+            vec![Bookmark {
+                url: "https://www.youtube.com/watch?v=dQw4w9WgXcQ&t=42s".to_string(),
+                saved_at: Some(time("2023-01-15T12:34:56Z")),
+                tags: vec!["music".to_string()],
+            }]
+        );
+    }
+}
@@ -0,0 +1,220 @@
+use std::cmp::Reverse;
+use std::collections::HashMap;
+
+use crate::models::{Priority, Video, VideoMeta};
+
+/// A queue entry with a known watch time.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TimedEntry {
+    /// Index into the queue.
+    pub index: usize,
+    /// Time left to watch: the duration minus the start offset.
+    pub seconds: u64,
+    pub priority: Priority,
+}
+
+/// Queue entries that a time budget can use, in pick order, and counts of
+/// the ones it has to leave out.
+#[derive(Debug, Default)]
+pub struct TimedQueue {
+    pub entries: Vec<TimedEntry>,
+    /// Videos without fetched metadata or with a zero duration (live streams).
+    pub unknown_duration: usize,
+    pub unavailable: usize,
+}
+
+impl TimedQueue {
+    /// Looks up the watch time of the videos at `order` (queue indices in
+    /// pick order, as from `Mode::selection_order`).
+    pub fn new(videos: &[Video], order: &[usize], metadata: &HashMap<String, VideoMeta>) -> Self {
+        let mut timed = Self::default();
+        for &index in order {
+            let video = &videos[index];
+            match metadata.get(&video.id) {
+                Some(meta) if meta.unavailable => timed.unavailable += 1,
+                Some(meta) if meta.duration_seconds > 0 => timed.entries.push(TimedEntry {
+                    index,
+                    seconds: meta
+                        .duration_seconds
+                        .saturating_sub(video.start_seconds.unwrap_or(0)),
+                    priority: video.priority,
+                }),
+                _ => timed.unknown_duration += 1,
+            }
+        }
+        timed
+    }
+
+    /// Explains which videos were left out, if any.
+    pub fn skipped_note(&self) -> Option<String> {
+        let plural = |n: usize| if n == 1 { "video" } else { "videos" };
+        let mut parts = Vec::new();
+        if self.unknown_duration > 0 {
+            parts.push(format!(
+                "{} {} without a known duration (run `ytq fetch`)",
+                self.unknown_duration,
+                plural(self.unknown_duration)
+            ));
+        }
+        if self.unavailable > 0 {
+            parts.push(format!(
+                "{} unavailable {}",
+                self.unavailable,
+                plural(self.unavailable)
+            ));
+        }
+        (!parts.is_empty()).then(|| format!("Skipped {}.", parts.join(" and ")))
+    }
+
+    /// The single entry to watch within `budget` seconds: highest priority
+    /// first, then the one filling the most of the budget, then pick order.
+    pub fn best_fit(&self, budget: u64) -> Option<TimedEntry> {
+        self.entries
+            .iter()
+            .enumerate()
+            .filter(|(_, e)| e.seconds <= budget)
+            .min_by_key(|(pos, e)| (Reverse(e.priority), Reverse(e.seconds), *pos))
+            .map(|(_, e)| *e)
+    }
+
+    /// Chooses entries whose watch times add up to as much of `budget` as
+    /// possible (0/1 knapsack over seconds). Among equally full plans, the
+    /// one reaching least far down the pick order wins. Returns the chosen
+    /// entries in pick order.
+    pub fn plan(&self, budget: u64) -> Vec<TimedEntry> {
+        let total: u64 = self.entries.iter().map(|e| e.seconds).sum();
+        if total <= budget {
+            return self.entries.clone();
+        }
+
+        // reached[t] = entry (by position in pick order) that first made a
+        // total of t seconds possible. Walking t downwards means each entry
+        // is used at most once, and since a slot is never overwritten, the
+        // chain behind it only uses entries earlier in pick order.
+        let budget = budget as usize;
+        let mut reached: Vec<Option<usize>> = vec![None; budget + 1];
+        let mut best = 0;
+        for (pos, entry) in self.entries.iter().enumerate() {
+            let seconds = entry.seconds as usize;
+            if seconds > budget {
+                continue;
+            }
+            for t in (seconds..=budget).rev() {
+                let reachable = t == seconds || (t > seconds && reached[t - seconds].is_some());
+                if reached[t].is_none() && reachable {
+                    reached[t] = Some(pos);
+                    best = best.max(t);
+                }
+            }
+        }
+
+        let mut chosen = Vec::new();
+        let mut t = best;
+        while t > 0 {
+            let pos = reached[t].expect("every step of the chain was reached");
+            chosen.push(pos);
+            t -= self.entries[pos].seconds as usize;
+        }
+        chosen.sort_unstable();
+        chosen.into_iter().map(|pos| self.entries[pos]).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::meta;
+    use chrono::Utc;
+
+    fn entry(index: usize, seconds: u64) -> TimedEntry {
+        TimedEntry {
+            index,
+            seconds,
+            priority: Priority::Normal,
+        }
+    }
+
+    fn timed(seconds: &[u64]) -> TimedQueue {
+        TimedQueue {
+            entries: seconds
+                .iter()
+                .enumerate()
+                .map(|(i, &s)| entry(i, s))
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    fn indices(entries: &[TimedEntry]) -> Vec<usize> {
+        entries.iter().map(|e| e.index).collect()
+    }
+
+    #[test]
+    fn new_skips_unknown_and_unavailable_and_subtracts_start() {
+        let mut videos: Vec<Video> = ["a", "b", "c", "d"]
+            .iter()
+            .map(|id| Video::new(id.to_string(), String::new(), Utc::now()))
+            .collect();
+        videos[0].start_seconds = Some(60);
+        let metadata = HashMap::from([
+            (
+                "a".to_string(),
+                VideoMeta {
+                    duration_seconds: 600,
+                    ..meta("a")
+                },
+            ),
+            ("b".to_string(), meta("b")),
+            (
+                "d".to_string(),
+                VideoMeta {
+                    unavailable: true,
+                    ..meta("d")
+                },
+            ),
+        ]);
+
+        let timed = TimedQueue::new(&videos, &[0, 1, 2, 3], &metadata);
+        assert_eq!(timed.entries, vec![entry(0, 540)]);
+        assert_eq!(timed.unknown_duration, 2);
+        assert_eq!(timed.unavailable, 1);
+        assert_eq!(
+            timed.skipped_note().unwrap(),
+            "Skipped 2 videos without a known duration (run `ytq fetch`) and 1 unavailable video."
+        );
+        assert!(TimedQueue::default().skipped_note().is_none());
+    }
+
+    #[test]
+    fn best_fit_prefers_priority_then_length_then_order() {
+        let mut queue = timed(&[300, 900, 900, 1500]);
+        assert_eq!(queue.best_fit(1200).unwrap().index, 1);
+        assert!(queue.best_fit(200).is_none());
+
+        queue.entries[0].priority = Priority::High;
+        assert_eq!(queue.best_fit(1200).unwrap().index, 0);
+    }
+
+    #[test]
+    fn plan_fills_budget_exactly_when_possible() {
+        // Greedy in order would take 50 + 30 and stop at 80
+        let queue = timed(&[50, 30, 45, 55]);
+        let plan = queue.plan(100);
+        assert_eq!(plan.iter().map(|e| e.seconds).sum::<u64>(), 100);
+        assert_eq!(indices(&plan), vec![2, 3]);
+    }
+
+    #[test]
+    fn plan_reaches_least_far_down_the_queue_on_ties() {
+        // 10 + 20 and 30 both fill the budget; 30 comes before the 20
+        let queue = timed(&[10, 30, 20]);
+        assert_eq!(indices(&queue.plan(30)), vec![1]);
+    }
+
+    #[test]
+    fn plan_takes_everything_that_fits_and_nothing_too_long() {
+        let queue = timed(&[10, 20]);
+        assert_eq!(indices(&queue.plan(100)), vec![0, 1]);
+        assert!(timed(&[500]).plan(100).is_empty());
+    }
+}
@@ -1,134 +1,760 @@
 use std::collections::HashMap;
-
-use crate::models::{Action, Event, Mode, Video, VideoMeta};
+use std::fs;
+use std::io::{self, Read};
+use std::path::Path;
+
+use crate::filter::ListFilter;
+use crate::journal::JournalEntry;
+use crate::models::{
+    Action, Config, DEFAULT_QUEUE, Event, Mode, Priority, QueueConfig, SelectionContext,
+    StorageKind, Subscription, Video, VideoMeta,
+};
+use crate::output::{self, Format, VideoRow};
 use crate::stats::DateRange;
-use crate::{paths, stats, store, youtube, youtube_api};
+use crate::store::{Notice, Schema};
+use crate::{
+    archive, bookmarks, budget, doctor, feeds, journal, paths, player, search, stats, store,
+    takeout, youtube, youtube_api,
+};
 
-use anyhow::{Result, bail};
+use anyhow::{Context, Result, bail};
 use chrono::{DateTime, Datelike, Local, NaiveDate, Utc};
 use colored::Colorize;
 use rand::RngExt;
 
-pub fn add(input: &str) -> Result<()> {
+/// How `add` treats a video URL that also names a playlist (`list=`).
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum PlaylistScope {
+    /// Add the video and mention the playlist.
+    #[default]
+    Unspecified,
+    /// Add only the video.
+    VideoOnly,
+    /// Add the whole playlist.
+    Whole,
+    /// Add the playlist starting at the video.
+    FromHere,
+}
+
+pub fn add(
+    input: &str,
+    queue: Option<&str>,
+    scope: PlaylistScope,
+    tags: &[String],
+    note: Option<&str>,
+    priority: Option<&str>,
+) -> Result<()> {
     let paths = paths::AppPaths::init()?;
+    let queue = queue.unwrap_or(DEFAULT_QUEUE);
+    let priority = priority.map(parse_priority).transpose()?;
+    let annotations = NewVideoAnnotations {
+        tags,
+        note,
+        priority: priority.unwrap_or_default(),
+    };
+
+    // Playlist pages are expanded into their videos (online only)
+    if let Some(playlist_id) = youtube::extract_playlist_id(input) {
+        if matches!(scope, PlaylistScope::VideoOnly | PlaylistScope::FromHere) {
+            bail!(
+                "'{input}' is a playlist page, not a video.\n\
+                 --video-only and --from-here need a video URL with a list= parameter."
+            );
+        }
+        return add_playlist(&paths, queue, &playlist_id, None, &annotations);
+    }
 
-    // Normalize input before acquiring lock
-    let id = youtube::extract_video_id(input)?;
-    let url = youtube::build_canonical_url(&id);
+    // Normalize input before acquiring lock, keeping offsets such as &t=1h12m
+    let link = youtube::VideoLink::parse(input)?;
+    let id = link.id.clone();
+    let start_seconds = link.start_seconds;
+    let context = youtube::extract_playlist_context(input);
 
-    let added = store::with_queue(&paths, |queue| {
-        // Deduplicate
-        if queue.iter().any(|v| v.id == id) {
-            return Ok(false);
+    match (scope, &context) {
+        (PlaylistScope::Whole | PlaylistScope::FromHere, None) => {
+            bail!("'{input}' does not reference a playlist (no list= parameter)")
+        }
+        (PlaylistScope::Whole, Some(ctx)) => {
+            return add_playlist(&paths, queue, &ctx.playlist_id, None, &annotations);
         }
+        (PlaylistScope::FromHere, Some(ctx)) => {
+            let start = PlaylistStart {
+                video_id: &id,
+                index: ctx.index,
+            };
+            return add_playlist(&paths, queue, &ctx.playlist_id, Some(start), &annotations);
+        }
+        _ => {}
+    }
+
+    let outcome = enqueue(&paths, queue, &[link], &annotations)?;
 
-        let video = Video {
-            id: id.clone(),
-            url: url.clone(),
-            added_at: Utc::now(),
+    if outcome.added.is_empty() {
+        println!("{} {input}", "Video already in queue:".yellow());
+        if !tags.is_empty() || note.is_some() || priority.is_some() {
+            println!(
+                "  Use {} to change its tags, note or priority.",
+                "`ytq edit`".bold()
+            );
+        }
+        if start_seconds.is_some() {
+            println!("  Use {} to change where it starts.", "`ytq seek`".bold());
+        }
+    } else if let Some(start) = start_seconds {
+        println!(
+            "{} {id} (starts at {})",
+            "Added:".green(),
+            youtube_api::format_duration(start)
+        );
+    } else {
+        println!("{} {id}", "Added:".green());
+    }
+
+    if let (PlaylistScope::Unspecified, Some(ctx)) = (scope, &context) {
+        println!(
+            "  This video is part of playlist {}. Use {} to add all of it, or {} to add the rest.",
+            ctx.playlist_id,
+            "--playlist".bold(),
+            "--from-here".bold()
+        );
+    }
+
+    if outcome.added.is_empty() {
+        return Ok(());
+    }
+
+    // Hint about fetching metadata when online features are enabled
+    let cfg = store::load_config(&paths.config_file)?;
+    if !cfg.offline {
+        println!("  Run {} to get video metadata.", "`ytq fetch`".bold());
+    }
+
+    Ok(())
+}
+
+/// Adds every video link found in a file, or in stdin when `path` is `None`.
+/// Blank lines and lines starting with `#` are ignored; a line may contain
+/// several links amid other text.
+pub fn add_bulk(
+    path: Option<&Path>,
+    queue: Option<&str>,
+    tags: &[String],
+    note: Option<&str>,
+    priority: Option<&str>,
+) -> Result<()> {
+    let paths = paths::AppPaths::init()?;
+    let queue = queue.unwrap_or(DEFAULT_QUEUE);
+    let priority = priority.map(parse_priority).transpose()?;
+    let annotations = NewVideoAnnotations {
+        tags,
+        note,
+        priority: priority.unwrap_or_default(),
+    };
+
+    let (source, text) = match path {
+        Some(path) => (
+            path.display().to_string(),
+            fs::read_to_string(path)
+                .with_context(|| format!("failed to read {}", path.display()))?,
+        ),
+        None => {
+            let mut text = String::new();
+            io::stdin()
+                .read_to_string(&mut text)
+                .context("failed to read stdin")?;
+            ("stdin".to_string(), text)
+        }
+    };
+
+    // Parse every line before acquiring the lock
+    let mut links: Vec<(usize, youtube::VideoLink)> = Vec::new();
+    let mut invalid: Vec<(usize, String)> = Vec::new();
+    for (line_no, line) in text.lines().enumerate().map(|(i, l)| (i + 1, l.trim())) {
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let mut found = false;
+        let mut first_error = None;
+        for result in youtube::find_video_links(line) {
+            match result {
+                Ok(link) => {
+                    links.push((line_no, link));
+                    found = true;
+                }
+                Err(e) => {
+                    first_error.get_or_insert(e);
+                }
+            }
+        }
+
+        // A line only counts as invalid if it yielded no video at all
+        if !found {
+            let reason = first_error.map_or_else(
+                || "no YouTube video link found".to_string(),
+                |e| format!("{e:#}"),
+            );
+            invalid.push((line_no, reason));
+        }
+    }
+
+    if links.is_empty() && invalid.is_empty() {
+        println!("{}", format!("No links found in {source}.").yellow());
+        return Ok(());
+    }
+
+    let batch: Vec<youtube::VideoLink> = links.iter().map(|(_, link)| link.clone()).collect();
+    let added = if batch.is_empty() {
+        Vec::new()
+    } else {
+        enqueue(&paths, queue, &batch, &annotations)?.added
+    };
+
+    // The first line mentioning each added video gets the credit
+    let mut pending: std::collections::HashSet<&str> = added.iter().map(String::as_str).collect();
+    let duplicates: Vec<&(usize, youtube::VideoLink)> = links
+        .iter()
+        .filter(|(_, link)| !pending.remove(link.id.as_str()))
+        .collect();
+
+    println!(
+        "{} {} video(s) from {source}.",
+        "Added:".green(),
+        added.len()
+    );
+    if !duplicates.is_empty() {
+        println!(
+            "  {}",
+            format!("Already in queue ({}):", duplicates.len()).yellow()
+        );
+        for (line_no, link) in duplicates {
+            println!("    line {line_no}: {}", link.id);
+        }
+    }
+    if !invalid.is_empty() {
+        println!("  {}", format!("Invalid ({}):", invalid.len()).red());
+        for (line_no, reason) in &invalid {
+            println!("    line {line_no}: {reason}");
+        }
+    }
+
+    // Hint about fetching metadata when online features are enabled
+    let cfg = store::load_config(&paths.config_file)?;
+    if !added.is_empty() && !cfg.offline {
+        println!("  Run {} to get video metadata.", "`ytq fetch`".bold());
+    }
+
+    Ok(())
+}
+
+/// User annotations applied to every video created by an `add`.
+struct NewVideoAnnotations<'a> {
+    tags: &'a [String],
+    note: Option<&'a str>,
+    priority: Priority,
+}
+
+/// Result of adding a batch of video IDs to a queue.
+struct EnqueueOutcome {
+    added: Vec<String>,
+    duplicates: Vec<String>,
+}
+
+/// Adds videos to a queue in a single locked transaction, skipping IDs
+/// already queued (or repeated in `links`), and logs a `Queued` event for each
+/// added video.
+fn enqueue(
+    paths: &paths::AppPaths,
+    queue: &str,
+    links: &[youtube::VideoLink],
+    annotations: &NewVideoAnnotations,
+) -> Result<EnqueueOutcome> {
+    let outcome = store::with_queue(paths, queue, |videos| {
+        let mut outcome = EnqueueOutcome {
+            added: Vec::new(),
+            duplicates: Vec::new(),
         };
 
-        queue.push(video);
-        Ok(true)
+        for youtube::VideoLink { id, start_seconds } in links {
+            // Deduplicate
+            if videos.iter().any(|v| v.id == *id) {
+                outcome.duplicates.push(id.clone());
+                continue;
+            }
+
+            let url = youtube::build_canonical_url(id);
+            let mut video = Video::new(id.clone(), url, Utc::now());
+            video.add_tags(annotations.tags);
+            video.note = annotations.note.map(String::from);
+            video.priority = annotations.priority;
+            video.start_seconds = *start_seconds;
+
+            videos.push(video);
+            outcome.added.push(id.clone());
+        }
+
+        Ok(outcome)
     })?;
 
-    if added {
+    for id in &outcome.added {
         let event = Event {
             timestamp: Utc::now(),
             action: Action::Queued,
             video_id: id.clone(),
             time_in_queue_sec: None,
+            queue: Some(queue.to_string()),
         };
-        store::log_event(&paths.history_dir, &event)?;
+        store::log_event(paths, &event)?;
+    }
 
-        println!("{} {id}", "Added:".green());
+    Ok(outcome)
+}
 
-        // Hint about fetching metadata when online features are enabled
-        let cfg = store::load_config(&paths.config_file);
-        if !cfg.offline {
-            println!("  Run {} to get video metadata.", "`ytq fetch`".bold());
-        }
-    } else {
-        println!("{} {input}", "Video already in queue:".yellow());
+/// Where `add --from-here` starts within a playlist.
+struct PlaylistStart<'a> {
+    video_id: &'a str,
+    /// 1-based `index=` from the URL, if present.
+    index: Option<usize>,
+}
+
+/// Adds every video of a playlist via the YouTube Data API, optionally only
+/// those from `start` onwards.
+fn add_playlist(
+    paths: &paths::AppPaths,
+    queue: &str,
+    playlist_id: &str,
+    start: Option<PlaylistStart>,
+    annotations: &NewVideoAnnotations,
+) -> Result<()> {
+    let cfg = store::load_config(&paths.config_file)?;
+
+    if cfg.offline {
+        bail!(
+            "importing a playlist requires the YouTube Data API, but online features are disabled.\n\
+             To import playlist '{playlist_id}':\n  \
+             1. ytq config offline false\n  \
+             2. ytq config youtube_api_key <key> (or set YOUTUBE_DATA_API_KEY)\n\
+             Or add the playlist's videos individually."
+        );
+    }
+    let api_key = require_api_key(&cfg)?;
+
+    println!("Fetching playlist {playlist_id}...");
+    let mut ids = youtube_api::fetch_playlist_video_ids(playlist_id, &api_key)?;
+
+    if let Some(start) = &start {
+        // Trust index= only if it still points at the video; playlists change
+        let position = start
+            .index
+            .map(|i| i - 1)
+            .filter(|&i| ids.get(i).is_some_and(|id| id == start.video_id))
+            .or_else(|| ids.iter().position(|id| id == start.video_id))
+            .ok_or_else(|| {
+                anyhow::anyhow!("video {} is not in playlist {playlist_id}", start.video_id)
+            })?;
+        ids.drain(..position);
+    }
+
+    // Playlists may contain the same video more than once
+    let mut seen = std::collections::HashSet::new();
+    ids.retain(|id| seen.insert(id.clone()));
+
+    if ids.is_empty() {
+        println!("{}", "Playlist is empty.".yellow());
+        return Ok(());
+    }
+
+    let links: Vec<youtube::VideoLink> = ids.into_iter().map(youtube::VideoLink::from_id).collect();
+    let outcome = enqueue(paths, queue, &links, annotations)?;
+
+    let from = match &start {
+        Some(start) => format!(" starting at {}", start.video_id),
+        None => String::new(),
+    };
+    println!(
+        "{} {} video(s) from playlist {playlist_id}{from}.",
+        "Added:".green(),
+        outcome.added.len()
+    );
+    if !outcome.duplicates.is_empty() {
+        println!("  {} already in queue (skipped).", outcome.duplicates.len());
+    }
+    if !outcome.added.is_empty() {
+        println!("  Run {} to get video metadata.", "`ytq fetch`".bold());
     }
 
     Ok(())
 }
 
-pub fn next(target: Option<&str>) -> Result<()> {
+/// Resolves the YouTube Data API key, with setup instructions when missing.
+fn require_api_key(cfg: &Config) -> Result<String> {
+    cfg.effective_api_key().ok_or_else(|| {
+        anyhow::anyhow!(
+            "no YouTube Data API key configured.\n\
+             Set it via: ytq config youtube_api_key <key>\n\
+             Or set the YOUTUBE_DATA_API_KEY environment variable."
+        )
+    })
+}
+
+/// What `--print` writes for the chosen video.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PrintField {
+    Url,
+    Id,
+}
+
+/// How `next` and `random` deliver the chosen video.
+pub struct PlayOptions<'a> {
+    /// `--player` template overriding the configured player.
+    pub player: Option<&'a str>,
+    /// Print the URL or ID instead of opening the video.
+    pub print: Option<PrintField>,
+    /// Print the video and its metadata as JSON instead of opening it.
+    pub json: bool,
+    /// Leave the queue and history untouched.
+    pub dry_run: bool,
+}
+
+impl PlayOptions<'_> {
+    /// Whether output is meant for other programs rather than people.
+    fn is_scripted(&self) -> bool {
+        self.print.is_some() || self.json
+    }
+}
+
+pub fn parse_print_field(value: &str) -> Result<PrintField> {
+    match value.to_lowercase().as_str() {
+        "url" => Ok(PrintField::Url),
+        "id" => Ok(PrintField::Id),
+        _ => bail!("invalid --print value '{value}': use 'url' or 'id'"),
+    }
+}
+
+/// Watches the next video, a specific `target`, or with `fits` the best
+/// video that can be watched within that many seconds.
+pub fn next(
+    target: Option<&str>,
+    fits: Option<u64>,
+    mode: Option<Mode>,
+    queue: Option<&str>,
+    options: &PlayOptions,
+) -> Result<()> {
     let paths = paths::AppPaths::init()?;
-    let cfg = store::load_config(&paths.config_file);
+    let cfg = store::load_config(&paths.config_file)?;
+    let queue = queue.unwrap_or(DEFAULT_QUEUE);
+    let mode = mode.unwrap_or_else(|| cfg.mode_for(queue));
 
     // If a specific target is provided, parse it before acquiring the lock
     let target_id = target.map(youtube::extract_video_id).transpose()?;
+    options.player.map(parse_player).transpose()?;
 
-    // Remove the video from queue while holding the lock
-    let video = store::with_queue(&paths, |queue| {
-        if queue.is_empty() {
-            return Ok(None);
-        }
+    // Durations come from cached metadata, which the queue lock doesn't cover
+    let context = selection_context(&paths, queue, &mode, fits.is_some())?;
 
-        let video = match &target_id {
+    play(&paths, &cfg, queue, options, |videos| {
+        let order = mode.selection_order(videos, &context);
+        match (&target_id, fits) {
             // Specific video requested - find by ID
-            Some(id) => {
-                let idx = queue
-                    .iter()
-                    .position(|v| v.id == *id)
-                    .ok_or_else(|| anyhow::anyhow!("video with ID '{id}' not found in queue"))?;
-                queue.remove(idx)
+            (Some(id), _) => find_index(videos, id),
+            (None, Some(budget)) => {
+                let timed = budget::TimedQueue::new(videos, &order, &context.metadata);
+                let Some(entry) = timed.best_fit(budget) else {
+                    bail!(
+                        "nothing in the queue fits in {}{}",
+                        youtube_api::format_duration(budget),
+                        timed
+                            .skipped_note()
+                            .map(|note| format!("\n{note}"))
+                            .unwrap_or_default()
+                    );
+                };
+                if let Some(note) = timed.skipped_note() {
+                    // Keep stdout clean for scripts
+                    if options.is_scripted() {
+                        eprintln!("{note}");
+                    } else {
+                        println!("{}", note.dimmed());
+                    }
+                }
+                Ok(entry.index)
             }
-            // No target - use mode-based selection
-            None => match cfg.mode {
-                Mode::Queue => queue.remove(0),
-                Mode::Stack => queue.pop().expect("queue verified non-empty"),
-            },
-        };
+            // No target - highest priority first, then mode order
+            (None, None) => Ok(order[0]),
+        }
+    })
+}
+
+/// Loads what `mode` needs to order the queue. `with_metadata` forces
+/// loading metadata for callers that need durations anyway.
+fn selection_context(
+    paths: &paths::AppPaths,
+    queue: &str,
+    mode: &Mode,
+    with_metadata: bool,
+) -> Result<SelectionContext> {
+    if !with_metadata && !mode.needs_metadata() {
+        return Ok(SelectionContext::default());
+    }
+    let metadata = store::load_metadata(paths)?;
+
+    let last_channel = if *mode == Mode::RoundRobin {
+        stats::apply_undo(store::stream_history(paths)?)
+            .iter()
+            .rev()
+            .find(|e| matches!(e.action, Action::Watched) && e.queue_name() == queue)
+            .and_then(|e| metadata.get(&e.video_id))
+            .filter(|m| !m.unavailable)
+            .map(|m| m.channel_id.clone())
+    } else {
+        None
+    };
+
+    Ok(SelectionContext {
+        metadata,
+        last_channel,
+    })
+}
+
+/// Proposes videos to watch back to back within `budget` seconds, without
+/// changing the queue.
+pub fn plan(budget: u64, mode: Option<Mode>, queue: Option<&str>, format: Format) -> Result<()> {
+    let paths = paths::AppPaths::init()?;
+    let cfg = store::load_config(&paths.config_file)?;
+    let queue = queue.unwrap_or(DEFAULT_QUEUE);
+    let mode = mode.unwrap_or_else(|| cfg.mode_for(queue));
+
+    let videos = store::with_queue_read(&paths, queue, |videos| videos.to_vec())?;
+    let context = selection_context(&paths, queue, &mode, true)?;
+    let order = mode.selection_order(&videos, &context);
+    let metadata = context.metadata;
+    let timed = budget::TimedQueue::new(&videos, &order, &metadata);
+    let plan = timed.plan(budget);
+
+    if format.is_machine() {
+        if let Some(note) = timed.skipped_note() {
+            eprintln!("{note}");
+        }
+        let order: Vec<usize> = plan.iter().map(|e| e.index).collect();
+        return print_video_rows(&paths, queue, &videos, &order, format);
+    }
+
+    let budget_label = youtube_api::format_duration(budget);
+    if videos.is_empty() {
+        println!("{}", "Queue is empty.".yellow());
+    } else if plan.is_empty() {
+        println!(
+            "{}",
+            format!("Nothing in the queue fits in {budget_label}.").yellow()
+        );
+    } else {
+        println!("Plan for {budget_label} ({mode:?} mode):");
+
+        let title_width = plan
+            .iter()
+            .map(|e| metadata[&videos[e.index].id].title.chars().count())
+            .max()
+            .unwrap_or(5)
+            .clamp(5, 50);
+        println!(
+            "  {:<4} {:<13} {:<title_width$}  {:>8}  {:>8}",
+            "#", "ID", "Title", "Duration", "Total"
+        );
+
+        let mut elapsed = 0;
+        for entry in &plan {
+            let video = &videos[entry.index];
+            elapsed += entry.seconds;
+            println!(
+                "  {:<4} {:<13} {:<title_width$}  {:>8}  {:>8}",
+                entry.index + 1,
+                video.id,
+                truncate(&metadata[&video.id].title, title_width),
+                youtube_api::format_duration(entry.seconds),
+                youtube_api::format_duration(elapsed),
+            );
+        }
+
+        println!(
+            "{} videos, {} ({} to spare).",
+            plan.len(),
+            youtube_api::format_duration(elapsed),
+            youtube_api::format_duration(budget - elapsed)
+        );
+    }
+
+    if let Some(note) = timed.skipped_note() {
+        println!("{}", note.dimmed());
+    }
+    Ok(())
+}
 
+/// Removes the video chosen by `pick` from the queue, or with `dry_run` just
+/// returns a copy of it. `pick` is only called for a non-empty queue.
+/// `deliver` runs before the queue is saved, so if it fails the video stays.
+fn take_video<F, D>(
+    paths: &paths::AppPaths,
+    queue: &str,
+    dry_run: bool,
+    pick: F,
+    deliver: D,
+) -> Result<Option<Video>>
+where
+    F: FnOnce(&[Video]) -> Result<usize>,
+    D: FnOnce(&Video) -> Result<()>,
+{
+    if dry_run {
+        return store::with_queue_read(paths, queue, |videos| {
+            if videos.is_empty() {
+                return Ok(None);
+            }
+            Ok(Some(videos[pick(videos)?].clone()))
+        })?;
+    }
+
+    // Remove the video from queue while holding the lock
+    store::with_queue(paths, queue, |videos| {
+        if videos.is_empty() {
+            return Ok(None);
+        }
+        let idx = pick(videos)?;
+        let video = videos.remove(idx);
+        deliver(&video)?;
         Ok(Some(video))
+    })
+}
+
+/// Takes the video chosen by `pick` off the queue, logs the watch and opens
+/// or prints it, as `options` asks. The player is started before the queue
+/// is saved, so a player that fails to start costs nothing.
+fn play<F>(
+    paths: &paths::AppPaths,
+    cfg: &Config,
+    queue: &str,
+    options: &PlayOptions,
+    pick: F,
+) -> Result<()>
+where
+    F: FnOnce(&[Video]) -> Result<usize>,
+{
+    let video = take_video(paths, queue, options.dry_run, pick, |video| {
+        if options.is_scripted() {
+            return Ok(());
+        }
+        open_video(paths, cfg, queue, video, options.player)
     })?;
 
     let Some(video) = video else {
+        // Scripts need a failing exit status rather than a message on stdout
+        if options.is_scripted() {
+            bail!("queue is empty");
+        }
         println!("{}", "Queue is empty.".yellow());
         return Ok(());
     };
 
-    // Log event and open video (outside the lock)
-    let duration = Utc::now().signed_duration_since(video.added_at);
-    let sec_in_queue = duration.num_seconds();
-
-    let event = Event {
-        timestamp: Utc::now(),
-        action: Action::Watched,
-        video_id: video.id.clone(),
-        time_in_queue_sec: Some(sec_in_queue),
-    };
+    // Log event (outside the lock), now that the video is on its way
+    if !options.dry_run {
+        let duration = Utc::now().signed_duration_since(video.added_at);
+        let event = Event {
+            timestamp: Utc::now(),
+            action: Action::Watched,
+            video_id: video.id.clone(),
+            time_in_queue_sec: Some(duration.num_seconds()),
+            queue: Some(queue.to_string()),
+        };
+        store::log_event(paths, &event)?;
+    }
 
-    store::log_event(&paths.history_dir, &event)?;
+    if options.json {
+        let meta = store::load_metadata(paths)?.remove(&video.id);
+        let output = serde_json::json!({
+            "queue": queue,
+            "url": video.watch_url(),
+            "video": video,
+            "meta": meta,
+        });
+        println!("{}", serde_json::to_string_pretty(&output)?);
+        return Ok(());
+    }
 
-    println!("{} {}", "Opening:".blue(), video.url);
-    open::that(&video.url)?;
+    match options.print {
+        Some(PrintField::Url) => println!("{}", video.watch_url()),
+        Some(PrintField::Id) => println!("{}", video.id),
+        None if options.dry_run => {
+            println!("{} {}", "Would open:".blue(), video.watch_url());
+        }
+        // Already opened by take_video
+        None => {}
+    }
 
     Ok(())
 }
 
-pub fn remove(target: &str) -> Result<()> {
+/// Opens a video with the `--player` template, the configured player for its
+/// category or queue, or the system default handler.
+fn open_video(
+    paths: &paths::AppPaths,
+    cfg: &Config,
+    queue: &str,
+    video: &Video,
+    player_flag: Option<&str>,
+) -> Result<()> {
+    let url = video.watch_url();
+
+    let template = match player_flag {
+        Some(template) => Some(template),
+        None => {
+            // Category overrides need the video's metadata
+            let category_id = if cfg.category_players.is_empty() {
+                None
+            } else {
+                store::load_metadata(paths)?
+                    .remove(&video.id)
+                    .filter(|m| !m.unavailable)
+                    .map(|m| m.category_id)
+            };
+            let category_name = match &category_id {
+                Some(id) => store::load_categories(paths)?.remove(id),
+                None => None,
+            };
+            cfg.player_for(queue, category_id.as_deref(), category_name.as_deref())
+        }
+    };
+
+    println!("{} {url}", "Opening:".blue());
+    match template {
+        Some(template) => player::launch(
+            template,
+            &player::PlayerArgs {
+                url: &url,
+                id: &video.id,
+                start: video.start_seconds.unwrap_or(0),
+            },
+        ),
+        None => open::that(&url).map_err(|e| anyhow::anyhow!(e).context("failed to open browser")),
+    }
+}
+
+pub fn remove(target: &str, queue: Option<&str>) -> Result<()> {
     let paths = paths::AppPaths::init()?;
+    let queue = queue.unwrap_or(DEFAULT_QUEUE);
 
     // Extract ID from input before acquiring lock
     let target_id = youtube::extract_video_id(target)?;
 
-    let video = store::with_queue(&paths, |queue| {
-        if queue.is_empty() {
+    let video = store::with_queue(&paths, queue, |videos| {
+        if videos.is_empty() {
             return Ok(None);
         }
 
         // Find by ID
-        let idx = queue
+        let idx = videos
             .iter()
             .position(|v| v.id == target_id)
             .ok_or_else(|| anyhow::anyhow!("video with ID '{target_id}' not found in queue"))?;
 
-        Ok(Some(queue.remove(idx)))
+        Ok(Some(videos.remove(idx)))
     })?;
 
     let Some(video) = video else {
@@ -136,60 +762,515 @@ pub fn remove(target: &str) -> Result<()> {
         return Ok(());
     };
 
-    let event = Event {
-        timestamp: Utc::now(),
-        action: Action::Skipped,
-        video_id: video.id.clone(),
-        time_in_queue_sec: None,
+    let event = Event {
+        timestamp: Utc::now(),
+        action: Action::Skipped,
+        video_id: video.id.clone(),
+        time_in_queue_sec: None,
+        queue: Some(queue.to_string()),
+    };
+    store::log_event(&paths, &event)?;
+
+    println!("{} {}", "Removed:".red(), video.id);
+    Ok(())
+}
+
+/// Moves a video to a 1-based position as shown by `list`.
+pub fn move_to(target: &str, position: usize, queue: Option<&str>) -> Result<()> {
+    let paths = paths::AppPaths::init()?;
+    let queue = queue.unwrap_or(DEFAULT_QUEUE);
+
+    // Extract ID from input before acquiring lock
+    let target_id = youtube::extract_video_id(target)?;
+
+    let new_position = store::with_queue(&paths, queue, |videos| {
+        if position == 0 || position > videos.len() {
+            bail!("position {position} is out of range (1-{})", videos.len());
+        }
+        let idx = find_index(videos, &target_id)?;
+        let video = videos.remove(idx);
+        videos.insert(position - 1, video);
+        Ok(position)
+    })?;
+
+    log_reorder(&paths, &target_id, queue)?;
+    println!("{} {target_id} -> #{new_position}", "Moved:".blue());
+    Ok(())
+}
+
+/// Moves a video so it is picked next (front of the queue in queue mode,
+/// end of the list in stack mode).
+pub fn top(target: &str, queue: Option<&str>) -> Result<()> {
+    move_to_edge(target, queue, true)
+}
+
+/// Moves a video so it is picked last.
+pub fn bottom(target: &str, queue: Option<&str>) -> Result<()> {
+    move_to_edge(target, queue, false)
+}
+
+fn move_to_edge(target: &str, queue: Option<&str>, next_up: bool) -> Result<()> {
+    let paths = paths::AppPaths::init()?;
+    let cfg = store::load_config(&paths.config_file)?;
+    let queue = queue.unwrap_or(DEFAULT_QUEUE);
+
+    // Stack mode picks from the end of the list, so "next up" is the back
+    // (other modes fall back to queue order only to break ties)
+    let mode = cfg.mode_for(queue);
+    let to_front = match mode {
+        Mode::Stack => !next_up,
+        _ => next_up,
+    };
+
+    let target_id = youtube::extract_video_id(target)?;
+
+    let (new_position, new_priority) = store::with_queue(&paths, queue, |videos| {
+        let idx = find_index(videos, &target_id)?;
+        let mut video = videos.remove(idx);
+
+        // Priority outranks position in every mode, so the video also takes
+        // on the highest (or lowest) priority left in the queue
+        let edge = if next_up {
+            videos.iter().map(|v| v.priority).max()
+        } else {
+            videos.iter().map(|v| v.priority).min()
+        };
+        let new_priority = edge.filter(|&edge| {
+            if next_up {
+                edge > video.priority
+            } else {
+                edge < video.priority
+            }
+        });
+        if let Some(priority) = new_priority {
+            video.priority = priority;
+        }
+
+        if to_front {
+            videos.insert(0, video);
+            Ok((1, new_priority))
+        } else {
+            videos.push(video);
+            Ok((videos.len(), new_priority))
+        }
+    })?;
+
+    log_reorder(&paths, &target_id, queue)?;
+    match new_priority {
+        Some(priority) => println!(
+            "{} {target_id} -> #{new_position} (priority now {priority:?})",
+            "Moved:".blue()
+        ),
+        None => println!("{} {target_id} -> #{new_position}", "Moved:".blue()),
+    }
+    if !matches!(mode, Mode::Queue | Mode::Stack) {
+        println!(
+            "{} '{}' mode doesn't pick by position, so this video won't necessarily be picked {}.",
+            "Note:".blue(),
+            mode.name(),
+            if next_up { "next" } else { "last" }
+        );
+    }
+    Ok(())
+}
+
+/// Swaps the positions of two videos.
+pub fn swap(first: &str, second: &str, queue: Option<&str>) -> Result<()> {
+    let paths = paths::AppPaths::init()?;
+    let queue = queue.unwrap_or(DEFAULT_QUEUE);
+
+    let first_id = youtube::extract_video_id(first)?;
+    let second_id = youtube::extract_video_id(second)?;
+    if first_id == second_id {
+        bail!("cannot swap a video with itself");
+    }
+
+    store::with_queue(&paths, queue, |videos| {
+        let a = find_index(videos, &first_id)?;
+        let b = find_index(videos, &second_id)?;
+        videos.swap(a, b);
+        Ok(())
+    })?;
+
+    log_reorder(&paths, &first_id, queue)?;
+    log_reorder(&paths, &second_id, queue)?;
+    println!("{} {first_id} <-> {second_id}", "Swapped:".blue());
+    Ok(())
+}
+
+/// Reverts the last `n` queue mutations and logs compensating history events.
+pub fn undo(n: usize) -> Result<()> {
+    let paths = paths::AppPaths::init()?;
+
+    let replayed = store::undo(&paths, n)?;
+    if replayed.entries.is_empty() {
+        println!("{}", "Nothing to undo.".yellow());
+        return Ok(());
+    }
+
+    for entry in &replayed.entries {
+        log_compensation(&paths, entry, Action::Undone)?;
+        println!("{} {}", "Undone:".yellow(), describe_entry(entry));
+    }
+    warn_stopped_early(&replayed);
+    Ok(())
+}
+
+/// Re-applies the last `n` undone queue mutations.
+pub fn redo(n: usize) -> Result<()> {
+    let paths = paths::AppPaths::init()?;
+
+    let replayed = store::redo(&paths, n)?;
+    if replayed.entries.is_empty() {
+        println!("{}", "Nothing to redo.".yellow());
+        return Ok(());
+    }
+
+    for entry in &replayed.entries {
+        log_compensation(&paths, entry, Action::Redone)?;
+        println!("{} {}", "Redone:".green(), describe_entry(entry));
+    }
+    warn_stopped_early(&replayed);
+    Ok(())
+}
+
+fn warn_stopped_early(replayed: &store::Replayed) {
+    if let Some(e) = &replayed.stopped_early {
+        eprintln!("{} stopped early: {e:#}", "Warning:".yellow());
+    }
+}
+
+/// Prints what the store ran into while the command ran, such as files it
+/// upgraded or history it had to skip.
+pub fn print_notices() {
+    for notice in store::take_notices() {
+        match notice {
+            Notice::Upgraded {
+                path,
+                version,
+                backup,
+            } => eprintln!(
+                "{} upgraded {} to format version {version} (backup: {})",
+                "note:".blue(),
+                path.display(),
+                backup.display()
+            ),
+            Notice::DroppedMetadata(count) => eprintln!(
+                "{} dropped {count} metadata entries in an outdated format; run `ytq fetch` to refetch them",
+                "note:".blue()
+            ),
+            Notice::UnreadableHistory { path, count } => eprintln!(
+                "{} skipped {count} unreadable history entr{} in {}; run `ytq doctor`",
+                "warning:".yellow(),
+                if count == 1 { "y" } else { "ies" },
+                path.display()
+            ),
+        }
+    }
+}
+
+fn log_compensation(paths: &paths::AppPaths, entry: &JournalEntry, action: Action) -> Result<()> {
+    for (action, video_id, queue) in journal::compensating_events(entry, action) {
+        let event = Event {
+            timestamp: Utc::now(),
+            action,
+            video_id,
+            time_in_queue_sec: None,
+            queue: Some(queue),
+        };
+        store::log_event(paths, &event)?;
+    }
+    Ok(())
+}
+
+/// Summarizes a journal entry for undo/redo output, e.g.
+/// "removed dQw4w9WgXcQ from default (2026-02-14 10:30)".
+fn describe_entry(entry: &JournalEntry) -> String {
+    let parts: Vec<String> = entry
+        .changes
+        .iter()
+        .map(|c| {
+            let removed: Vec<&str> = c
+                .removed
+                .iter()
+                .filter(|v| !c.inserted.iter().any(|o| o.id == v.id))
+                .map(|v| v.id.as_str())
+                .collect();
+            let added: Vec<&str> = c
+                .inserted
+                .iter()
+                .filter(|v| !c.removed.iter().any(|o| o.id == v.id))
+                .map(|v| v.id.as_str())
+                .collect();
+            match (removed.is_empty(), added.is_empty()) {
+                (true, false) => format!("added {} to {}", added.join(", "), c.queue),
+                (false, true) => format!("removed {} from {}", removed.join(", "), c.queue),
+                (false, false) => format!(
+                    "replaced {} with {} in {}",
+                    removed.join(", "),
+                    added.join(", "),
+                    c.queue
+                ),
+                (true, true) => format!(
+                    "reordered/edited {} video(s) in {}",
+                    c.inserted.len(),
+                    c.queue
+                ),
+            }
+        })
+        .collect();
+
+    let local_time: DateTime<Local> = DateTime::from(entry.timestamp);
+    format!(
+        "{} ({})",
+        parts.join("; "),
+        local_time.format("%Y-%m-%d %H:%M")
+    )
+}
+
+/// Returns the index of a video in the queue, or a "not found" error.
+fn find_index(videos: &[Video], id: &str) -> Result<usize> {
+    videos
+        .iter()
+        .position(|v| v.id == id)
+        .ok_or_else(|| anyhow::anyhow!("video with ID '{id}' not found in queue"))
+}
+
+fn log_reorder(paths: &paths::AppPaths, video_id: &str, queue: &str) -> Result<()> {
+    let event = Event {
+        timestamp: Utc::now(),
+        action: Action::Reordered,
+        video_id: video_id.to_string(),
+        time_in_queue_sec: None,
+        queue: Some(queue.to_string()),
+    };
+    store::log_event(paths, &event)
+}
+
+/// Updates the user annotations (tags, note, priority) of a queued video.
+#[allow(clippy::too_many_arguments)]
+pub fn edit(
+    target: &str,
+    queue: Option<&str>,
+    tags: &[String],
+    untags: &[String],
+    note: Option<&str>,
+    clear_note: bool,
+    priority: Option<&str>,
+) -> Result<()> {
+    let paths = paths::AppPaths::init()?;
+    let queue = queue.unwrap_or(DEFAULT_QUEUE);
+
+    if tags.is_empty() && untags.is_empty() && note.is_none() && !clear_note && priority.is_none() {
+        bail!("nothing to edit: use --tag, --untag, --note, --clear-note or --priority");
+    }
+
+    // Parse input before acquiring lock
+    let target_id = youtube::extract_video_id(target)?;
+    let priority = priority.map(parse_priority).transpose()?;
+
+    let video = store::with_queue(&paths, queue, |videos| {
+        let video = videos
+            .iter_mut()
+            .find(|v| v.id == target_id)
+            .ok_or_else(|| anyhow::anyhow!("video with ID '{target_id}' not found in queue"))?;
+
+        video.remove_tags(untags);
+        video.add_tags(tags);
+        if clear_note {
+            video.note = None;
+        }
+        if let Some(note) = note {
+            video.note = Some(note.to_string());
+        }
+        if let Some(priority) = priority {
+            video.priority = priority;
+        }
+
+        Ok(video.clone())
+    })?;
+
+    println!("{} {}", "Updated:".green(), video.id);
+    if !video.tags.is_empty() {
+        println!("  Tags:     {}", video.tags.join(", "));
+    }
+    if let Some(note) = &video.note {
+        println!("  Note:     {note}");
+    }
+    println!("  Priority: {:?}", video.priority);
+
+    Ok(())
+}
+
+fn parse_priority(value: &str) -> Result<Priority> {
+    match value.to_lowercase().as_str() {
+        "low" => Ok(Priority::Low),
+        "normal" => Ok(Priority::Normal),
+        "high" => Ok(Priority::High),
+        _ => bail!("invalid priority '{value}': use 'low', 'normal' or 'high'"),
+    }
+}
+
+pub fn list(queue: Option<&str>, format: Format, filter: &ListFilter) -> Result<()> {
+    let paths = paths::AppPaths::init()?;
+    let cfg = store::load_config(&paths.config_file)?;
+    let queue = queue.unwrap_or(DEFAULT_QUEUE);
+    let videos = store::with_queue_read(&paths, queue, |videos| videos.to_vec())?;
+
+    // Filters use cached metadata even in offline mode
+    let needs_metadata = format.is_machine() || filter.is_active() || !cfg.offline;
+    let metadata = if needs_metadata {
+        store::load_metadata(&paths)?
+    } else {
+        HashMap::new()
+    };
+    let categories = if filter.category.is_some() {
+        store::load_categories(&paths)?
+    } else {
+        HashMap::new()
+    };
+    let order = filter.apply(&videos, &metadata, &categories);
+
+    if format.is_machine() {
+        return print_video_rows(&paths, queue, &videos, &order, format);
+    }
+
+    if videos.is_empty() {
+        println!("{}", "Queue is empty.".yellow());
+        return Ok(());
+    }
+    if order.is_empty() {
+        println!("{}", "No videos match.".yellow());
+        return Ok(());
+    }
+
+    let count = if order.len() == videos.len() {
+        format!("{} videos", videos.len())
+    } else {
+        format!("{} of {} videos", order.len(), videos.len())
     };
-    store::log_event(&paths.history_dir, &event)?;
+    if queue == DEFAULT_QUEUE {
+        println!("{count} in queue:");
+    } else {
+        println!("{count} in queue '{queue}':");
+    }
 
-    println!("{} {}", "Removed:".red(), video.id);
+    let shown: Vec<Video> = order.iter().map(|&i| videos[i].clone()).collect();
+    let positions: Vec<usize> = order.iter().map(|i| i + 1).collect();
+    if cfg.offline {
+        print_list_offline(&shown, &positions);
+    } else {
+        print_list_online(&shown, &positions, &metadata);
+    }
     Ok(())
 }
 
-pub fn list() -> Result<()> {
-    let paths = paths::AppPaths::init()?;
-    let cfg = store::load_config(&paths.config_file);
-
-    // Load metadata if online mode is enabled
-    let metadata = if !cfg.offline {
-        store::load_metadata(&paths.metadata_file)
-    } else {
-        HashMap::new()
-    };
-
-    store::with_queue_read(&paths, |queue| {
-        if queue.is_empty() {
-            println!("{}", "Queue is empty.".yellow());
-            return;
-        }
+/// Prints the videos at `order` (indices into `videos`) as JSON, CSV or TSV,
+/// joined with cached metadata whether or not online features are enabled.
+fn print_video_rows(
+    paths: &paths::AppPaths,
+    queue: &str,
+    videos: &[Video],
+    order: &[usize],
+    format: Format,
+) -> Result<()> {
+    let metadata = store::load_metadata(paths)?;
+    let categories = store::load_categories(paths)?;
 
-        println!("{} videos in queue:", queue.len());
+    let rows: Vec<VideoRow> = order
+        .iter()
+        .map(|&i| {
+            let video = &videos[i];
+            VideoRow::new(i + 1, queue, video, metadata.get(&video.id), &categories)
+        })
+        .collect();
 
-        if cfg.offline {
-            print_list_offline(queue);
-        } else {
-            print_list_online(queue, &metadata);
-        }
-    })
+    output::to_stdout(|out| output::write_video_rows(out, format, &rows))
 }
 
-fn print_list_offline(queue: &[Video]) {
-    // Header
-    println!("  {:<4} {:<13} Added", "#", "ID");
-    for (i, v) in queue.iter().enumerate() {
+/// Prints videos with their `positions` in the `#` column.
+fn print_list_offline(queue: &[Video], positions: &[usize]) {
+    let annotations = AnnotationColumns::for_queue(queue);
+
+    // Header (dates are always 16 chars wide, so only the header needs padding)
+    let header = format!(
+        "  {:<4} {:<13} {:<16}{}",
+        "#",
+        "ID",
+        "Added",
+        annotations.header()
+    );
+    println!("{}", header.trim_end());
+    for (v, position) in queue.iter().zip(positions) {
         let local_time: DateTime<Local> = DateTime::from(v.added_at);
         println!(
-            "  {:<4} {:<13} {}",
-            i + 1,
+            "  {:<4} {:<13} {}{}",
+            position,
             v.id,
-            local_time.format("%Y-%m-%d %H:%M")
+            local_time.format("%Y-%m-%d %H:%M"),
+            annotations.row(v)
         );
     }
 }
 
-fn print_list_online(queue: &[Video], metadata: &HashMap<String, VideoMeta>) {
+/// Trailing list columns for user annotations (priority, tags, note).
+/// Columns are only shown when at least one video in the list uses them.
+struct AnnotationColumns {
+    start: bool,
+    priority: bool,
+    tags_width: usize,
+    note: bool,
+}
+
+impl AnnotationColumns {
+    fn for_queue(queue: &[Video]) -> Self {
+        Self {
+            start: queue.iter().any(|v| v.start_seconds.is_some()),
+            priority: queue.iter().any(|v| v.priority != Priority::Normal),
+            tags_width: queue
+                .iter()
+                .map(|v| v.tags.join(",").chars().count())
+                .max()
+                .unwrap_or(0)
+                .min(30), // cap at 30 chars
+            note: queue.iter().any(|v| v.note.is_some()),
+        }
+    }
+
+    fn header(&self) -> String {
+        self.render("Start", "Pri", "Tags", "Note")
+    }
+
+    fn row(&self, v: &Video) -> String {
+        let start = v
+            .start_seconds
+            .map(youtube_api::format_duration)
+            .unwrap_or_default();
+        let tags = truncate(&v.tags.join(","), self.tags_width);
+        let note = truncate(v.note.as_deref().unwrap_or(""), 40);
+        self.render(&start, v.priority.label(), &tags, &note)
+    }
+
+    fn render(&self, start: &str, priority: &str, tags: &str, note: &str) -> String {
+        let mut out = String::new();
+        if self.start {
+            out.push_str(&format!("  {start:<8}"));
+        }
+        if self.priority {
+            out.push_str(&format!("  {priority:<4}"));
+        }
+        if self.tags_width > 0 {
+            let width = self.tags_width.max(4);
+            out.push_str(&format!("  {tags:<width$}"));
+        }
+        if self.note {
+            out.push_str(&format!("  {note}"));
+        }
+        out.trim_end().to_string()
+    }
+}
+
+fn print_list_online(queue: &[Video], positions: &[usize], metadata: &HashMap<String, VideoMeta>) {
     let hint_fetch = "(run `ytq fetch`)";
     let hint_unavailable = "(unavailable - consider `ytq rm`)";
 
@@ -214,32 +1295,38 @@ fn print_list_online(queue: &[Video], metadata: &HashMap<String, VideoMeta>) {
         .unwrap_or(7)
         .min(25); // cap at 25 chars
 
+    let annotations = AnnotationColumns::for_queue(queue);
+
     // Header
-    println!(
-        "  {:<4} {:<13} {:<title_w$}  {:<chan_w$}  {:<8}  Added",
+    let header = format!(
+        "  {:<4} {:<13} {:<title_w$}  {:<chan_w$}  {:<8}  {:<16}{}",
         "#",
         "ID",
         "Title",
         "Channel",
         "Duration",
+        "Added",
+        annotations.header(),
         title_w = title_width,
         chan_w = channel_width,
     );
+    println!("{}", header.trim_end());
 
-    for (i, v) in queue.iter().enumerate() {
+    for (v, position) in queue.iter().zip(positions) {
         let local_time: DateTime<Local> = DateTime::from(v.added_at);
         let added = local_time.format("%Y-%m-%d %H:%M").to_string();
 
         match metadata.get(&v.id) {
             Some(meta) if meta.unavailable => {
                 println!(
-                    "  {:<4} {:<13} {:<title_w$}  {:<chan_w$}  {:<8}  {}",
-                    i + 1,
+                    "  {:<4} {:<13} {:<title_w$}  {:<chan_w$}  {:<8}  {}{}",
+                    position,
                     v.id,
                     hint_unavailable,
                     "",
                     "",
                     added,
+                    annotations.row(v),
                     title_w = title_width,
                     chan_w = channel_width,
                 );
@@ -250,26 +1337,28 @@ fn print_list_online(queue: &[Video], metadata: &HashMap<String, VideoMeta>) {
                 let duration = youtube_api::format_duration(meta.duration_seconds);
 
                 println!(
-                    "  {:<4} {:<13} {:<title_w$}  {:<chan_w$}  {:<8}  {}",
-                    i + 1,
+                    "  {:<4} {:<13} {:<title_w$}  {:<chan_w$}  {:<8}  {}{}",
+                    position,
                     v.id,
                     title,
                     channel,
                     duration,
                     added,
+                    annotations.row(v),
                     title_w = title_width,
                     chan_w = channel_width,
                 );
             }
             None => {
                 println!(
-                    "  {:<4} {:<13} {:<title_w$}  {:<chan_w$}  {:<8}  {}",
-                    i + 1,
+                    "  {:<4} {:<13} {:<title_w$}  {:<chan_w$}  {:<8}  {}{}",
+                    position,
                     v.id,
                     hint_fetch,
                     "",
                     "",
                     added,
+                    annotations.row(v),
                     title_w = title_width,
                     chan_w = channel_width,
                 );
@@ -288,42 +1377,57 @@ fn truncate(s: &str, max: usize) -> String {
     }
 }
 
-pub fn peek(n: usize) -> Result<()> {
+pub fn peek(n: usize, mode: Option<Mode>, queue: Option<&str>, format: Format) -> Result<()> {
     let paths = paths::AppPaths::init()?;
-    let cfg = store::load_config(&paths.config_file);
-
-    let metadata = if !cfg.offline {
-        store::load_metadata(&paths.metadata_file)
-    } else {
-        HashMap::new()
-    };
+    let cfg = store::load_config(&paths.config_file)?;
+    let queue = queue.unwrap_or(DEFAULT_QUEUE);
+    let mode = mode.unwrap_or_else(|| cfg.mode_for(queue));
+    let context = selection_context(&paths, queue, &mode, !cfg.offline)?;
+
+    if format.is_machine() {
+        let videos = store::with_queue_read(&paths, queue, |videos| videos.to_vec())?;
+        let order: Vec<usize> = mode
+            .selection_order(&videos, &context)
+            .into_iter()
+            .take(n)
+            .collect();
+        return print_video_rows(&paths, queue, &videos, &order, format);
+    }
 
-    store::with_queue_read(&paths, |queue| {
-        if queue.is_empty() {
+    store::with_queue_read(&paths, queue, |videos| {
+        if videos.is_empty() {
             println!("{}", "Queue is empty.".yellow());
             return;
         }
 
-        // Collect the slice based on mode
-        let slice: Vec<&Video> = match cfg.mode {
-            Mode::Queue => queue.iter().take(n).collect(),
-            Mode::Stack => queue.iter().rev().take(n).collect(),
-        };
-
-        let actual = slice.len();
-        println!("Next {actual} video(s) ({:?} mode):", cfg.mode);
+        // Collect the slice in selection order (priority, then mode)
+        let videos: Vec<Video> = mode
+            .selection_order(videos, &context)
+            .into_iter()
+            .take(n)
+            .map(|i| videos[i].clone())
+            .collect();
+
+        let actual = videos.len();
+        if mode.is_random() {
+            println!("Next {actual} video(s) ({mode:?} mode, one possible draw):");
+        } else {
+            println!("Next {actual} video(s) ({mode:?} mode):");
+        }
 
-        // Reuse the same tabular format as list
-        let videos: Vec<Video> = slice.into_iter().cloned().collect();
+        // Reuse the same tabular format as list, numbered in pick order
+        let positions: Vec<usize> = (1..=actual).collect();
         if cfg.offline {
-            print_list_offline(&videos);
+            print_list_offline(&videos, &positions);
         } else {
-            print_list_online(&videos, &metadata);
+            print_list_online(&videos, &positions, &context.metadata);
         }
     })
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn stats(
+    queue: Option<&str>,
     wrapped: bool,
     all: bool,
     week: bool,
@@ -337,18 +1441,32 @@ pub fn stats(
     // Resolve date range from flags
     let range = resolve_date_range(all, week, month, year, from, to)?;
 
-    // Load events and filter by date range
-    let all_events = store::stream_history(&paths.history_dir);
-    let filtered = stats::filter_events(&all_events, &range);
+    // Load events and filter by date range (and queue, when one is selected).
+    // Only the history partitions the range needs are read.
+    let all_events = stats::apply_undo(store::history_for(&paths, &range, queue)?);
+    let mut filtered = stats::filter_events(&all_events, &range);
+    if let Some(name) = queue {
+        filtered.retain(|e| e.queue_name() == name);
+    }
 
     // Load metadata opportunistically (no network requests)
-    let metadata = store::load_metadata(&paths.metadata_file);
-    let categories = store::load_categories(&paths.categories_file);
-
-    // Get current queue video IDs for queue profile stats
-    let queue_ids = store::with_queue_read(&paths, |queue| {
-        queue.iter().map(|v| v.id.clone()).collect::<Vec<_>>()
-    })?;
+    let metadata = store::load_metadata(&paths)?;
+    let categories = store::load_categories(&paths)?;
+
+    // Get current queue video IDs for queue profile stats. Without a queue
+    // selector, every queue contributes. A deleted queue still has history
+    // but no current videos.
+    let queue_names = match queue {
+        Some(name) if store::queue_exists(&paths, name)? => vec![name.to_string()],
+        Some(_) => vec![],
+        None => store::list_queues(&paths)?,
+    };
+    let mut queue_ids = Vec::new();
+    for name in &queue_names {
+        store::with_queue_read(&paths, name, |videos| {
+            queue_ids.extend(videos.iter().map(|v| v.id.clone()));
+        })?;
+    }
 
     // Check whether we have any useful metadata (for queue or watched videos)
     let has_metadata = queue_ids
@@ -445,16 +1563,35 @@ fn resolve_date_range(
         .ok_or_else(|| anyhow::anyhow!("failed to build date range for current year"))
 }
 
-pub fn config(key: &str, value: &str) -> Result<()> {
+pub fn config(key: &str, value: &str, queue: Option<&str>) -> Result<()> {
     let paths = paths::AppPaths::init()?;
-    let mut cfg = store::load_config(&paths.config_file);
+    let mut cfg = store::load_config(&paths.config_file)?;
+
+    // With a queue selector, only per-queue settings can be changed
+    if let Some(name) = queue {
+        if !store::queue_exists(&paths, name)? {
+            bail!("queue '{name}' does not exist. Create it with `ytq queue create {name}`.");
+        }
+        match key {
+            "mode" => {
+                let mode = parse_mode(value)?;
+                cfg.queues.entry(name.to_string()).or_default().mode = Some(mode);
+            }
+            "player" => {
+                cfg.queues.entry(name.to_string()).or_default().player = parse_player(value)?;
+            }
+            _ => bail!("unknown per-queue config key '{key}': available keys are 'mode', 'player'"),
+        }
+        cfg.queues
+            .retain(|_, q| q.mode.is_some() || q.player.is_some());
+
+        store::save_config(&paths.config_file, &cfg)?;
+        println!("{}", "Config updated.".green());
+        return Ok(());
+    }
 
     match key {
-        "mode" => match value.to_lowercase().as_str() {
-            "stack" => cfg.mode = Mode::Stack,
-            "queue" => cfg.mode = Mode::Queue,
-            _ => bail!("invalid mode '{value}': use 'stack' or 'queue'"),
-        },
+        "mode" => cfg.mode = parse_mode(value)?,
         "offline" => match value.to_lowercase().as_str() {
             "true" => cfg.offline = true,
             "false" => cfg.offline = false,
@@ -463,8 +1600,31 @@ pub fn config(key: &str, value: &str) -> Result<()> {
         "youtube_api_key" => {
             cfg.youtube_api_key = Some(value.to_string());
         }
+        "feed_base_url" => {
+            // An empty value restores the default
+            cfg.feed_base_url = Some(value.trim().to_string()).filter(|v| !v.is_empty());
+        }
+        "player" => cfg.player = parse_player(value)?,
+        "storage" => {
+            let kind = parse_storage(value)?;
+            bail!(
+                "the storage backend can't be switched by config alone: run `ytq migrate-storage {}` to move your data",
+                kind.name()
+            );
+        }
+        _ if key.starts_with("player.") => {
+            let category = key["player.".len()..].trim().to_lowercase();
+            if category.is_empty() {
+                bail!("missing category in '{key}': use e.g. 'player.music'");
+            }
+            match parse_player(value)? {
+                Some(template) => cfg.category_players.insert(category, template),
+                None => cfg.category_players.remove(&category),
+            };
+        }
         _ => bail!(
-            "unknown config key '{key}': available keys are 'mode', 'offline', 'youtube_api_key'"
+            "unknown config key '{key}': available keys are 'mode', 'offline', 'youtube_api_key', \
+             'feed_base_url', 'player', 'player.<category>', 'storage'"
         ),
     }
 
@@ -473,26 +1633,656 @@ pub fn config(key: &str, value: &str) -> Result<()> {
     Ok(())
 }
 
-pub fn info() -> Result<()> {
+/// Validates a player command template. An empty value clears the setting.
+fn parse_player(value: &str) -> Result<Option<String>> {
+    let value = value.trim();
+    if value.is_empty() {
+        return Ok(None);
+    }
+
+    let sample = player::PlayerArgs {
+        url: "https://www.youtube.com/watch?v=dQw4w9WgXcQ",
+        id: "dQw4w9WgXcQ",
+        start: 0,
+    };
+    player::build_command(value, &sample)?;
+    if !value.contains("{url}") && !value.contains("{id}") {
+        bail!("player command '{value}' must contain {{url}} or {{id}}");
+    }
+
+    Ok(Some(value.to_string()))
+}
+
+pub fn parse_mode(value: &str) -> Result<Mode> {
+    match value.to_lowercase().as_str() {
+        "stack" => Ok(Mode::Stack),
+        "queue" => Ok(Mode::Queue),
+        "shortest" => Ok(Mode::Shortest),
+        "longest" => Ok(Mode::Longest),
+        "oldest-published" => Ok(Mode::OldestPublished),
+        "round-robin" => Ok(Mode::RoundRobin),
+        "weighted-age" => Ok(Mode::WeightedAge),
+        "weighted-priority" => Ok(Mode::WeightedPriority),
+        _ => bail!(
+            "invalid mode '{value}': use one of {}",
+            Mode::NAMES.join(", ")
+        ),
+    }
+}
+
+pub fn parse_storage(value: &str) -> Result<StorageKind> {
+    match value.to_lowercase().as_str() {
+        "json" => Ok(StorageKind::Json),
+        "sqlite" => Ok(StorageKind::Sqlite),
+        _ => bail!("invalid storage backend '{value}': use 'json' or 'sqlite'"),
+    }
+}
+
+pub fn info(format: Format) -> Result<()> {
     let paths = paths::AppPaths::init()?;
+    let storage = store::load_config(&paths.config_file)?.storage;
+
+    if format.is_machine() {
+        let path = |p: &std::path::PathBuf| serde_json::Value::from(p.display().to_string());
+        let pairs = [
+            ("config_dir", path(&paths.config_dir)),
+            ("config_dir_source", paths.config_dir_source.into()),
+            ("data_dir", path(&paths.data_dir)),
+            ("data_dir_source", paths.data_dir_source.into()),
+            ("storage", storage.name().into()),
+            ("database", path(&paths.database_file)),
+            ("config", path(&paths.config_file)),
+            ("queue", path(&paths.queue_file)),
+            ("queues", path(&paths.queues_dir)),
+            ("metadata", path(&paths.metadata_file)),
+            ("categories", path(&paths.categories_file)),
+            ("history", path(&paths.history_dir)),
+            ("journal", path(&paths.journal_file)),
+            ("subscriptions", path(&paths.subscriptions_file)),
+            ("queue_file_exists", paths.queue_file.exists().into()),
+        ];
+        return output::to_stdout(|out| output::write_key_values(out, format, &pairs));
+    }
 
     println!("{}", "Data Paths".bold());
     println!("---------------");
+    let source = |source: Option<&str>| match source {
+        Some(source) => format!(" (set by {source})"),
+        None => String::new(),
+    };
+    println!(
+        "Config Dir: {}{}",
+        paths.config_dir.display(),
+        source(paths.config_dir_source)
+    );
+    println!(
+        "Data Dir:   {}{}",
+        paths.data_dir.display(),
+        source(paths.data_dir_source)
+    );
+    println!("Storage:    {}", storage.name());
+    println!("Database:   {}", paths.database_file.display());
     println!("Config:     {}", paths.config_file.display());
     println!("Queue:      {}", paths.queue_file.display());
+    println!("Queues:     {}", paths.queues_dir.display());
     println!("Metadata:   {}", paths.metadata_file.display());
     println!("Categories: {}", paths.categories_file.display());
     println!("History:    {}", paths.history_dir.display());
+    println!("Journal:    {}", paths.journal_file.display());
+    println!("Subscriptions: {}", paths.subscriptions_file.display());
 
     let queue_exists = paths.queue_file.exists();
     println!("Queue File Exists? {queue_exists}");
 
-    Ok(())
+    Ok(())
+}
+
+/// Validates every data file, including each line of the history partitions,
+/// and with `repair` fixes what it can under the queue lock.
+pub fn doctor(repair: bool) -> Result<()> {
+    let paths = paths::AppPaths::init()?;
+
+    // Hold the lock for the whole check so no command writes in between
+    store::with_exclusive_lock(&paths, || {
+        let mut findings = Vec::new();
+        let mut checked = 0;
+        let mut check = |finding: Result<Option<doctor::Finding>>| -> Result<()> {
+            checked += 1;
+            findings.extend(finding?);
+            Ok(())
+        };
+
+        check(doctor::check_object::<Config>(
+            &paths.config_file,
+            Some(Schema::Config),
+        ))?;
+        // JSON files are checked even under the SQLite backend, since
+        // `ytq migrate-storage` keeps them around as a backup
+        let queue_files = std::iter::once(paths.queue_file.clone())
+            .chain(doctor::files_with_extension(&paths.queues_dir, "json"));
+        for file in queue_files {
+            check(doctor::check_list::<Video>(&file, Some(Schema::Queue)))?;
+        }
+        check(doctor::check_map::<VideoMeta>(
+            &paths.metadata_file,
+            Some(Schema::Metadata),
+        ))?;
+        check(doctor::check_map::<String>(&paths.categories_file, None))?;
+        check(doctor::check_object::<journal::Journal>(
+            &paths.journal_file,
+            None,
+        ))?;
+        check(doctor::check_list::<Subscription>(
+            &paths.subscriptions_file,
+            None,
+        ))?;
+        for file in doctor::files_with_extension(&paths.history_dir, "jsonl") {
+            check(doctor::check_history(&file))?;
+        }
+        if paths.database_file.exists() {
+            check(doctor::check_database(&paths.database_file))?;
+        }
+
+        let dirs = [
+            &paths.config_dir,
+            &paths.data_dir,
+            &paths.queues_dir,
+            &paths.history_dir,
+        ];
+        for dir in dirs {
+            findings.extend(doctor::stale_temp_files(dir));
+        }
+
+        for finding in &findings {
+            println!("{} {}", "Problem:".red(), finding.path.display());
+            const SHOWN: usize = 5;
+            for problem in finding.problems.iter().take(SHOWN) {
+                println!("  {problem}");
+            }
+            if finding.problems.len() > SHOWN {
+                println!("  ... and {} more", finding.problems.len() - SHOWN);
+            }
+
+            if !repair {
+                println!("  {} {}", "Repair:".yellow(), finding.repair.describe());
+                continue;
+            }
+            match finding.repair()? {
+                Some(backup) => println!(
+                    "  {} {} (original kept as {})",
+                    "Repaired:".green(),
+                    finding.repair.describe(),
+                    backup.display()
+                ),
+                None => println!("  {} {}", "Repaired:".green(), finding.repair.describe()),
+            }
+        }
+
+        let quarantined: Vec<_> = dirs
+            .iter()
+            .flat_map(|dir| doctor::quarantined_files(dir))
+            .collect();
+        if !quarantined.is_empty() {
+            println!(
+                "{} {} damaged file(s) kept from earlier repairs; delete them once you've checked nothing is missing:",
+                "Note:".blue(),
+                quarantined.len()
+            );
+            for path in &quarantined {
+                println!("  {}", path.display());
+            }
+        }
+
+        if findings.is_empty() {
+            println!(
+                "{} Checked {checked} data file(s); no problems found.",
+                "OK:".green()
+            );
+        } else if repair {
+            println!("{} Repaired {} file(s).", "Done.".green(), findings.len());
+        } else {
+            println!(
+                "Found problems in {} file(s). Run `ytq doctor --repair` to fix them.",
+                findings.len()
+            );
+        }
+        Ok(())
+    })
+}
+
+/// Copies all data to another backend, verifies the copy and switches the
+/// config over. The old backend's files stay where they are as a backup.
+pub fn migrate_storage(target: &str) -> Result<()> {
+    let paths = paths::AppPaths::init()?;
+    let target = parse_storage(target)?;
+    let report = store::migrate_storage(&paths, target)?;
+
+    let counts = &report.counts;
+    println!(
+        "{} Moved {} queue(s) with {} video(s), {} history event(s), {} metadata entr{} and {} subscription(s) from {} to {}.",
+        "Done.".green(),
+        counts.queues,
+        counts.videos,
+        counts.events,
+        counts.metadata,
+        if counts.metadata == 1 { "y" } else { "ies" },
+        counts.subscriptions,
+        report.from.name(),
+        target.name()
+    );
+    if let Some(path) = &report.set_aside {
+        println!(
+            "{} Earlier {} data was moved to {}.",
+            "Note:".blue(),
+            target.name(),
+            path.display()
+        );
+    }
+    let kept = match report.from {
+        StorageKind::Json => &paths.data_dir,
+        StorageKind::Sqlite => &paths.database_file,
+    };
+    println!(
+        "The {} data in {} was left as a backup; delete it once you're happy with the move.",
+        report.from.name(),
+        kept.display()
+    );
+    Ok(())
+}
+
+/// Writes every queue, the history, caches, subscriptions and the config
+/// (without the API key) to one archive file, or to stdout.
+pub fn export(file: Option<&Path>) -> Result<()> {
+    let paths = paths::AppPaths::init()?;
+    let config = store::load_config(&paths.config_file)?;
+    let has_api_key = config.youtube_api_key.is_some();
+    let snapshot = store::with_storage(&paths, store::Snapshot::read)?;
+    let archive = archive::Archive::new(config, snapshot);
+    let data = serde_json::to_string_pretty(&archive)?;
+
+    let Some(file) = file.filter(|f| *f != Path::new("-")) else {
+        println!("{data}");
+        return Ok(());
+    };
+    store::write_atomic(file, format!("{data}\n").as_bytes())?;
+    println!(
+        "{} Exported {} queue(s) with {} video(s), {} history event(s) and {} metadata entr{} to {}.",
+        "Done.".green(),
+        archive.queues.len(),
+        archive.video_count(),
+        archive.history.len(),
+        archive.metadata.len(),
+        if archive.metadata.len() == 1 {
+            "y"
+        } else {
+            "ies"
+        },
+        file.display()
+    );
+    if has_api_key {
+        println!(
+            "{} Your YouTube API key was left out; set it again on the other machine.",
+            "Note:".blue()
+        );
+    }
+    Ok(())
+}
+
+/// Writes a queue as a Netscape bookmark file, which browsers and
+/// read-later tools import, or to stdout.
+pub fn export_bookmarks(file: Option<&Path>, queue: Option<&str>) -> Result<()> {
+    let paths = paths::AppPaths::init()?;
+    let queue = queue.unwrap_or(DEFAULT_QUEUE);
+    let videos = store::with_queue_read(&paths, queue, <[Video]>::to_vec)?;
+    let metadata = store::load_metadata(&paths)?;
+    let html = bookmarks::to_netscape(queue, &videos, &metadata);
+
+    let Some(file) = file.filter(|f| *f != Path::new("-")) else {
+        print!("{html}");
+        return Ok(());
+    };
+    store::write_atomic(file, html.as_bytes())?;
+    println!(
+        "{} Exported {} video(s) from queue '{queue}' to {}.",
+        "Done.".green(),
+        videos.len(),
+        file.display()
+    );
+    Ok(())
+}
+
+/// Imports an archive written by `ytq export`, merging it into the current
+/// data or, with `replace`, replacing all of it.
+pub fn import(file: &Path, replace: bool) -> Result<()> {
+    let paths = paths::AppPaths::init()?;
+    let text = if file == Path::new("-") {
+        let mut text = String::new();
+        io::stdin().read_to_string(&mut text)?;
+        text
+    } else {
+        fs::read_to_string(file).with_context(|| format!("failed to read {}", file.display()))?
+    };
+    let archive = archive::Archive::parse(&text)
+        .with_context(|| format!("cannot import {}", file.display()))?;
+
+    if replace {
+        let (queues, videos, events) = (
+            archive.queues.len(),
+            archive.video_count(),
+            archive.history.len(),
+        );
+        let (config, snapshot) = archive.into_snapshot();
+        let set_aside = store::replace_all(&paths, config, &snapshot)?;
+
+        println!(
+            "{} Replaced your data with {queues} queue(s), {videos} video(s) and {events} history event(s).",
+            "Done.".green()
+        );
+        if let Some(path) = set_aside {
+            println!(
+                "{} Your previous data was moved to {}.",
+                "Note:".blue(),
+                path.display()
+            );
+        }
+        return Ok(());
+    }
+
+    // The config is merged under the lock too, so a concurrent change to
+    // it isn't overwritten
+    let report = store::with_storage(&paths, |storage| {
+        let mut config = store::load_config(&paths.config_file)?;
+        let report = archive::merge(storage, &mut config, archive)?;
+        store::save_config(&paths.config_file, &config)?;
+        Ok(report)
+    })?;
+
+    println!(
+        "{} Added {} video(s) ({} new queue(s)), {} history event(s) and {} metadata entr{}.",
+        "Done.".green(),
+        report.videos_added,
+        report.queues_created,
+        report.events_added,
+        report.metadata_updated,
+        if report.metadata_updated == 1 {
+            "y"
+        } else {
+            "ies"
+        }
+    );
+    if report.videos_backdated > 0 {
+        println!(
+            "  {} video(s) already queued kept the archive's earlier added date.",
+            report.videos_backdated
+        );
+    }
+    if report.events_skipped > 0 {
+        println!(
+            "  Skipped {} history event(s) you already had.",
+            report.events_skipped
+        );
+    }
+    if report.categories_added > 0 || report.subscriptions_added > 0 {
+        println!(
+            "  Also added {} categor{} and {} subscription(s).",
+            report.categories_added,
+            if report.categories_added == 1 {
+                "y"
+            } else {
+                "ies"
+            },
+            report.subscriptions_added
+        );
+    }
+    Ok(())
+}
+
+/// Imports a Google Takeout export: Watch Later entries are queued with the
+/// date they were saved, and the watch history is back-filled as `Watched`
+/// events. Entries imported before are skipped, even once watched or
+/// removed from the queue, so it can be run again on a newer export.
+pub fn import_takeout(path: &Path, queue: Option<&str>) -> Result<()> {
+    let paths = paths::AppPaths::init()?;
+    let queue = queue.unwrap_or(DEFAULT_QUEUE);
+
+    let mut watch_later = Vec::new();
+    let mut watched = Vec::new();
+    let mut skipped: std::collections::BTreeMap<String, usize> = Default::default();
+    for file in takeout::find_files(path)? {
+        println!("Reading {}...", file.path().display());
+        let parsed = takeout::parse_file(&file)?;
+        for (reason, count) in parsed.skipped {
+            *skipped.entry(reason).or_default() += count;
+        }
+        match file {
+            takeout::TakeoutFile::WatchLater(_) => watch_later.extend(parsed.videos),
+            takeout::TakeoutFile::WatchHistory(_) => watched.extend(parsed.videos),
+        }
+    }
+
+    let saved = watch_later
+        .into_iter()
+        .map(|video| SavedVideo {
+            link: youtube::VideoLink::from_id(video.id),
+            saved_at: Some(video.time),
+            tags: Vec::new(),
+        })
+        .collect();
+    let outcome = enqueue_saved(&paths, queue, saved)?;
+
+    let history: Vec<Event> = watched
+        .into_iter()
+        .map(|video| Event {
+            timestamp: video.time,
+            action: Action::Watched,
+            video_id: video.id,
+            time_in_queue_sec: None,
+            queue: Some(queue.to_string()),
+        })
+        .collect();
+    let total = history.len();
+    let events = archive::new_events(&store::stream_history(&paths)?, history);
+    let new_watched = events.len();
+    let first_watched = events.first().map(|e| e.timestamp);
+    let last_watched = events.last().map(|e| e.timestamp);
+    store::log_events(&paths, &events)?;
+
+    println!(
+        "{} {} video(s) from Watch Later to queue '{queue}'.",
+        "Added:".green(),
+        outcome.added
+    );
+    outcome.print_skipped();
+    print!("{} {new_watched} watched video(s)", "History:".green());
+    match (first_watched, last_watched) {
+        (Some(first), Some(last)) => println!(
+            " from {} to {}.",
+            first.with_timezone(&Local).format("%Y-%m-%d"),
+            last.with_timezone(&Local).format("%Y-%m-%d")
+        ),
+        _ => println!("."),
+    }
+    if total > new_watched {
+        println!("  {} already imported (skipped).", total - new_watched);
+    }
+    if !skipped.is_empty() {
+        println!("  {}", "Not imported:".yellow());
+        for (reason, count) in &skipped {
+            println!("    {count} x {reason}");
+        }
+    }
+    if new_watched > 0 {
+        println!("  Run {} to see it.", "`ytq stats --wrapped`".bold());
+    }
+    Ok(())
+}
+
+/// Adds the YouTube videos in a bookmark file (browser HTML, Pocket or
+/// Raindrop CSV, OPML), each dated when it was bookmarked. YouTube links
+/// that are not videos are listed with the reason; other sites are counted.
+pub fn import_bookmarks(path: &Path, queue: Option<&str>) -> Result<()> {
+    let paths = paths::AppPaths::init()?;
+    let queue = queue.unwrap_or(DEFAULT_QUEUE);
+    let text = if path == Path::new("-") {
+        let mut text = String::new();
+        io::stdin()
+            .read_to_string(&mut text)
+            .context("failed to read stdin")?;
+        text
+    } else {
+        fs::read_to_string(path).with_context(|| format!("failed to read {}", path.display()))?
+    };
+    let (format, found) =
+        bookmarks::parse(&text).with_context(|| format!("cannot import {}", path.display()))?;
+    println!("Reading {} ({format})...", path.display());
+
+    let scan = bookmarks::scan(found);
+    if scan.videos.is_empty() && scan.rejected.is_empty() {
+        println!(
+            "{}",
+            format!("No YouTube links found in {}.", path.display()).yellow()
+        );
+        return Ok(());
+    }
+
+    let saved = scan
+        .videos
+        .into_iter()
+        .map(|(link, bookmark)| SavedVideo {
+            link,
+            saved_at: bookmark.saved_at,
+            tags: bookmark.tags,
+        })
+        .collect();
+    let outcome = enqueue_saved(&paths, queue, saved)?;
+
+    println!(
+        "{} {} video(s) to queue '{queue}'.",
+        "Added:".green(),
+        outcome.added
+    );
+    outcome.print_skipped();
+    if !scan.rejected.is_empty() {
+        println!("  {}", format!("Rejected ({}):", scan.rejected.len()).red());
+        for (url, reason) in &scan.rejected {
+            println!("    {url}: {reason}");
+        }
+    }
+    if scan.other_sites > 0 {
+        println!("  Ignored {} link(s) to other sites.", scan.other_sites);
+    }
+
+    let cfg = store::load_config(&paths.config_file)?;
+    if outcome.added > 0 && !cfg.offline {
+        println!("  Run {} to get video metadata.", "`ytq fetch`".bold());
+    }
+    Ok(())
+}
+
+/// A video saved somewhere else (Watch Later, a bookmark) to be queued with
+/// the date it was saved.
+struct SavedVideo {
+    link: youtube::VideoLink,
+    /// Now, if unknown.
+    saved_at: Option<DateTime<Utc>>,
+    tags: Vec<String>,
+}
+
+/// What [`enqueue_saved`] did.
+struct SavedOutcome {
+    added: usize,
+    already_queued: usize,
+    /// Queued by an earlier import and since watched or removed.
+    imported_before: usize,
+}
+
+impl SavedOutcome {
+    fn print_skipped(&self) {
+        if self.already_queued > 0 {
+            println!("  {} already in queue (skipped).", self.already_queued);
+        }
+        if self.imported_before > 0 {
+            println!(
+                "  {} imported before and since watched or removed (skipped).",
+                self.imported_before
+            );
+        }
+    }
+}
+
+/// Adds saved videos to a queue, oldest first, with their saved date as
+/// `added_at` and as the time of their `Queued` event. Videos whose `Queued`
+/// event is already in the history were added by an earlier import, so they
+/// are skipped even if they have left the queue since.
+fn enqueue_saved(
+    paths: &paths::AppPaths,
+    queue: &str,
+    mut saved: Vec<SavedVideo>,
+) -> Result<SavedOutcome> {
+    let now = Utc::now();
+    saved.sort_by_key(|video| video.saved_at.unwrap_or(now));
+
+    let queued_event = |id: &str, timestamp| Event {
+        timestamp,
+        action: Action::Queued,
+        video_id: id.to_string(),
+        time_in_queue_sec: None,
+        queue: Some(queue.to_string()),
+    };
+    let dated: Vec<Event> = saved
+        .iter()
+        .filter_map(|video| Some(queued_event(&video.link.id, video.saved_at?)))
+        .collect();
+    let mut not_imported: std::collections::HashSet<(String, DateTime<Utc>)> =
+        archive::new_events(&store::stream_history(paths)?, dated)
+            .into_iter()
+            .map(|event| (event.video_id, event.timestamp))
+            .collect();
+
+    let (events, already_queued, imported_before) = store::with_queue(paths, queue, |videos| {
+        let mut events = Vec::new();
+        let (mut already_queued, mut imported_before) = (0, 0);
+        for SavedVideo {
+            link,
+            saved_at,
+            tags,
+        } in saved
+        {
+            if videos.iter().any(|v| v.id == link.id) {
+                already_queued += 1;
+                continue;
+            }
+            if saved_at.is_some_and(|at| !not_imported.remove(&(link.id.clone(), at))) {
+                imported_before += 1;
+                continue;
+            }
+            let added_at = saved_at.unwrap_or(now);
+            let mut video = Video::new(
+                link.id.clone(),
+                youtube::build_canonical_url(&link.id),
+                added_at,
+            );
+            video.add_tags(&tags);
+            video.start_seconds = link.start_seconds;
+            videos.push(video);
+            events.push(queued_event(&link.id, added_at));
+        }
+        Ok((events, already_queued, imported_before))
+    })?;
+
+    store::log_events(paths, &events)?;
+    Ok(SavedOutcome {
+        added: events.len(),
+        already_queued,
+        imported_before,
+    })
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn fetch(
     target: Option<&str>,
-    queue_flag: bool,
+    queue: Option<&str>,
+    queue_only: bool,
     history_flag: bool,
     all_flag: bool,
     limit: Option<usize>,
@@ -500,7 +2290,7 @@ pub fn fetch(
     refresh_categories: bool,
 ) -> Result<()> {
     let paths = paths::AppPaths::init()?;
-    let cfg = store::load_config(&paths.config_file);
+    let cfg = store::load_config(&paths.config_file)?;
 
     // Check offline mode
     if cfg.offline {
@@ -508,19 +2298,13 @@ pub fn fetch(
     }
 
     // Resolve API key
-    let api_key = cfg.effective_api_key().ok_or_else(|| {
-        anyhow::anyhow!(
-            "no YouTube Data API key configured.\n\
-             Set it via: ytq config youtube_api_key <key>\n\
-             Or set the YOUTUBE_DATA_API_KEY environment variable."
-        )
-    })?;
+    let api_key = require_api_key(&cfg)?;
 
     // Fetch and save video categories if missing or explicitly requested
     if refresh_categories || !paths.categories_file.exists() {
         match youtube_api::fetch_categories(&api_key) {
             Ok(categories) => {
-                store::save_categories(&paths.categories_file, &categories)?;
+                store::save_categories(&paths, &categories)?;
                 eprintln!("Updated {} video categories.", categories.len());
             }
             Err(e) => {
@@ -539,7 +2323,7 @@ pub fn fetch(
             .collect::<Result<Vec<_>>>()?;
         (ids, true)
     } else {
-        let ids = collect_ids_for_scope(&paths, queue_flag, history_flag, all_flag)?;
+        let ids = collect_ids_for_scope(&paths, queue, queue_only, history_flag, all_flag)?;
         (ids, false)
     };
 
@@ -548,7 +2332,7 @@ pub fn fetch(
     ids_to_fetch.dedup();
 
     // Load existing metadata
-    let mut metadata = store::load_metadata(&paths.metadata_file);
+    let metadata = store::load_metadata(&paths)?;
 
     // For scope-based fetches, filter out IDs that already have metadata.
     // Also skip unavailable (tombstone) entries unless --force is passed.
@@ -589,33 +2373,28 @@ pub fn fetch(
         .map(|id| id.as_str())
         .collect();
 
-    // Merge fetched entries into existing metadata (upsert)
-    for meta in fetched {
-        metadata.insert(meta.id.clone(), meta);
-    }
+    // Fetched entries replace existing metadata (upsert)
+    let mut entries = fetched;
 
     // Store tombstone entries for videos the API returned nothing for
     let now = Utc::now();
     for id in &missing_ids {
-        metadata.insert(
-            id.to_string(),
-            VideoMeta {
-                id: id.to_string(),
-                title: String::new(),
-                channel: String::new(),
-                channel_id: String::new(),
-                duration: String::new(),
-                duration_seconds: 0,
-                published_at: now,
-                category_id: String::new(),
-                tags: vec![],
-                fetched_at: now,
-                unavailable: true,
-            },
-        );
+        entries.push(VideoMeta {
+            id: id.to_string(),
+            title: String::new(),
+            channel: String::new(),
+            channel_id: String::new(),
+            duration: String::new(),
+            duration_seconds: 0,
+            published_at: now,
+            category_id: String::new(),
+            tags: vec![],
+            fetched_at: now,
+            unavailable: true,
+        });
     }
 
-    store::save_metadata(&paths.metadata_file, &metadata)?;
+    store::upsert_metadata(&paths, &entries)?;
 
     println!("{} Fetched metadata for {count} video(s).", "Done.".green());
 
@@ -634,29 +2413,37 @@ pub fn fetch(
 }
 
 /// Collects video IDs based on the scope flags.
-/// Default (no flags) behaves as --queue.
+/// Default (no flags) behaves as queue-only across every queue; `--queue NAME`
+/// narrows the queue scope to a single queue.
 fn collect_ids_for_scope(
     paths: &paths::AppPaths,
-    queue_flag: bool,
+    queue: Option<&str>,
+    queue_only: bool,
     history_flag: bool,
     all_flag: bool,
 ) -> Result<Vec<String>> {
     let mut ids = Vec::new();
 
     // Default to queue-only when no flags are given
-    let use_queue = all_flag || queue_flag || (!history_flag);
+    let use_queue = all_flag || queue.is_some() || queue_only || !history_flag;
     let use_history = all_flag || history_flag;
 
     if use_queue {
-        store::with_queue_read(paths, |queue| {
-            for v in queue {
-                ids.push(v.id.clone());
-            }
-        })?;
+        let queue_names = match queue {
+            Some(name) if !all_flag => vec![name.to_string()],
+            _ => store::list_queues(paths)?,
+        };
+        for name in &queue_names {
+            store::with_queue_read(paths, name, |videos| {
+                for v in videos {
+                    ids.push(v.id.clone());
+                }
+            })?;
+        }
     }
 
     if use_history {
-        let events = store::stream_history(&paths.history_dir);
+        let events = store::stream_history(paths)?;
         for event in &events {
             ids.push(event.video_id.clone());
         }
@@ -665,35 +2452,645 @@ fn collect_ids_for_scope(
     Ok(ids)
 }
 
-pub fn random() -> Result<()> {
+/// Which videos `ytq search` looks at.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SearchScope {
+    /// Queued and watched videos.
+    All,
+    Queued,
+    Watched,
+}
+
+/// What `ytq search` does with the best queued match.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SearchAction {
+    Next,
+    Remove,
+}
+
+/// Where a search result was found.
+enum SearchHit {
+    Queued { queue: String, position: usize },
+    Watched { at: DateTime<Utc> },
+}
+
+struct SearchResult {
+    video_id: String,
+    hit: SearchHit,
+    score: u32,
+}
+
+/// Fuzzy-searches IDs, titles, channels and tags of queued videos (every
+/// queue, or only `--queue NAME`) and watched history. With an action, the
+/// best queued match is played or removed instead of listing results.
+pub fn search(
+    query: &str,
+    queue: Option<&str>,
+    scope: SearchScope,
+    limit: usize,
+    action: Option<SearchAction>,
+) -> Result<()> {
     let paths = paths::AppPaths::init()?;
+    let metadata = store::load_metadata(&paths)?;
 
-    let video = store::with_queue(&paths, |queue| {
-        if queue.is_empty() {
-            return Ok(None);
+    if query.trim().is_empty() {
+        bail!("search query is empty");
+    }
+
+    let score = |video_id: &str, user_tags: &[String]| {
+        let meta = metadata.get(video_id).filter(|m| !m.unavailable);
+        let mut tags: Vec<&str> = user_tags.iter().map(String::as_str).collect();
+        if let Some(meta) = meta {
+            tags.extend(meta.tags.iter().map(String::as_str));
         }
+        search::score(
+            query,
+            &search::SearchFields {
+                id: video_id,
+                title: meta.map(|m| m.title.as_str()),
+                channel: meta.map(|m| m.channel.as_str()),
+                tags,
+            },
+        )
+    };
 
-        let idx = rand::rng().random_range(0..queue.len());
-        Ok(Some(queue.remove(idx)))
-    })?;
+    let mut results = Vec::new();
 
-    let Some(video) = video else {
-        println!("{}", "Queue is empty.".yellow());
+    if scope != SearchScope::Watched {
+        let queue_names = match queue {
+            Some(name) => vec![name.to_string()],
+            None => store::list_queues(&paths)?,
+        };
+        for name in &queue_names {
+            store::with_queue_read(&paths, name, |videos| {
+                for (i, video) in videos.iter().enumerate() {
+                    if let Some(score) = score(&video.id, &video.tags) {
+                        results.push(SearchResult {
+                            video_id: video.id.clone(),
+                            hit: SearchHit::Queued {
+                                queue: name.clone(),
+                                position: i + 1,
+                            },
+                            score,
+                        });
+                    }
+                }
+            })?;
+        }
+    }
+
+    if scope != SearchScope::Queued {
+        // Most recent watch per video, ignoring undone watches
+        let mut last_watched: HashMap<String, DateTime<Utc>> = HashMap::new();
+        for event in stats::apply_undo(store::stream_history(&paths)?) {
+            if matches!(event.action, Action::Watched) {
+                last_watched.insert(event.video_id, event.timestamp);
+            }
+        }
+        for (video_id, at) in last_watched {
+            if let Some(score) = score(&video_id, &[]) {
+                results.push(SearchResult {
+                    video_id,
+                    hit: SearchHit::Watched { at },
+                    score,
+                });
+            }
+        }
+    }
+
+    // Best first; queued before watched on ties, then by ID for stable output
+    results.sort_by(|a, b| {
+        b.score
+            .cmp(&a.score)
+            .then_with(|| {
+                let watched = |r: &SearchResult| matches!(r.hit, SearchHit::Watched { .. });
+                watched(a).cmp(&watched(b))
+            })
+            .then_with(|| a.video_id.cmp(&b.video_id))
+    });
+
+    if let Some(action) = action {
+        let Some((video_id, queue)) = results.iter().find_map(|r| match &r.hit {
+            SearchHit::Queued { queue, .. } => Some((r.video_id.as_str(), queue.as_str())),
+            SearchHit::Watched { .. } => None,
+        }) else {
+            bail!("no queued video matches '{query}'");
+        };
+
+        return match action {
+            SearchAction::Next => next(
+                Some(video_id),
+                None,
+                None,
+                Some(queue),
+                &PlayOptions {
+                    player: None,
+                    print: None,
+                    json: false,
+                    dry_run: false,
+                },
+            ),
+            SearchAction::Remove => remove(video_id, Some(queue)),
+        };
+    }
+
+    if results.is_empty() {
+        println!("{}", format!("No matches for '{query}'.").yellow());
         return Ok(());
+    }
+
+    let total = results.len();
+    results.truncate(limit);
+    if total > results.len() {
+        println!("{total} matches for '{query}' (showing {}):", results.len());
+    } else {
+        println!("{total} matches for '{query}':");
+    }
+
+    let title_of = |id: &str| {
+        metadata
+            .get(id)
+            .filter(|m| !m.unavailable)
+            .map(|m| (m.title.as_str(), m.channel.as_str()))
+            .unwrap_or_default()
     };
+    let title_width = results
+        .iter()
+        .map(|r| title_of(&r.video_id).0.chars().count())
+        .max()
+        .unwrap_or(0)
+        .clamp(5, 50);
+    let channel_width = results
+        .iter()
+        .map(|r| title_of(&r.video_id).1.chars().count())
+        .max()
+        .unwrap_or(0)
+        .clamp(7, 25);
 
-    // Log event and open video
-    let duration = Utc::now().signed_duration_since(video.added_at);
-    let event = Event {
-        timestamp: Utc::now(),
-        action: Action::Watched,
-        video_id: video.id.clone(),
-        time_in_queue_sec: Some(duration.num_seconds()),
+    println!(
+        "  {:<13} {:<title_width$}  {:<channel_width$}  Where",
+        "ID", "Title", "Channel"
+    );
+    for result in &results {
+        let (title, channel) = title_of(&result.video_id);
+        let location = match &result.hit {
+            SearchHit::Queued { queue, position } => format!("{queue} #{position}"),
+            SearchHit::Watched { at } => {
+                let local: DateTime<Local> = DateTime::from(*at);
+                format!("watched {}", local.format("%Y-%m-%d"))
+            }
+        };
+        println!(
+            "  {:<13} {:<title_width$}  {:<channel_width$}  {}",
+            result.video_id,
+            truncate(title, title_width),
+            truncate(channel, channel_width),
+            location,
+        );
+    }
+
+    Ok(())
+}
+
+pub fn random(queue: Option<&str>, options: &PlayOptions) -> Result<()> {
+    let paths = paths::AppPaths::init()?;
+    let cfg = store::load_config(&paths.config_file)?;
+    let queue = queue.unwrap_or(DEFAULT_QUEUE);
+
+    // Catch a malformed --player before popping anything
+    options.player.map(parse_player).transpose()?;
+
+    play(&paths, &cfg, queue, options, |videos| {
+        Ok(rand::rng().random_range(0..videos.len()))
+    })
+}
+
+pub fn queue_list() -> Result<()> {
+    let paths = paths::AppPaths::init()?;
+    let cfg = store::load_config(&paths.config_file)?;
+
+    let names = store::list_queues(&paths)?;
+    let name_width = names.iter().map(|n| n.len()).max().unwrap_or(4).max(4);
+
+    println!(
+        "  {:<name_w$}  {:>6}  Mode",
+        "Name",
+        "Videos",
+        name_w = name_width
+    );
+    for name in &names {
+        let count = store::with_queue_read(&paths, name, |videos| videos.len())?;
+        let mode = cfg.mode_for(name).name();
+        println!("  {name:<name_w$}  {count:>6}  {mode}", name_w = name_width);
+    }
+
+    Ok(())
+}
+
+pub fn queue_create(name: &str, mode: Option<&str>) -> Result<()> {
+    let paths = paths::AppPaths::init()?;
+
+    // Validate the mode before creating anything
+    let mode = mode.map(parse_mode).transpose()?;
+
+    store::create_queue(&paths, name)?;
+
+    if let Some(mode) = mode {
+        let mut cfg = store::load_config(&paths.config_file)?;
+        cfg.queues.insert(
+            name.to_string(),
+            QueueConfig {
+                mode: Some(mode),
+                ..Default::default()
+            },
+        );
+        store::save_config(&paths.config_file, &cfg)?;
+    }
+
+    println!("{} {name}", "Created queue:".green());
+    Ok(())
+}
+
+pub fn queue_rename(name: &str, new_name: &str) -> Result<()> {
+    let paths = paths::AppPaths::init()?;
+
+    store::rename_queue(&paths, name, new_name)?;
+
+    // Carry per-queue settings over to the new name
+    let mut cfg = store::load_config(&paths.config_file)?;
+    if let Some(settings) = cfg.queues.remove(name) {
+        cfg.queues.insert(new_name.to_string(), settings);
+        store::save_config(&paths.config_file, &cfg)?;
+    }
+
+    // Keep subscriptions syncing into the renamed queue
+    store::with_subscriptions(&paths, |subscriptions| {
+        for sub in subscriptions.iter_mut().filter(|s| s.queue == name) {
+            sub.queue = new_name.to_string();
+        }
+        Ok(())
+    })?;
+
+    println!("{} {name} -> {new_name}", "Renamed queue:".green());
+    Ok(())
+}
+
+pub fn queue_delete(name: &str, force: bool) -> Result<()> {
+    let paths = paths::AppPaths::init()?;
+
+    if !force {
+        let count = store::with_queue_read(&paths, name, |videos| videos.len())?;
+        if count > 0 {
+            bail!("queue '{name}' still has {count} video(s). Use --force to delete it anyway.");
+        }
+    }
+
+    let videos = store::delete_queue(&paths, name)?;
+
+    // Videos dropped with the queue count as skipped
+    for video in &videos {
+        let event = Event {
+            timestamp: Utc::now(),
+            action: Action::Skipped,
+            video_id: video.id.clone(),
+            time_in_queue_sec: None,
+            queue: Some(name.to_string()),
+        };
+        store::log_event(&paths, &event)?;
+    }
+
+    let mut cfg = store::load_config(&paths.config_file)?;
+    if cfg.queues.remove(name).is_some() {
+        store::save_config(&paths.config_file, &cfg)?;
+    }
+
+    println!("{} {name}", "Deleted queue:".red());
+    Ok(())
+}
+
+/// Moves a video from one queue to the end of another, keeping its
+/// original `added_at`.
+pub fn queue_move(target: &str, from: Option<&str>, to: &str) -> Result<()> {
+    let paths = paths::AppPaths::init()?;
+    let from = from.unwrap_or(DEFAULT_QUEUE);
+
+    let target_id = youtube::extract_video_id(target)?;
+
+    store::with_queue_pair(&paths, from, to, |source, destination| {
+        let idx = source
+            .iter()
+            .position(|v| v.id == target_id)
+            .ok_or_else(|| {
+                anyhow::anyhow!("video with ID '{target_id}' not found in queue '{from}'")
+            })?;
+
+        if destination.iter().any(|v| v.id == target_id) {
+            bail!("video '{target_id}' is already in queue '{to}'");
+        }
+
+        let video = source.remove(idx);
+        destination.push(video);
+        Ok(())
+    })?;
+
+    println!("{} {target_id} ({from} -> {to})", "Moved:".blue());
+    Ok(())
+}
+
+pub fn subscribe(channel: &str, queue: Option<&str>) -> Result<()> {
+    let paths = paths::AppPaths::init()?;
+    let cfg = store::load_config(&paths.config_file)?;
+    let queue = queue.unwrap_or(DEFAULT_QUEUE);
+
+    if !store::queue_exists(&paths, queue)? {
+        bail!("queue '{queue}' does not exist. Create it with `ytq queue create {queue}`.");
+    }
+
+    let channel_ref = youtube::extract_channel_ref(channel).ok_or_else(|| {
+        anyhow::anyhow!(
+            "'{channel}' is not a channel. Supported formats:\n  \
+             - youtube.com/@handle (or just @handle)\n  \
+             - youtube.com/channel/UC...\n  \
+             - youtube.com/c/name, youtube.com/user/name"
+        )
+    })?;
+
+    // Handles and legacy names need the channel page to find the ID
+    if !matches!(channel_ref, youtube::ChannelRef::Id(_)) && cfg.offline {
+        bail!(
+            "resolving '{channel}' requires loading the channel page, but online features are disabled.\n\
+             Enable them with `ytq config offline false`, or subscribe with the \
+             channel's youtube.com/channel/UC... URL."
+        );
+    }
+    let channel_id = feeds::resolve_channel_id(cfg.feed_base_url(), &channel_ref)?;
+    let handle = match channel_ref {
+        youtube::ChannelRef::Handle(handle) => Some(handle),
+        _ => None,
+    };
+
+    let existing = store::with_subscriptions(&paths, |subscriptions| {
+        if let Some(sub) = subscriptions
+            .iter_mut()
+            .find(|s| s.channel_id == channel_id)
+        {
+            let previous = std::mem::replace(&mut sub.queue, queue.to_string());
+            return Ok(Some((sub.label(), previous)));
+        }
+        subscriptions.push(Subscription::new(
+            channel_id.clone(),
+            handle.clone(),
+            queue.to_string(),
+        ));
+        Ok(None)
+    })?;
+
+    match existing {
+        Some((label, previous)) if previous == queue => {
+            println!("{} {label}", "Already subscribed:".yellow());
+        }
+        Some((label, _)) => {
+            println!("{} {label} now syncs into '{queue}'", "Updated:".green());
+        }
+        None => {
+            println!("{} {channel_id}", "Subscribed:".green());
+            println!(
+                "  Uploads published from now on are added to '{queue}' by {}.",
+                "`ytq sync`".bold()
+            );
+        }
+    }
+
+    Ok(())
+}
+
+pub fn unsubscribe(channel: &str) -> Result<()> {
+    let paths = paths::AppPaths::init()?;
+
+    // Accept the same URLs as subscribe, as well as IDs, handles and titles
+    let query = match youtube::extract_channel_ref(channel) {
+        Some(youtube::ChannelRef::Id(id)) => id,
+        Some(youtube::ChannelRef::Handle(handle)) => format!("@{handle}"),
+        _ => channel.to_string(),
+    };
+
+    let removed = store::with_subscriptions(&paths, |subscriptions| {
+        let index = subscriptions
+            .iter()
+            .position(|s| s.matches(&query))
+            .ok_or_else(|| anyhow::anyhow!("not subscribed to '{channel}'"))?;
+        Ok(subscriptions.remove(index))
+    })?;
+
+    println!("{} {}", "Unsubscribed:".green(), removed.label());
+    Ok(())
+}
+
+pub fn subscriptions() -> Result<()> {
+    let paths = paths::AppPaths::init()?;
+    let subscriptions = store::load_subscriptions(&paths)?;
+
+    if subscriptions.is_empty() {
+        println!("No subscriptions. Follow a channel with `ytq subscribe <channel>`.");
+        return Ok(());
+    }
+
+    let labels: Vec<String> = subscriptions.iter().map(|s| s.label()).collect();
+    let label_width = labels.iter().map(|l| l.len()).max().unwrap_or(7).max(7);
+
+    println!(
+        "  {:<label_w$}  {:<24}  {:<12}  Last sync",
+        "Channel",
+        "ID",
+        "Queue",
+        label_w = label_width
+    );
+    for (sub, label) in subscriptions.iter().zip(&labels) {
+        let last_sync = sub.last_synced_at.map_or_else(
+            || "never".to_string(),
+            |t| t.with_timezone(&Local).format("%Y-%m-%d %H:%M").to_string(),
+        );
+        println!(
+            "  {label:<label_w$}  {:<24}  {:<12}  {last_sync}",
+            sub.channel_id,
+            sub.queue,
+            label_w = label_width
+        );
+    }
+
+    Ok(())
+}
+
+/// Uploads found for one subscription during `ytq sync`.
+struct SyncedFeed {
+    channel_id: String,
+    title: Option<String>,
+    queue: String,
+    /// `(video_id, title)` of uploads not offered before, oldest first.
+    new_uploads: Vec<(String, String)>,
+    seen_ids: Vec<String>,
+}
+
+pub fn sync(queue: Option<&str>) -> Result<()> {
+    let paths = paths::AppPaths::init()?;
+    let cfg = store::load_config(&paths.config_file)?;
+
+    if cfg.offline {
+        bail!(
+            "syncing subscriptions downloads each channel's public RSS feed, but online features are disabled.\n\
+             Enable them with `ytq config offline false` (no API key needed)."
+        );
+    }
+
+    // The global --queue selector limits the sync to subscriptions feeding that queue
+    let subscriptions: Vec<Subscription> = store::load_subscriptions(&paths)?
+        .into_iter()
+        .filter(|s| queue.is_none_or(|q| s.queue == q))
+        .collect();
+
+    if subscriptions.is_empty() {
+        println!("No subscriptions to sync. Follow a channel with `ytq subscribe <channel>`.");
+        return Ok(());
+    }
+
+    // Download feeds without holding the lock
+    let mut synced = Vec::new();
+    let mut failures = 0;
+    for sub in &subscriptions {
+        match feeds::fetch_channel_feed(cfg.feed_base_url(), &sub.channel_id) {
+            Ok(feed) => {
+                // Feeds list newest first; queue older uploads first
+                let new_uploads = feed
+                    .entries
+                    .iter()
+                    .rev()
+                    .filter(|e| sub.is_new_upload(&e.video_id, e.published))
+                    .map(|e| (e.video_id.clone(), e.title.clone()))
+                    .collect();
+                synced.push(SyncedFeed {
+                    channel_id: sub.channel_id.clone(),
+                    title: feed.title,
+                    queue: sub.queue.clone(),
+                    new_uploads,
+                    seen_ids: feed.entries.into_iter().map(|e| e.video_id).collect(),
+                });
+            }
+            Err(e) => {
+                eprintln!("{} {}: {e:#}", "Failed:".red(), sub.label());
+                failures += 1;
+            }
+        }
+    }
+
+    let annotations = NewVideoAnnotations {
+        tags: &[],
+        note: None,
+        priority: Priority::default(),
     };
-    store::log_event(&paths.history_dir, &event)?;
 
-    println!("{} {}", "Opening:".blue(), video.url);
-    open::that(&video.url)?;
+    let mut total_added = 0;
+    let mut completed = Vec::new();
+    for feed in synced {
+        let label = feed
+            .title
+            .clone()
+            .unwrap_or_else(|| feed.channel_id.clone());
+
+        if !store::queue_exists(&paths, &feed.queue)? {
+            eprintln!(
+                "{} {label}: queue '{}' no longer exists. Re-subscribe with --queue to fix.",
+                "Skipped:".yellow(),
+                feed.queue
+            );
+            failures += 1;
+            continue;
+        }
+
+        let new_links: Vec<youtube::VideoLink> = feed
+            .new_uploads
+            .iter()
+            .map(|(id, _)| youtube::VideoLink::from_id(id.clone()))
+            .collect();
+        let added = if new_links.is_empty() {
+            Vec::new()
+        } else {
+            enqueue(&paths, &feed.queue, &new_links, &annotations)?.added
+        };
+        total_added += added.len();
+
+        if !added.is_empty() {
+            println!("  {label}: {} new -> '{}'", added.len(), feed.queue);
+            for (id, title) in feed.new_uploads.iter().filter(|(id, _)| added.contains(id)) {
+                println!("    {} {title}", id.dimmed());
+            }
+        }
+        completed.push(feed);
+    }
+
+    // Remember everything seen so removed videos are never offered again
+    let now = Utc::now();
+    store::with_subscriptions(&paths, |subscriptions| {
+        for feed in &completed {
+            if let Some(sub) = subscriptions
+                .iter_mut()
+                .find(|s| s.channel_id == feed.channel_id)
+            {
+                sub.offered.extend(feed.seen_ids.iter().cloned());
+                sub.last_synced_at = Some(now);
+                if feed.title.is_some() {
+                    sub.title.clone_from(&feed.title);
+                }
+            }
+        }
+        Ok(())
+    })?;
+
+    if total_added == 0 {
+        println!("No new uploads.");
+    } else {
+        println!(
+            "{} {total_added} new video(s) from {} channel(s).",
+            "Synced:".green(),
+            completed.len()
+        );
+    }
+
+    if failures > 0 {
+        bail!("{failures} subscription(s) could not be synced");
+    }
+
+    Ok(())
+}
+
+/// Sets or clears (with a time of 0) where a queued video starts playing.
+pub fn seek(target: &str, time: &str, queue: Option<&str>) -> Result<()> {
+    let paths = paths::AppPaths::init()?;
+    let queue = queue.unwrap_or(DEFAULT_QUEUE);
+
+    // Parse input before acquiring lock
+    let target_id = youtube::extract_video_id(target)?;
+    let seconds = youtube::parse_timestamp(time).ok_or_else(|| {
+        anyhow::anyhow!("invalid time '{time}': use seconds (90), 1h2m3s, or 1:02:03")
+    })?;
+
+    store::with_queue(&paths, queue, |videos| {
+        let idx = find_index(videos, &target_id)?;
+        videos[idx].start_seconds = Some(seconds).filter(|&s| s > 0);
+        Ok(())
+    })?;
+
+    if seconds == 0 {
+        println!(
+            "{} {target_id} starts from the beginning",
+            "Updated:".green()
+        );
+    } else {
+        println!(
+            "{} {target_id} starts at {}",
+            "Updated:".green(),
+            youtube_api::format_duration(seconds)
+        );
+    }
 
     Ok(())
 }
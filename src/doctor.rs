@@ -0,0 +1,499 @@
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use crate::sqlite::{self, BadRow};
+use crate::store::{self, Envelope, Schema};
+
+use anyhow::{Context, Result};
+use serde::de::DeserializeOwned;
+use serde_json::{Map, Value};
+
+/// Temp files younger than this may belong to a write still in progress.
+const STALE_TEMP_AGE: Duration = Duration::from_secs(60);
+
+/// How `ytq doctor --repair` fixes a damaged file.
+#[derive(Debug, PartialEq)]
+pub enum Repair {
+    /// Rewrite the file with only the parts that still parse. `unit` names
+    /// what was counted: entries, fields or lines.
+    Salvage {
+        contents: String,
+        kept: usize,
+        dropped: usize,
+        unit: &'static str,
+    },
+    /// Nothing is salvageable; move the file aside and start empty.
+    Reset,
+    /// A temp file left behind by an interrupted write.
+    RemoveTemp,
+    /// Delete the database rows that no longer parse.
+    DeleteRows(Vec<BadRow>),
+}
+
+impl Repair {
+    pub fn describe(&self) -> String {
+        match self {
+            Repair::Salvage {
+                kept,
+                dropped,
+                unit,
+                ..
+            } => format!("keep {kept} readable {unit} and drop {dropped}"),
+            Repair::Reset => "move it aside and start empty".to_string(),
+            Repair::RemoveTemp => "delete it".to_string(),
+            Repair::DeleteRows(rows) => format!("delete {} unreadable row(s)", rows.len()),
+        }
+    }
+}
+
+/// A data file that failed validation.
+#[derive(Debug)]
+pub struct Finding {
+    pub path: PathBuf,
+    pub problems: Vec<String>,
+    pub repair: Repair,
+}
+
+impl Finding {
+    /// Applies the repair. The damaged original is kept as a `.corrupt` copy
+    /// whose path is returned; temp files are simply deleted.
+    pub fn repair(&self) -> Result<Option<PathBuf>> {
+        // The config or the database may be replaced under an open backend
+        store::forget_opened();
+        match &self.repair {
+            Repair::RemoveTemp => {
+                fs::remove_file(&self.path)
+                    .with_context(|| format!("failed to delete {}", self.path.display()))?;
+                Ok(None)
+            }
+            Repair::Reset => store::quarantine(&self.path).map(Some),
+            Repair::Salvage { contents, .. } => {
+                // Copy rather than move, so the file is never missing
+                let backup = store::quarantine_path(&self.path)?;
+                fs::copy(&self.path, &backup)
+                    .with_context(|| format!("failed to back up {}", self.path.display()))?;
+                store::write_atomic(&self.path, contents.as_bytes())?;
+                Ok(Some(backup))
+            }
+            Repair::DeleteRows(rows) => {
+                let backup = store::quarantine_path(&self.path)?;
+                fs::copy(&self.path, &backup)
+                    .with_context(|| format!("failed to back up {}", self.path.display()))?;
+                sqlite::delete_rows(&self.path, rows)?;
+                Ok(Some(backup))
+            }
+        }
+    }
+}
+
+/// Checks a JSON array, stored in an envelope when `schema` is given; a
+/// repair keeps the elements that parse as `E`.
+pub fn check_list<E: DeserializeOwned>(
+    path: &Path,
+    schema: Option<Schema>,
+) -> Result<Option<Finding>> {
+    check_json::<Vec<E>>(path, schema, |value| {
+        let Value::Array(items) = value else {
+            return None;
+        };
+        let total = items.len();
+        let kept: Vec<Value> = items.into_iter().filter(parses_as::<E>).collect();
+        Some((
+            Value::Array(kept.clone()),
+            kept.len(),
+            total - kept.len(),
+            "entries",
+        ))
+    })
+}
+
+/// Checks a JSON object keyed by ID; a repair keeps the values that parse as `E`.
+pub fn check_map<E: DeserializeOwned>(
+    path: &Path,
+    schema: Option<Schema>,
+) -> Result<Option<Finding>> {
+    check_json::<std::collections::HashMap<String, E>>(path, schema, |value| {
+        let Value::Object(entries) = value else {
+            return None;
+        };
+        let total = entries.len();
+        let kept: Map<String, Value> = entries
+            .into_iter()
+            .filter(|(_, v)| parses_as::<E>(v))
+            .collect();
+        let kept_len = kept.len();
+        Some((Value::Object(kept), kept_len, total - kept_len, "entries"))
+    })
+}
+
+/// Checks a JSON object such as the config; a repair keeps the fields that
+/// still parse, leaving the rest at their defaults.
+pub fn check_object<T: DeserializeOwned>(
+    path: &Path,
+    schema: Option<Schema>,
+) -> Result<Option<Finding>> {
+    check_json::<T>(path, schema, |value| {
+        let Value::Object(fields) = value else {
+            return None;
+        };
+        let total = fields.len();
+        let mut kept = Map::new();
+        if !parses_as::<T>(&Value::Object(kept.clone())) {
+            return None;
+        }
+        for (key, field) in fields {
+            let mut candidate = kept.clone();
+            candidate.insert(key, field);
+            if parses_as::<T>(&Value::Object(candidate.clone())) {
+                kept = candidate;
+            }
+        }
+        let kept_len = kept.len();
+        Some((Value::Object(kept), kept_len, total - kept_len, "fields"))
+    })
+}
+
+fn check_json<T: DeserializeOwned>(
+    path: &Path,
+    schema: Option<Schema>,
+    salvage: impl FnOnce(Value) -> Option<(Value, usize, usize, &'static str)>,
+) -> Result<Option<Finding>> {
+    let Some(text) = read_if_exists(path)? else {
+        return Ok(None);
+    };
+    let value: Value = match serde_json::from_str(&text) {
+        Ok(value) => value,
+        Err(e) => {
+            return Ok(Some(Finding {
+                path: path.to_path_buf(),
+                problems: vec![e.to_string()],
+                repair: Repair::Reset,
+            }));
+        }
+    };
+
+    // Older formats are checked as they will be after loading upgrades them
+    let (data, version) = match schema {
+        Some(schema) => store::upgrade(path, schema, value)?,
+        None => (value, 0),
+    };
+    let error = match schema {
+        // Parsing the text itself keeps the line and column
+        Some(schema) if version == schema.current_version() => {
+            serde_json::from_str::<Envelope<T>>(&text).err()
+        }
+        Some(_) => T::deserialize(&data).err(),
+        None => serde_json::from_str::<T>(&text).err(),
+    };
+    let Some(error) = error else {
+        return Ok(None);
+    };
+
+    let repair = match salvage(data) {
+        Some((value, kept, dropped, unit)) if dropped > 0 => Repair::Salvage {
+            contents: match schema {
+                Some(schema) => store::to_versioned_json(schema, &value)?,
+                None => serde_json::to_string_pretty(&value)?,
+            },
+            kept,
+            dropped,
+            unit,
+        },
+        _ => Repair::Reset,
+    };
+
+    Ok(Some(Finding {
+        path: path.to_path_buf(),
+        problems: vec![error.to_string()],
+        repair,
+    }))
+}
+
+/// Checks every line of a history partition.
+pub fn check_history(path: &Path) -> Result<Option<Finding>> {
+    let Some(data) = read_if_exists(path)? else {
+        return Ok(None);
+    };
+
+    let mut problems = Vec::new();
+    let mut good = String::new();
+    let mut kept = 0;
+    let mut version = 0;
+    for (n, line) in data.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        match store::parse_history_line(path, &mut version, line) {
+            Ok(event) => {
+                good.push_str(line);
+                good.push('\n');
+                kept += usize::from(event.is_some());
+            }
+            Err(e) => match e.downcast_ref::<serde_json::Error>() {
+                Some(e) => {
+                    // serde reports the position within the line; use the file's
+                    let message = e.to_string();
+                    let message = message.split(" at line ").next().unwrap_or_default();
+                    problems.push(format!("line {}, column {}: {message}", n + 1, e.column()));
+                }
+                None => problems.push(format!("line {}: {e}", n + 1)),
+            },
+        }
+    }
+
+    if problems.is_empty() {
+        return Ok(None);
+    }
+    let dropped = problems.len();
+    Ok(Some(Finding {
+        path: path.to_path_buf(),
+        problems,
+        repair: Repair::Salvage {
+            contents: good,
+            kept,
+            dropped,
+            unit: "lines",
+        },
+    }))
+}
+
+/// Checks the SQLite database: SQLite's own integrity check plus every
+/// JSON row. A damaged database file can only be moved aside; unreadable
+/// rows are deleted.
+pub fn check_database(path: &Path) -> Result<Option<Finding>> {
+    if !path.exists() {
+        return Ok(None);
+    }
+    let (problems, rows) = match sqlite::check(path) {
+        Ok(result) => result,
+        // Other errors, like a newer format, are not damage
+        Err(e) if sqlite::is_corrupt(&e) => (vec![format!("{e:#}")], Vec::new()),
+        Err(e) => return Err(e),
+    };
+    if !problems.is_empty() {
+        return Ok(Some(Finding {
+            path: path.to_path_buf(),
+            problems,
+            repair: Repair::Reset,
+        }));
+    }
+    if rows.is_empty() {
+        return Ok(None);
+    }
+    Ok(Some(Finding {
+        path: path.to_path_buf(),
+        problems: rows
+            .iter()
+            .map(|row| format!("{} {}: {}", row.table, row.key, row.error))
+            .collect(),
+        repair: Repair::DeleteRows(rows),
+    }))
+}
+
+/// Finds temp files in `dir` left behind by interrupted atomic writes.
+pub fn stale_temp_files(dir: &Path) -> Vec<Finding> {
+    let now = SystemTime::now();
+    read_dir_sorted(dir)
+        .into_iter()
+        .filter(|path| {
+            let name = file_name(path);
+            name.starts_with('.') && name.ends_with(".tmp")
+        })
+        .filter(|path| {
+            // Unknown age counts as stale
+            fs::metadata(path)
+                .and_then(|m| m.modified())
+                .ok()
+                .and_then(|modified| now.duration_since(modified).ok())
+                .is_none_or(|age| age >= STALE_TEMP_AGE)
+        })
+        .map(|path| Finding {
+            path,
+            problems: vec!["leftover temp file from an interrupted write".to_string()],
+            repair: Repair::RemoveTemp,
+        })
+        .collect()
+}
+
+/// Lists `.corrupt` copies kept in `dir` by earlier quarantines.
+pub fn quarantined_files(dir: &Path) -> Vec<PathBuf> {
+    read_dir_sorted(dir)
+        .into_iter()
+        .filter(|path| file_name(path).ends_with(".corrupt"))
+        .collect()
+}
+
+/// Returns the files in `dir` with the given extension, sorted.
+pub fn files_with_extension(dir: &Path, extension: &str) -> Vec<PathBuf> {
+    read_dir_sorted(dir)
+        .into_iter()
+        .filter(|path| path.extension().is_some_and(|ext| ext == extension))
+        .collect()
+}
+
+fn read_dir_sorted(dir: &Path) -> Vec<PathBuf> {
+    let mut paths: Vec<PathBuf> = fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.is_file())
+        .collect();
+    paths.sort();
+    paths
+}
+
+fn read_if_exists(path: &Path) -> Result<Option<String>> {
+    match fs::read_to_string(path) {
+        Ok(data) => Ok(Some(data)),
+        Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e).with_context(|| format!("failed to read {}", path.display())),
+    }
+}
+
+fn parses_as<T: DeserializeOwned>(value: &Value) -> bool {
+    T::deserialize(value).is_ok()
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{Config, Video};
+    use crate::test_util::TestDir;
+
+    const VIDEO: &str = r#"{"id": "dQw4w9WgXcQ", "url": "https://youtu.be/dQw4w9WgXcQ", "added_at": "2026-01-01T00:00:00Z"}"#;
+
+    #[test]
+    fn valid_and_missing_files_pass() {
+        let dir = TestDir::new("valid");
+        let path = dir.join("queue.json");
+        assert!(
+            check_list::<Video>(&path, Some(Schema::Queue))
+                .unwrap()
+                .is_none()
+        );
+
+        fs::write(&path, format!("{{\"version\": 1, \"data\": [{VIDEO}]}}")).unwrap();
+        assert!(
+            check_list::<Video>(&path, Some(Schema::Queue))
+                .unwrap()
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn list_repair_keeps_readable_entries_and_backs_up() {
+        let dir = TestDir::new("list");
+        let path = dir.join("queue.json");
+        fs::write(
+            &path,
+            format!("{{\"version\": 1, \"data\": [{VIDEO}, {{\"id\": 42}}]}}"),
+        )
+        .unwrap();
+
+        let finding = check_list::<Video>(&path, Some(Schema::Queue))
+            .unwrap()
+            .unwrap();
+        assert!(finding.problems[0].contains("line 1 column"));
+        assert!(matches!(
+            finding.repair,
+            Repair::Salvage {
+                kept: 1,
+                dropped: 1,
+                ..
+            }
+        ));
+
+        let backup = finding.repair().unwrap().unwrap();
+        assert!(backup.exists());
+        let envelope: Envelope<Vec<Video>> =
+            serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(envelope.data.len(), 1);
+        assert!(
+            check_list::<Video>(&path, Some(Schema::Queue))
+                .unwrap()
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn older_formats_are_checked_after_upgrading() {
+        let dir = TestDir::new("legacy");
+        let path = dir.join("queue.json");
+        fs::write(&path, format!("[{VIDEO}]")).unwrap();
+        assert!(
+            check_list::<Video>(&path, Some(Schema::Queue))
+                .unwrap()
+                .is_none()
+        );
+
+        fs::write(&path, format!("{{\"version\": 99, \"data\": [{VIDEO}]}}")).unwrap();
+        let err = check_list::<Video>(&path, Some(Schema::Queue)).unwrap_err();
+        assert!(err.to_string().contains("newer version of ytq"));
+    }
+
+    #[test]
+    fn broken_syntax_is_reset() {
+        let dir = TestDir::new("reset");
+        let path = dir.join("metadata.json");
+        fs::write(&path, "{\"abc\": ").unwrap();
+
+        let finding = check_map::<String>(&path, None).unwrap().unwrap();
+        assert_eq!(finding.repair, Repair::Reset);
+        finding.repair().unwrap();
+        assert!(!path.exists());
+        assert_eq!(quarantined_files(&dir).len(), 1);
+    }
+
+    #[test]
+    fn object_repair_drops_only_bad_fields() {
+        let dir = TestDir::new("object");
+        let path = dir.join("config.json");
+        fs::write(&path, r#"{"mode": "sideways", "offline": true}"#).unwrap();
+
+        let finding = check_object::<Config>(&path, Some(Schema::Config))
+            .unwrap()
+            .unwrap();
+        let Repair::Salvage { contents, .. } = &finding.repair else {
+            panic!("expected a salvage, got {:?}", finding.repair);
+        };
+        let config: Config = serde_json::from_str(contents).unwrap();
+        assert!(config.offline);
+    }
+
+    #[test]
+    fn history_reports_each_bad_line() {
+        let dir = TestDir::new("history");
+        let path = dir.join("2026-03.jsonl");
+        let event = r#"{"timestamp":"2026-03-01T12:00:00Z","action":"Watched","video_id":"a"}"#;
+        fs::write(
+            &path,
+            format!("{event}\nnot json\n\n{event}\n{{\"timestamp\""),
+        )
+        .unwrap();
+
+        let finding = check_history(&path).unwrap().unwrap();
+        assert_eq!(finding.problems.len(), 2);
+        assert!(finding.problems[0].starts_with("line 2, column "));
+        assert!(finding.problems[1].starts_with("line 5, column "));
+
+        finding.repair().unwrap();
+        assert!(check_history(&path).unwrap().is_none());
+        assert_eq!(fs::read_to_string(&path).unwrap().lines().count(), 2);
+    }
+
+    #[test]
+    fn fresh_temp_files_are_left_alone() {
+        let dir = TestDir::new("temp");
+        fs::write(dir.join(".queue.json.123.tmp"), "[").unwrap();
+        assert!(stale_temp_files(&dir).is_empty());
+    }
+}
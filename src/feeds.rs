@@ -0,0 +1,254 @@
+use std::sync::LazyLock;
+
+use crate::youtube::ChannelRef;
+
+use anyhow::{Context, Result, bail};
+use chrono::{DateTime, Utc};
+use regex::Regex;
+
+/// Matches each `<entry>` element of an Atom feed.
+static ENTRY_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?s)<entry>(.*?)</entry>").unwrap());
+
+static VIDEO_ID_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"<yt:videoId>([a-zA-Z0-9_-]{11})</yt:videoId>").unwrap());
+
+static TITLE_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?s)<title>(.*?)</title>").unwrap());
+
+static PUBLISHED_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"<published>([^<]+)</published>").unwrap());
+
+/// Channel pages name their ID in the canonical link and in embedded JSON.
+static PAGE_CHANNEL_ID_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r#"(?:<link rel="canonical" href="[^"]*/channel/|"externalId":")(UC[a-zA-Z0-9_-]{22})"#,
+    )
+    .unwrap()
+});
+
+/// A channel's public uploads feed.
+#[derive(Debug)]
+pub struct ChannelFeed {
+    pub title: Option<String>,
+    /// Most recent uploads, newest first (YouTube serves the latest 15).
+    pub entries: Vec<FeedEntry>,
+}
+
+#[derive(Debug)]
+pub struct FeedEntry {
+    pub video_id: String,
+    pub title: String,
+    pub published: DateTime<Utc>,
+}
+
+/// Downloads and parses `{base_url}/feeds/videos.xml?channel_id={channel_id}`.
+/// No API key is needed.
+pub fn fetch_channel_feed(base_url: &str, channel_id: &str) -> Result<ChannelFeed> {
+    let url = format!("{base_url}/feeds/videos.xml?channel_id={channel_id}");
+    let body = get_text(&url).with_context(|| format!("failed to fetch feed for {channel_id}"))?;
+    parse_feed(&body).with_context(|| format!("failed to read feed for {channel_id}"))
+}
+
+/// Resolves a handle or legacy channel URL to a channel ID by loading the
+/// channel page. Channel IDs are returned as-is without a request.
+pub fn resolve_channel_id(base_url: &str, channel: &ChannelRef) -> Result<String> {
+    if let ChannelRef::Id(id) = channel {
+        return Ok(id.clone());
+    }
+
+    let url = format!("{base_url}{}", channel.page_path());
+    let page = get_text(&url).with_context(|| format!("failed to load channel page {url}"))?;
+    parse_channel_id_from_page(&page).with_context(|| {
+        format!(
+            "could not find the channel ID on {url}.\n\
+             Subscribe with the channel's /channel/UC... URL instead."
+        )
+    })
+}
+
+/// Performs a GET request and returns the body, mapping errors like
+/// `youtube_api` does.
+fn get_text(url: &str) -> Result<String> {
+    // ureq 3.x returns Err for non-2xx status codes
+    let mut response = match ureq::get(url).call() {
+        Ok(resp) => resp,
+        Err(ureq::Error::StatusCode(404)) => {
+            bail!("not found (HTTP 404). Check that the channel exists.");
+        }
+        Err(ureq::Error::StatusCode(code)) => {
+            bail!("server returned HTTP {code}");
+        }
+        Err(e) => {
+            return Err(anyhow::anyhow!(e).context("failed to reach YouTube"));
+        }
+    };
+
+    response
+        .body_mut()
+        .read_to_string()
+        .context("failed to read response body")
+}
+
+/// Parses a YouTube Atom feed. Entries without a video ID or a valid
+/// `<published>` date are skipped.
+pub fn parse_feed(xml: &str) -> Result<ChannelFeed> {
+    if !xml.contains("<feed") {
+        bail!("response is not an Atom feed");
+    }
+
+    // The channel title is the first <title>, before any entry
+    let header = xml.split("<entry>").next().unwrap_or_default();
+    let title = TITLE_RE
+        .captures(header)
+        .map(|caps| unescape_xml(caps[1].trim()));
+
+    let entries = ENTRY_RE
+        .captures_iter(xml)
+        .filter_map(|caps| {
+            let entry = &caps[1];
+            let video_id = VIDEO_ID_RE.captures(entry)?[1].to_string();
+            let published = PUBLISHED_RE.captures(entry)?[1]
+                .parse::<DateTime<Utc>>()
+                .ok()?;
+            let title = TITLE_RE
+                .captures(entry)
+                .map(|caps| unescape_xml(caps[1].trim()))
+                .unwrap_or_default();
+            Some(FeedEntry {
+                video_id,
+                title,
+                published,
+            })
+        })
+        .collect();
+
+    Ok(ChannelFeed { title, entries })
+}
+
+/// Extracts the channel ID from a channel page's HTML.
+pub fn parse_channel_id_from_page(html: &str) -> Option<String> {
+    PAGE_CHANNEL_ID_RE
+        .captures(html)
+        .map(|caps| caps[1].to_string())
+}
+
+/// Decodes the XML entities YouTube uses in titles.
+fn unescape_xml(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+
+    const FEED: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<feed xmlns:yt="http://www.youtube.com/xml/schemas/2015" xmlns="http://www.w3.org/2005/Atom">
+ <yt:channelId>UCuAXFkgsw1L7xaCfnd5JJOw</yt:channelId>
+ <title>Tom &amp; Jerry's Channel</title>
+ <entry>
+  <id>yt:video:dQw4w9WgXcQ</id>
+  <yt:videoId>dQw4w9WgXcQ</yt:videoId>
+  <title>Newest &lt;upload&gt;</title>
+  <published>2026-02-01T12:00:00+00:00</published>
+ </entry>
+ <entry>
+  <yt:videoId>9bZkp7q19f0</yt:videoId>
+  <title>Older upload</title>
+  <published>2026-01-15T08:30:00+00:00</published>
+ </entry>
+ <entry>
+  <yt:videoId>brokenentry</yt:videoId>
+  <title>No date</title>
+ </entry>
+</feed>"#;
+
+    #[test]
+    fn parse_feed_reads_title_and_entries() {
+        let feed = parse_feed(FEED).unwrap();
+        assert_eq!(feed.title.as_deref(), Some("Tom & Jerry's Channel"));
+        assert_eq!(feed.entries.len(), 2);
+        assert_eq!(feed.entries[0].video_id, "dQw4w9WgXcQ");
+        assert_eq!(feed.entries[0].title, "Newest <upload>");
+        assert_eq!(
+            feed.entries[1].published,
+            "2026-01-15T08:30:00Z".parse::<DateTime<Utc>>().unwrap()
+        );
+    }
+
+    #[test]
+    fn parse_feed_rejects_non_feed() {
+        assert!(parse_feed("<html><body>Consent</body></html>").is_err());
+    }
+
+    #[test]
+    fn parse_feed_empty_channel() {
+        let feed = parse_feed("<feed><title>Quiet</title></feed>").unwrap();
+        assert_eq!(feed.title.as_deref(), Some("Quiet"));
+        assert!(feed.entries.is_empty());
+    }
+
+    #[test]
+    fn parse_channel_id_from_canonical_link_or_json() {
+        let html = r#"<link rel="canonical" href="https://www.youtube.com/channel/UCuAXFkgsw1L7xaCfnd5JJOw">"#;
+        assert_eq!(
+            parse_channel_id_from_page(html).as_deref(),
+            Some("UCuAXFkgsw1L7xaCfnd5JJOw")
+        );
+        let html = r#"{"externalId":"UCuAXFkgsw1L7xaCfnd5JJOw","title":"x"}"#;
+        assert!(parse_channel_id_from_page(html).is_some());
+        assert!(parse_channel_id_from_page("<html></html>").is_none());
+    }
+
+    /// Serves one canned HTTP response on a local port and returns its base
+    /// URL along with a handle yielding the request line that was received.
+    fn serve_once(status: &str, body: &'static str) -> (String, std::thread::JoinHandle<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        let status = status.to_string();
+
+        let handle = std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut buf = [0u8; 4096];
+            let n = stream.read(&mut buf).unwrap();
+            let request = String::from_utf8_lossy(&buf[..n]).to_string();
+            write!(
+                stream,
+                "HTTP/1.1 {status}\r\nContent-Type: application/atom+xml\r\n\
+                 Content-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                body.len()
+            )
+            .unwrap();
+            request.lines().next().unwrap_or_default().to_string()
+        });
+
+        (base_url, handle)
+    }
+
+    #[test]
+    fn fetch_channel_feed_from_local_server() {
+        let (base_url, server) = serve_once("200 OK", FEED);
+        let feed = fetch_channel_feed(&base_url, "UCuAXFkgsw1L7xaCfnd5JJOw").unwrap();
+        assert_eq!(feed.entries.len(), 2);
+
+        let request_line = server.join().unwrap();
+        assert!(
+            request_line.starts_with("GET /feeds/videos.xml?channel_id=UCuAXFkgsw1L7xaCfnd5JJOw ")
+        );
+    }
+
+    #[test]
+    fn fetch_channel_feed_reports_missing_channel() {
+        let (base_url, server) = serve_once("404 Not Found", "");
+        let err = fetch_channel_feed(&base_url, "UCuAXFkgsw1L7xaCfnd5JJOw").unwrap_err();
+        assert!(format!("{err:#}").contains("HTTP 404"));
+        server.join().unwrap();
+    }
+}
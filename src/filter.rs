@@ -0,0 +1,378 @@
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+use crate::models::{Video, VideoMeta};
+use crate::stats::DateRange;
+use crate::youtube;
+
+use anyhow::{Result, anyhow, bail};
+use chrono::{DateTime, NaiveDate, Utc};
+
+/// Sort keys for `ytq list --sort`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ListSort {
+    Added,
+    Duration,
+    Title,
+    Channel,
+    Published,
+}
+
+pub fn parse_list_sort(value: &str) -> Result<ListSort> {
+    match value.to_lowercase().as_str() {
+        "added" => Ok(ListSort::Added),
+        "duration" => Ok(ListSort::Duration),
+        "title" => Ok(ListSort::Title),
+        "channel" => Ok(ListSort::Channel),
+        "published" => Ok(ListSort::Published),
+        _ => bail!(
+            "invalid sort '{value}': use 'added', 'duration', 'title', 'channel' or 'published'"
+        ),
+    }
+}
+
+/// Parses a duration flag such as `--min-duration 20m` into seconds.
+pub fn parse_duration(flag: &str, value: &str) -> Result<u64> {
+    youtube::parse_timestamp(value)
+        .ok_or_else(|| anyhow!("invalid {flag} '{value}': use seconds, 1h2m3s or 1:02:03"))
+}
+
+pub fn parse_date(flag: &str, value: &str) -> Result<NaiveDate> {
+    NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .map_err(|_| anyhow!("invalid {flag} date '{value}': expected YYYY-MM-DD"))
+}
+
+/// Filters, sort order and page of a `ytq list`. The default shows the whole
+/// queue in queue order.
+#[derive(Debug, Default)]
+pub struct ListFilter {
+    /// Case-insensitive part of the channel name, or an exact channel ID.
+    pub channel: Option<String>,
+    /// Category name (case-insensitive) or ID.
+    pub category: Option<String>,
+    /// The video must carry every one of these tags.
+    pub tags: Vec<String>,
+    pub min_duration: Option<u64>,
+    pub max_duration: Option<u64>,
+    /// Added on or after this date (UTC, like `ytq stats --from`).
+    pub added_after: Option<NaiveDate>,
+    /// Added before this date.
+    pub added_before: Option<NaiveDate>,
+    /// Only videos without cached metadata.
+    pub unfetched: bool,
+    /// Only videos YouTube reported as deleted or private.
+    pub unavailable: bool,
+    pub sort: Option<ListSort>,
+    pub reverse: bool,
+    pub limit: Option<usize>,
+    pub offset: usize,
+}
+
+/// A comparable sort value. Videos without one sort last.
+#[derive(PartialEq, Eq, PartialOrd, Ord)]
+enum SortValue {
+    Time(DateTime<Utc>),
+    Number(u64),
+    Text(String),
+}
+
+impl ListFilter {
+    /// Whether any option hides or reorders videos.
+    pub fn is_active(&self) -> bool {
+        self.channel.is_some()
+            || self.category.is_some()
+            || !self.tags.is_empty()
+            || self.min_duration.is_some()
+            || self.max_duration.is_some()
+            || self.added_after.is_some()
+            || self.added_before.is_some()
+            || self.unfetched
+            || self.unavailable
+            || self.sort.is_some()
+            || self.reverse
+            || self.limit.is_some()
+            || self.offset > 0
+    }
+
+    /// Returns the indices into `videos` to show, in display order.
+    pub fn apply(
+        &self,
+        videos: &[Video],
+        metadata: &HashMap<String, VideoMeta>,
+        categories: &HashMap<String, String>,
+    ) -> Vec<usize> {
+        let mut order: Vec<usize> = (0..videos.len())
+            .filter(|&i| self.matches(&videos[i], metadata.get(&videos[i].id), categories))
+            .collect();
+
+        match self.sort {
+            Some(sort) => {
+                let key = |i: usize| sort_value(sort, &videos[i], metadata.get(&videos[i].id));
+                // Stable, so ties keep queue order
+                order.sort_by(|&a, &b| match (key(a), key(b)) {
+                    (Some(a), Some(b)) if self.reverse => b.cmp(&a),
+                    (Some(a), Some(b)) => a.cmp(&b),
+                    (Some(_), None) => Ordering::Less,
+                    (None, Some(_)) => Ordering::Greater,
+                    (None, None) => Ordering::Equal,
+                });
+            }
+            None if self.reverse => order.reverse(),
+            None => {}
+        }
+
+        order
+            .into_iter()
+            .skip(self.offset)
+            .take(self.limit.unwrap_or(usize::MAX))
+            .collect()
+    }
+
+    fn matches(
+        &self,
+        video: &Video,
+        meta: Option<&VideoMeta>,
+        categories: &HashMap<String, String>,
+    ) -> bool {
+        if self.unfetched && meta.is_some() {
+            return false;
+        }
+        if self.unavailable && !meta.is_some_and(|m| m.unavailable) {
+            return false;
+        }
+        if !self
+            .tags
+            .iter()
+            .all(|tag| video.tags.contains(&tag.trim().to_lowercase()))
+        {
+            return false;
+        }
+        let added = DateRange::custom(self.added_after, self.added_before);
+        if !added.contains(&video.added_at) {
+            return false;
+        }
+
+        let needs_metadata = self.channel.is_some()
+            || self.category.is_some()
+            || self.min_duration.is_some()
+            || self.max_duration.is_some();
+        if !needs_metadata {
+            return true;
+        }
+        // Metadata filters never match unfetched or unavailable videos
+        let Some(meta) = meta.filter(|m| !m.unavailable) else {
+            return false;
+        };
+
+        if let Some(channel) = &self.channel
+            && meta.channel_id != *channel
+            && !meta
+                .channel
+                .to_lowercase()
+                .contains(&channel.to_lowercase())
+        {
+            return false;
+        }
+        if let Some(category) = &self.category {
+            let name = categories.get(&meta.category_id);
+            if meta.category_id != *category
+                && !name.is_some_and(|n| n.eq_ignore_ascii_case(category))
+            {
+                return false;
+            }
+        }
+        if self
+            .min_duration
+            .is_some_and(|min| meta.duration_seconds < min)
+        {
+            return false;
+        }
+        if self
+            .max_duration
+            .is_some_and(|max| meta.duration_seconds > max)
+        {
+            return false;
+        }
+        true
+    }
+}
+
+fn sort_value(sort: ListSort, video: &Video, meta: Option<&VideoMeta>) -> Option<SortValue> {
+    let meta = meta.filter(|m| !m.unavailable);
+    match sort {
+        ListSort::Added => Some(SortValue::Time(video.added_at)),
+        ListSort::Duration => meta.map(|m| SortValue::Number(m.duration_seconds)),
+        ListSort::Title => meta.map(|m| SortValue::Text(m.title.to_lowercase())),
+        ListSort::Channel => meta.map(|m| SortValue::Text(m.channel.to_lowercase())),
+        ListSort::Published => meta.map(|m| SortValue::Time(m.published_at)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::meta;
+
+    fn video(id: &str, added: &str, tags: &[&str]) -> Video {
+        let mut video = Video::new(id.to_string(), String::new(), added.parse().unwrap());
+        video.tags = tags.iter().map(|t| t.to_string()).collect();
+        video
+    }
+
+    /// Four videos: a short music video, a long lecture, an unfetched one
+    /// and an unavailable one.
+    fn fixture() -> (
+        Vec<Video>,
+        HashMap<String, VideoMeta>,
+        HashMap<String, String>,
+    ) {
+        let videos = vec![
+            video("a", "2026-01-01T10:00:00Z", &["fun"]),
+            video("b", "2026-01-05T10:00:00Z", &["fun", "learn"]),
+            video("c", "2026-01-10T10:00:00Z", &[]),
+            video("d", "2026-01-15T10:00:00Z", &[]),
+        ];
+        let metadata = HashMap::from([
+            (
+                "a".to_string(),
+                VideoMeta {
+                    title: "Zebra song".to_string(),
+                    channel: "Music".to_string(),
+                    category_id: "10".to_string(),
+                    duration_seconds: 200,
+                    ..meta("a")
+                },
+            ),
+            (
+                "b".to_string(),
+                VideoMeta {
+                    title: "Algebra lecture".to_string(),
+                    channel: "Uni Lectures".to_string(),
+                    category_id: "27".to_string(),
+                    duration_seconds: 3600,
+                    ..meta("b")
+                },
+            ),
+            (
+                "d".to_string(),
+                VideoMeta {
+                    unavailable: true,
+                    ..meta("d")
+                },
+            ),
+        ]);
+        let categories = HashMap::from([
+            ("10".to_string(), "Music".to_string()),
+            ("27".to_string(), "Education".to_string()),
+        ]);
+        (videos, metadata, categories)
+    }
+
+    fn ids(filter: &ListFilter) -> Vec<&'static str> {
+        let (videos, metadata, categories) = fixture();
+        let names = ["a", "b", "c", "d"];
+        filter
+            .apply(&videos, &metadata, &categories)
+            .into_iter()
+            .map(|i| names[i])
+            .collect()
+    }
+
+    #[test]
+    fn default_keeps_queue_order() {
+        let filter = ListFilter::default();
+        assert!(!filter.is_active());
+        assert_eq!(ids(&filter), vec!["a", "b", "c", "d"]);
+    }
+
+    #[test]
+    fn metadata_filters() {
+        let channel = ListFilter {
+            channel: Some("lecture".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(ids(&channel), vec!["b"]);
+
+        let category = ListFilter {
+            category: Some("music".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(ids(&category), vec!["a"]);
+
+        let duration = ListFilter {
+            min_duration: Some(20 * 60),
+            ..Default::default()
+        };
+        assert_eq!(ids(&duration), vec!["b"]);
+
+        let short = ListFilter {
+            max_duration: Some(20 * 60),
+            ..Default::default()
+        };
+        assert_eq!(ids(&short), vec!["a"]);
+    }
+
+    #[test]
+    fn tag_date_and_state_filters() {
+        let tags = ListFilter {
+            tags: vec!["Fun".to_string(), "learn".to_string()],
+            ..Default::default()
+        };
+        assert_eq!(ids(&tags), vec!["b"]);
+
+        let dates = ListFilter {
+            added_after: NaiveDate::from_ymd_opt(2026, 1, 5),
+            added_before: NaiveDate::from_ymd_opt(2026, 1, 15),
+            ..Default::default()
+        };
+        assert_eq!(ids(&dates), vec!["b", "c"]);
+
+        let unfetched = ListFilter {
+            unfetched: true,
+            ..Default::default()
+        };
+        assert_eq!(ids(&unfetched), vec!["c"]);
+
+        let unavailable = ListFilter {
+            unavailable: true,
+            ..Default::default()
+        };
+        assert_eq!(ids(&unavailable), vec!["d"]);
+    }
+
+    #[test]
+    fn sort_puts_missing_values_last_even_reversed() {
+        let by_title = ListFilter {
+            sort: Some(ListSort::Title),
+            ..Default::default()
+        };
+        assert_eq!(ids(&by_title), vec!["b", "a", "c", "d"]);
+
+        let longest_first = ListFilter {
+            sort: Some(ListSort::Duration),
+            reverse: true,
+            ..Default::default()
+        };
+        assert_eq!(ids(&longest_first), vec!["b", "a", "c", "d"]);
+    }
+
+    #[test]
+    fn reverse_without_sort_and_pagination() {
+        let page = ListFilter {
+            reverse: true,
+            offset: 1,
+            limit: Some(2),
+            ..Default::default()
+        };
+        assert_eq!(ids(&page), vec!["c", "b"]);
+    }
+
+    #[test]
+    fn parse_helpers() {
+        assert_eq!(parse_list_sort("Published").unwrap(), ListSort::Published);
+        assert!(parse_list_sort("views").is_err());
+        assert_eq!(parse_duration("--min-duration", "20m").unwrap(), 1200);
+        assert!(parse_duration("--min-duration", "soon").is_err());
+        assert!(parse_date("--added-after", "2026-13-01").is_err());
+    }
+}
@@ -0,0 +1,252 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use crate::models::{Action, Event};
+use crate::stats::DateRange;
+use crate::store;
+
+use anyhow::Result;
+use chrono::{NaiveDate, NaiveTime, TimeDelta};
+use serde::{Deserialize, Serialize};
+
+/// Bumping this makes every existing index be rebuilt instead of migrated;
+/// it only holds what the partitions already say. Version 1 also kept
+/// per-day counts that nothing read.
+const INDEX_VERSION: u32 = 2;
+
+/// Summary of one history partition.
+#[derive(Serialize, Deserialize, Debug, Default, PartialEq)]
+pub struct MonthSummary {
+    /// Size of the partition when it was last summarized. Any other size
+    /// means it was changed by something else, e.g. `ytq doctor --repair`.
+    pub bytes: u64,
+    /// The queues with events on each UTC day, and whether any of those
+    /// events is an undo or redo, which can change any earlier day.
+    pub days: BTreeMap<NaiveDate, BTreeMap<String, bool>>,
+}
+
+impl MonthSummary {
+    pub fn new(events: &[Event], bytes: u64) -> Self {
+        let mut summary = Self {
+            bytes,
+            ..Default::default()
+        };
+        for event in events {
+            summary.record(event);
+        }
+        summary
+    }
+
+    pub fn record(&mut self, event: &Event) {
+        let compensated = self
+            .days
+            .entry(event.timestamp.date_naive())
+            .or_default()
+            .entry(event.queue_name().to_string())
+            .or_default();
+        *compensated |= matches!(event.action, Action::Undone | Action::Redone);
+    }
+
+    /// For each day and queue with events overlapping `range` (one queue or
+    /// all of them), whether an undo or redo happened.
+    fn days_in<'a>(
+        &'a self,
+        range: &'a DateRange,
+        queue: Option<&'a str>,
+    ) -> impl Iterator<Item = bool> + 'a {
+        self.days
+            .iter()
+            .filter(|(day, _)| day_overlaps(**day, range))
+            .flat_map(move |(_, queues)| {
+                queues
+                    .iter()
+                    .filter(move |(name, _)| queue.is_none_or(|q| q == name.as_str()))
+                    .map(|(_, compensated)| *compensated)
+            })
+    }
+}
+
+/// Per-partition summaries of the JSON history, kept in `history/index.json`
+/// by [`store::log_event`] so `ytq stats` only reads the months it needs.
+/// It is a cache: a missing, damaged or outdated index is rebuilt from the
+/// partitions.
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct HistoryIndex {
+    version: u32,
+    /// Keyed by partition file name, e.g. `2026-03.jsonl`.
+    pub months: BTreeMap<String, MonthSummary>,
+}
+
+impl HistoryIndex {
+    /// Loads the index; anything unreadable or from another version counts
+    /// as empty.
+    pub fn load(path: &Path) -> Self {
+        fs::read_to_string(path)
+            .ok()
+            .and_then(|data| serde_json::from_str::<Self>(&data).ok())
+            .filter(|index| index.version == INDEX_VERSION)
+            .unwrap_or_default()
+    }
+
+    pub fn save(&mut self, path: &Path) -> Result<()> {
+        self.version = INDEX_VERSION;
+        store::write_atomic(path, serde_json::to_string(self)?.as_bytes())
+    }
+
+    /// The partitions holding events in `range`, for one queue or all of
+    /// them. Returns `None` when every partition is needed: an undo or redo
+    /// on or after the start of the range may cancel events inside it, and
+    /// resolving it takes the whole history.
+    pub fn partitions_for(&self, range: &DateRange, queue: Option<&str>) -> Option<Vec<String>> {
+        let since_start = DateRange {
+            start: range.start,
+            end: None,
+        };
+        let compensated = self.months.values().any(|month| {
+            month
+                .days_in(&since_start, queue)
+                .any(|compensated| compensated)
+        });
+        if compensated {
+            return None;
+        }
+
+        Some(
+            self.months
+                .iter()
+                .filter(|(_, month)| month.days_in(range, queue).next().is_some())
+                .map(|(name, _)| name.clone())
+                .collect(),
+        )
+    }
+}
+
+/// Whether any part of `day` (UTC) falls within `range`.
+fn day_overlaps(day: NaiveDate, range: &DateRange) -> bool {
+    let day_start = day.and_time(NaiveTime::MIN).and_utc();
+    let day_end = day_start + TimeDelta::days(1);
+    range.start.is_none_or(|start| day_end > start) && range.end.is_none_or(|end| day_start < end)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(action: Action, timestamp: &str, queue: Option<&str>) -> Event {
+        Event {
+            timestamp: timestamp.parse().unwrap(),
+            action,
+            video_id: "a".to_string(),
+            time_in_queue_sec: Some(60),
+            queue: queue.map(str::to_string),
+        }
+    }
+
+    fn index(months: &[(&str, Vec<Event>)]) -> HistoryIndex {
+        HistoryIndex {
+            version: INDEX_VERSION,
+            months: months
+                .iter()
+                .map(|(name, events)| (name.to_string(), MonthSummary::new(events, 0)))
+                .collect(),
+        }
+    }
+
+    fn range(from: &str, to: Option<&str>) -> DateRange {
+        DateRange::custom(from.parse().ok(), to.map(|t| t.parse().unwrap()))
+    }
+
+    #[test]
+    fn summary_records_days_queues_and_undos() {
+        let summary = MonthSummary::new(
+            &[
+                event(Action::Queued, "2026-03-01T10:00:00Z", None),
+                event(Action::Watched, "2026-03-01T11:00:00Z", None),
+                event(Action::Watched, "2026-03-01T12:00:00Z", Some("music")),
+            ],
+            123,
+        );
+        let day = &summary.days[&"2026-03-01".parse::<NaiveDate>().unwrap()];
+        assert_eq!(day.len(), 2);
+        assert!(!day["default"]);
+        assert!(!day["music"]);
+        assert_eq!(summary.bytes, 123);
+
+        let mut summary = summary;
+        summary.record(&event(Action::Undone, "2026-03-01T13:00:00Z", None));
+        assert!(summary.days[&"2026-03-01".parse::<NaiveDate>().unwrap()]["default"]);
+    }
+
+    #[test]
+    fn only_overlapping_partitions_are_read() {
+        let index = index(&[
+            (
+                "2026-01.jsonl",
+                vec![event(Action::Queued, "2026-01-31T23:00:00Z", None)],
+            ),
+            (
+                "2026-02.jsonl",
+                vec![event(
+                    Action::Watched,
+                    "2026-02-10T12:00:00Z",
+                    Some("music"),
+                )],
+            ),
+            (
+                "2026-03.jsonl",
+                vec![event(Action::Queued, "2026-03-02T12:00:00Z", None)],
+            ),
+        ]);
+
+        let february = range("2026-02-01", Some("2026-03-01"));
+        assert_eq!(
+            index.partitions_for(&february, None).unwrap(),
+            vec!["2026-02.jsonl"]
+        );
+        assert!(
+            index
+                .partitions_for(&february, Some("default"))
+                .unwrap()
+                .is_empty()
+        );
+        assert_eq!(
+            index
+                .partitions_for(&DateRange::all_time(), None)
+                .unwrap()
+                .len(),
+            3
+        );
+    }
+
+    #[test]
+    fn later_undo_needs_the_whole_history() {
+        let index = index(&[
+            (
+                "2026-01.jsonl",
+                vec![event(Action::Watched, "2026-01-10T12:00:00Z", None)],
+            ),
+            (
+                "2026-03.jsonl",
+                vec![event(Action::Undone, "2026-03-02T12:00:00Z", Some("music"))],
+            ),
+        ]);
+
+        assert!(
+            index
+                .partitions_for(&range("2026-02-01", None), None)
+                .is_none()
+        );
+        // The undo was in another queue, or before the range
+        assert!(
+            index
+                .partitions_for(&range("2026-02-01", None), Some("default"))
+                .is_some()
+        );
+        assert!(
+            index
+                .partitions_for(&range("2026-03-03", None), None)
+                .is_some()
+        );
+    }
+}
@@ -0,0 +1,341 @@
+use std::collections::HashSet;
+
+use crate::models::{Action, Video};
+
+use anyhow::{Result, bail};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Maximum number of undoable mutations kept in journal.json.
+pub const JOURNAL_LIMIT: usize = 50;
+
+/// Undo and redo stacks of queue mutations. The most recent entry is last.
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct Journal {
+    #[serde(default)]
+    pub undo: Vec<JournalEntry>,
+    #[serde(default)]
+    pub redo: Vec<JournalEntry>,
+}
+
+impl Journal {
+    /// Records a new mutation. Any redo history is discarded, as in an editor.
+    pub fn record(&mut self, entry: JournalEntry) {
+        self.undo.push(entry);
+        if self.undo.len() > JOURNAL_LIMIT {
+            let excess = self.undo.len() - JOURNAL_LIMIT;
+            self.undo.drain(..excess);
+        }
+        self.redo.clear();
+    }
+}
+
+/// One call to `store::with_queue` (or `with_queue_pair`) that changed at
+/// least one queue.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct JournalEntry {
+    pub timestamp: DateTime<Utc>,
+    pub changes: Vec<QueueSplice>,
+}
+
+/// A change to one queue, stored as a splice: starting at `start`, the
+/// `removed` videos were replaced by the `inserted` ones. Only the differing
+/// middle section is kept, so typical edits stay small.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct QueueSplice {
+    pub queue: String,
+    pub start: usize,
+    pub removed: Vec<Video>,
+    pub inserted: Vec<Video>,
+    /// Length of the queue before the splice, used to detect conflicting
+    /// changes when nothing was removed.
+    pub len_before: usize,
+}
+
+impl QueueSplice {
+    /// Computes the splice turning `before` into `after`, or `None` if they
+    /// are identical.
+    pub fn diff(queue: &str, before: &[Video], after: &[Video]) -> Option<Self> {
+        if before == after {
+            return None;
+        }
+
+        let prefix = before.iter().zip(after).take_while(|(a, b)| a == b).count();
+        let max_suffix = before.len().min(after.len()) - prefix;
+        let suffix = before
+            .iter()
+            .rev()
+            .zip(after.iter().rev())
+            .take(max_suffix)
+            .take_while(|(a, b)| a == b)
+            .count();
+
+        Some(Self {
+            queue: queue.to_string(),
+            start: prefix,
+            removed: before[prefix..before.len() - suffix].to_vec(),
+            inserted: after[prefix..after.len() - suffix].to_vec(),
+            len_before: before.len(),
+        })
+    }
+
+    /// Applies the splice. Fails without modifying the queue if it no longer
+    /// contains the `removed` videos at `start`.
+    pub fn apply(&self, videos: &mut Vec<Video>) -> Result<()> {
+        let end = self.start + self.removed.len();
+        if videos.len() != self.len_before || videos[self.start..end] != self.removed[..] {
+            bail!(
+                "queue '{}' has changed in a way that conflicts with this change",
+                self.queue
+            );
+        }
+        videos.splice(self.start..end, self.inserted.iter().cloned());
+        Ok(())
+    }
+
+    /// Returns the splice that reverts this one.
+    pub fn inverse(&self) -> Self {
+        Self {
+            queue: self.queue.clone(),
+            start: self.start,
+            removed: self.inserted.clone(),
+            inserted: self.removed.clone(),
+            len_before: self.len_before - self.removed.len() + self.inserted.len(),
+        }
+    }
+}
+
+/// Returns the history events that compensate for undoing (`Action::Undone`)
+/// or redoing (`Action::Redone`) an entry, as `(action, video_id, queue)`.
+///
+/// Videos that entered or left a queue get the compensating action, so stats
+/// can cancel or restore the Queued/Watched/Skipped event they caused. A
+/// queue whose videos only changed position gets one `Reordered`, for the
+/// video that moved furthest, like the one `ytq move` logs. Videos moved
+/// between queues in the same entry never logged an event, so they get none.
+pub fn compensating_events(
+    entry: &JournalEntry,
+    compensation: Action,
+) -> Vec<(Action, String, String)> {
+    let left: Vec<(&str, &str)> = entry
+        .changes
+        .iter()
+        .flat_map(|c| membership_only_in(&c.removed, &c.inserted).map(|id| (c.queue.as_str(), id)))
+        .collect();
+    let entered: Vec<(&str, &str)> = entry
+        .changes
+        .iter()
+        .flat_map(|c| membership_only_in(&c.inserted, &c.removed).map(|id| (c.queue.as_str(), id)))
+        .collect();
+
+    // A video that left one queue and entered another was moved between queues
+    let moved: HashSet<&str> = left
+        .iter()
+        .filter(|(q, id)| entered.iter().any(|(q2, id2)| id == id2 && q != q2))
+        .map(|(_, id)| *id)
+        .collect();
+
+    let mut events = Vec::new();
+    for (queue, id) in left.iter().chain(&entered) {
+        if !moved.contains(id) {
+            events.push((compensation.clone(), id.to_string(), queue.to_string()));
+        }
+    }
+
+    for change in &entry.changes {
+        let furthest = change
+            .removed
+            .iter()
+            .enumerate()
+            .filter_map(|(idx, video)| {
+                let new_idx = change.inserted.iter().position(|v| v.id == video.id)?;
+                (new_idx != idx).then_some((idx.abs_diff(new_idx), video))
+            })
+            // The first of equals, so swapping two neighbours names the first
+            .rev()
+            .max_by_key(|(distance, _)| *distance);
+        if let Some((_, video)) = furthest {
+            events.push((Action::Reordered, video.id.clone(), change.queue.clone()));
+        }
+    }
+
+    events
+}
+
+/// IDs of videos in `a` that do not appear in `b`.
+fn membership_only_in<'a>(a: &'a [Video], b: &'a [Video]) -> impl Iterator<Item = &'a str> {
+    a.iter()
+        .filter(|v| !b.iter().any(|o| o.id == v.id))
+        .map(|v| v.id.as_str())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn video(id: &str) -> Video {
+        Video::new(
+            id.to_string(),
+            String::new(),
+            "2026-01-01T00:00:00Z".parse().unwrap(),
+        )
+    }
+
+    fn videos(ids: &[&str]) -> Vec<Video> {
+        ids.iter().map(|id| video(id)).collect()
+    }
+
+    fn ids(videos: &[Video]) -> Vec<&str> {
+        videos.iter().map(|v| v.id.as_str()).collect()
+    }
+
+    #[test]
+    fn diff_identical_is_none() {
+        let q = videos(&["a", "b"]);
+        assert!(QueueSplice::diff("default", &q, &q).is_none());
+    }
+
+    #[test]
+    fn diff_append_keeps_only_new_video() {
+        let before = videos(&["a", "b"]);
+        let after = videos(&["a", "b", "c"]);
+        let splice = QueueSplice::diff("default", &before, &after).unwrap();
+        assert_eq!(splice.start, 2);
+        assert!(splice.removed.is_empty());
+        assert_eq!(ids(&splice.inserted), vec!["c"]);
+    }
+
+    #[test]
+    fn diff_removal_from_middle() {
+        let before = videos(&["a", "b", "c"]);
+        let after = videos(&["a", "c"]);
+        let splice = QueueSplice::diff("default", &before, &after).unwrap();
+        assert_eq!(splice.start, 1);
+        assert_eq!(ids(&splice.removed), vec!["b"]);
+        assert!(splice.inserted.is_empty());
+    }
+
+    #[test]
+    fn diff_duplicate_neighbours_do_not_overlap() {
+        // Prefix and suffix must not both claim the same element
+        let before = videos(&["a", "a"]);
+        let after = videos(&["a"]);
+        let splice = QueueSplice::diff("default", &before, &after).unwrap();
+        let mut q = before.clone();
+        splice.apply(&mut q).unwrap();
+        assert_eq!(ids(&q), vec!["a"]);
+    }
+
+    #[test]
+    fn apply_and_inverse_roundtrip() {
+        let before = videos(&["a", "b", "c", "d"]);
+        let after = videos(&["c", "a", "b", "d"]);
+        let splice = QueueSplice::diff("default", &before, &after).unwrap();
+
+        let mut q = before.clone();
+        splice.apply(&mut q).unwrap();
+        assert_eq!(q, after);
+
+        splice.inverse().apply(&mut q).unwrap();
+        assert_eq!(q, before);
+    }
+
+    #[test]
+    fn apply_restores_original_index_and_added_at() {
+        let mut original = videos(&["a", "b", "c"]);
+        original[1].added_at = "2020-05-05T00:00:00Z".parse().unwrap();
+        let mut after = original.clone();
+        after.remove(1);
+
+        let splice = QueueSplice::diff("default", &original, &after).unwrap();
+        splice.inverse().apply(&mut after).unwrap();
+        assert_eq!(after[1].id, "b");
+        assert_eq!(after[1].added_at, original[1].added_at);
+    }
+
+    #[test]
+    fn apply_rejects_conflicting_queue() {
+        let before = videos(&["a", "b"]);
+        let after = videos(&["a"]);
+        let splice = QueueSplice::diff("default", &before, &after).unwrap();
+
+        // Another video was added since the removal
+        let mut changed = videos(&["a", "x"]);
+        assert!(splice.inverse().apply(&mut changed).is_err());
+        assert_eq!(ids(&changed), vec!["a", "x"]);
+
+        // The surrounding videos were replaced
+        let mut changed = videos(&["x", "y"]);
+        assert!(splice.apply(&mut changed).is_err());
+    }
+
+    #[test]
+    fn journal_record_caps_and_clears_redo() {
+        let mut journal = Journal::default();
+        journal.redo.push(JournalEntry {
+            timestamp: Utc::now(),
+            changes: vec![],
+        });
+        for _ in 0..JOURNAL_LIMIT + 5 {
+            journal.record(JournalEntry {
+                timestamp: Utc::now(),
+                changes: vec![],
+            });
+        }
+        assert_eq!(journal.undo.len(), JOURNAL_LIMIT);
+        assert!(journal.redo.is_empty());
+    }
+
+    #[test]
+    fn compensating_events_for_removal_and_addition() {
+        let entry = JournalEntry {
+            timestamp: Utc::now(),
+            changes: vec![
+                QueueSplice::diff("default", &videos(&["a", "b"]), &videos(&["a", "c"])).unwrap(),
+            ],
+        };
+        let events = compensating_events(&entry, Action::Undone);
+        let summary: Vec<(String, &str)> = events
+            .iter()
+            .map(|(a, id, _)| (format!("{a:?}"), id.as_str()))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![("Undone".to_string(), "b"), ("Undone".to_string(), "c")]
+        );
+    }
+
+    #[test]
+    fn compensating_events_skip_moves_between_queues() {
+        let entry = JournalEntry {
+            timestamp: Utc::now(),
+            changes: vec![
+                QueueSplice::diff("default", &videos(&["a"]), &videos(&[])).unwrap(),
+                QueueSplice::diff("music", &videos(&[]), &videos(&["a"])).unwrap(),
+            ],
+        };
+        assert!(compensating_events(&entry, Action::Undone).is_empty());
+    }
+
+    #[test]
+    fn compensating_events_for_reorder() {
+        let reorder = |before: &[&str], after: &[&str]| {
+            let entry = JournalEntry {
+                timestamp: Utc::now(),
+                changes: vec![
+                    QueueSplice::diff("default", &videos(before), &videos(after)).unwrap(),
+                ],
+            };
+            compensating_events(&entry, Action::Undone)
+                .into_iter()
+                .map(|(action, id, _)| (format!("{action:?}"), id))
+                .collect::<Vec<_>>()
+        };
+        let reordered = |id: &str| vec![("Reordered".to_string(), id.to_string())];
+
+        // One event per move, naming the moved video
+        assert_eq!(reorder(&["a", "b", "c"], &["b", "c", "a"]), reordered("a"));
+        assert_eq!(reorder(&["a", "b", "c"], &["c", "a", "b"]), reordered("c"));
+        assert_eq!(reorder(&["a", "b"], &["b", "a"]), reordered("a"));
+    }
+}
@@ -1,18 +1,57 @@
+mod archive;
+mod bookmarks;
+mod budget;
 mod commands;
+mod doctor;
+mod feeds;
+mod filter;
+mod history_index;
+mod journal;
 mod models;
+mod output;
 mod paths;
+mod player;
+mod search;
+mod sqlite;
 mod stats;
 mod store;
+mod takeout;
+#[cfg(test)]
+mod test_util;
 mod youtube;
 mod youtube_api;
 
+use std::path::PathBuf;
+
 use anyhow::Result;
-use clap::{Parser, Subcommand};
+use clap::{Args, Parser, Subcommand};
 use colored::Colorize;
+use commands::{PlayOptions, PlaylistScope, SearchAction, SearchScope};
+use filter::ListFilter;
 
 #[derive(Parser)]
 #[command(name = "ytq", version)]
 struct Cli {
+    /// Queue to operate on (defaults to the "default" queue)
+    #[arg(short, long, global = true, value_name = "NAME")]
+    queue: Option<String>,
+
+    /// Output format for list, peek, plan and info: table, json, csv or tsv
+    #[arg(long, global = true, value_name = "FORMAT", default_value = "table")]
+    format: String,
+
+    /// Keep the config and all data in DIR instead of the platform's usual places
+    #[arg(long, global = true, value_name = "DIR", env = "YTQ_HOME")]
+    home: Option<PathBuf>,
+
+    /// Directory for config.json (overrides --home)
+    #[arg(long, global = true, value_name = "DIR", env = "YTQ_CONFIG_DIR")]
+    config_dir: Option<PathBuf>,
+
+    /// Directory for queues, history, metadata and the lock file (overrides --home)
+    #[arg(long, global = true, value_name = "DIR", env = "YTQ_DATA_DIR")]
+    data_dir: Option<PathBuf>,
+
     #[command(subcommand)]
     command: Commands,
 }
@@ -22,8 +61,64 @@ enum Commands {
     /// Add a video to the queue
     #[command(alias = "a")]
     Add {
-        /// Video URL, short link, or video ID
-        input: String,
+        /// Video URL, short link, video ID, or playlist URL ("-" reads links from stdin)
+        #[arg(required_unless_present = "file")]
+        input: Option<String>,
+
+        /// Read links from a file (blank lines and # comments are ignored)
+        #[arg(long, value_name = "PATH", conflicts_with = "input")]
+        file: Option<PathBuf>,
+
+        /// For a video URL with list=, add only the video
+        #[arg(long, conflicts_with_all = ["playlist", "from_here"])]
+        video_only: bool,
+
+        /// For a video URL with list=, add the whole playlist
+        #[arg(long, conflicts_with = "from_here")]
+        playlist: bool,
+
+        /// For a video URL with list=, add the playlist from this video onwards
+        #[arg(long)]
+        from_here: bool,
+
+        /// Tag the video (repeatable or comma-separated)
+        #[arg(long = "tag", value_name = "TAG", value_delimiter = ',')]
+        tags: Vec<String>,
+
+        /// Attach a free-text note (e.g. why you saved it)
+        #[arg(long)]
+        note: Option<String>,
+
+        /// Priority: low, normal or high (high is watched first)
+        #[arg(long)]
+        priority: Option<String>,
+    },
+
+    /// Edit the tags, note or priority of a queued video
+    #[command(alias = "e")]
+    Edit {
+        /// The ID or URL to edit
+        target: String,
+
+        /// Add tags (repeatable or comma-separated)
+        #[arg(long = "tag", value_name = "TAG", value_delimiter = ',')]
+        tags: Vec<String>,
+
+        /// Remove tags (repeatable or comma-separated)
+        #[arg(long = "untag", value_name = "TAG", value_delimiter = ',')]
+        untags: Vec<String>,
+
+        /// Replace the note
+        #[arg(long, conflicts_with = "clear_note")]
+        note: Option<String>,
+
+        /// Remove the note
+        #[arg(long)]
+        clear_note: bool,
+
+        /// Priority: low, normal or high
+        #[arg(long)]
+        priority: Option<String>,
     },
 
     /// Watch the next video and remove it from the queue
@@ -37,13 +132,27 @@ enum Commands {
         visible_alias = "open"
     )]
     Next {
-        /// Video ID or URL to open a specific video (uses queue/stack mode if omitted)
+        /// Video ID or URL to open a specific video (uses the selection mode if omitted)
         target: Option<String>,
+
+        /// Pick the best video that fits in this much time (e.g. 20m, 1h, 45:00)
+        #[arg(long, value_name = "DURATION", conflicts_with = "target")]
+        fits: Option<String>,
+
+        /// Selection mode for this run instead of the configured one
+        #[arg(long)]
+        mode: Option<String>,
+
+        #[command(flatten)]
+        play: PlayArgs,
     },
 
     /// List the current queue
     #[command(alias = "l", alias = "ls")]
-    List,
+    List {
+        #[command(flatten)]
+        filter: ListArgs,
+    },
 
     /// Look at the next few videos without watching
     #[command(alias = "k")]
@@ -51,6 +160,20 @@ enum Commands {
         /// How many videos to show
         #[arg(default_value_t = 1)]
         n: usize,
+
+        /// Selection mode to preview instead of the configured one
+        #[arg(long)]
+        mode: Option<String>,
+    },
+
+    /// Propose videos to watch back to back within a time budget
+    Plan {
+        /// Time available (e.g. 90m, 1h30m, 1:30:00)
+        budget: String,
+
+        /// Selection mode for this run instead of the configured one
+        #[arg(long)]
+        mode: Option<String>,
     },
 
     /// Remove a video by ID or URL
@@ -60,6 +183,66 @@ enum Commands {
         target: String,
     },
 
+    /// Move a video to a position in the queue (1 = first row of `list`)
+    #[command(alias = "mv")]
+    Move {
+        /// The ID or URL to move
+        target: String,
+        /// New 1-based position
+        position: usize,
+    },
+
+    /// Move a video to the head of the queue (raising its priority if another is higher)
+    ///
+    /// The head is the front of the list, or its end in stack mode, so in
+    /// those two modes the video is picked next. The sorting and random modes
+    /// don't pick by position, so there it may not be.
+    Top {
+        /// The ID or URL to bump
+        target: String,
+    },
+
+    /// Move a video to the tail of the queue (lowering its priority if another is lower)
+    ///
+    /// The tail is the end of the list, or its front in stack mode, so in
+    /// those two modes the video is picked last. The sorting and random modes
+    /// don't pick by position, so there it may not be.
+    Bottom {
+        /// The ID or URL to sink
+        target: String,
+    },
+
+    /// Swap the positions of two videos
+    Swap {
+        /// First ID or URL
+        a: String,
+        /// Second ID or URL
+        b: String,
+    },
+
+    /// Set where a queued video starts playing (0 starts from the beginning)
+    Seek {
+        /// The ID or URL to update
+        target: String,
+        /// Start time: seconds (90), 1h2m3s, or 1:02:03
+        time: String,
+    },
+
+    /// Undo the last queue change(s) (add, next, remove, move, edit, ...)
+    #[command(alias = "u")]
+    Undo {
+        /// How many changes to undo
+        #[arg(default_value_t = 1)]
+        n: usize,
+    },
+
+    /// Redo changes reverted by `undo`
+    Redo {
+        /// How many changes to redo
+        #[arg(default_value_t = 1)]
+        n: usize,
+    },
+
     /// Show statistics about your queue history
     #[command(alias = "s")]
     Stats {
@@ -92,10 +275,11 @@ enum Commands {
         to: Option<String>,
     },
 
-    /// Update a configuration value
+    /// Update a configuration value (per-queue when --queue is given)
     #[command(alias = "c")]
     Config {
-        /// Configuration key (mode, offline, youtube_api_key)
+        /// Configuration key: mode, offline, youtube_api_key, feed_base_url,
+        /// player or player.<category>; with --queue, mode or player
         key: String,
         /// New value
         value: String,
@@ -105,15 +289,58 @@ enum Commands {
     #[command(alias = "i")]
     Info,
 
+    /// Check every data file for damage and optionally repair it
+    Doctor {
+        /// Fix what was found, keeping the damaged originals as .corrupt copies
+        #[arg(long)]
+        repair: bool,
+    },
+
+    /// Export all queues, history, metadata and settings to one archive file
+    Export {
+        /// File to write (default: standard output)
+        file: Option<PathBuf>,
+        /// Write only the queue, as a bookmark file browsers can import
+        #[arg(long)]
+        bookmarks: bool,
+    },
+
+    /// Import an archive written by `ytq export`, merging it into your data
+    Import {
+        /// Archive to import ("-" reads standard input)
+        #[arg(required_unless_present_any = ["takeout", "bookmarks"])]
+        file: Option<PathBuf>,
+        /// Replace all current data instead of merging (it is kept as a backup)
+        #[arg(long)]
+        replace: bool,
+        /// Import Watch Later and watch history from a Google Takeout folder or file
+        #[arg(long, value_name = "PATH", conflicts_with_all = ["file", "replace"])]
+        takeout: Option<PathBuf>,
+        /// Import YouTube links from browser bookmarks, a Pocket or Raindrop CSV, or OPML
+        #[arg(
+            long,
+            value_name = "PATH",
+            conflicts_with_all = ["file", "replace", "takeout"]
+        )]
+        bookmarks: Option<PathBuf>,
+    },
+
+    /// Move your data to another storage backend
+    MigrateStorage {
+        /// Backend to move to: json or sqlite
+        target: String,
+    },
+
     /// Fetch video metadata from YouTube Data API v3
     #[command(alias = "f")]
     Fetch {
         /// Video ID(s), URL(s), or comma-separated list to fetch/refresh
+        /// (queue videos are the default scope; narrow it with --queue NAME)
         target: Option<String>,
 
-        /// Fetch for queue videos only (default when no flags given)
+        /// Fetch for queue videos only (the default; replaces the old `fetch --queue`)
         #[arg(long)]
-        queue: bool,
+        queue_only: bool,
 
         /// Fetch for history videos only
         #[arg(long)]
@@ -136,13 +363,251 @@ enum Commands {
         refresh_categories: bool,
     },
 
+    /// Fuzzy-search queued and watched videos by ID, title, channel or tag
+    Search {
+        /// Words to look for (every word must match)
+        #[arg(required = true, num_args = 1..)]
+        query: Vec<String>,
+
+        /// Only search queued videos (all queues unless --queue is given)
+        #[arg(long, conflicts_with = "watched")]
+        queued: bool,
+
+        /// Only search watched history
+        #[arg(long, conflicts_with_all = ["next", "remove"])]
+        watched: bool,
+
+        /// Maximum number of results to show
+        #[arg(long, default_value_t = 20)]
+        limit: usize,
+
+        /// Watch the best queued match
+        #[arg(long, conflicts_with = "remove")]
+        next: bool,
+
+        /// Remove the best queued match
+        #[arg(long)]
+        remove: bool,
+    },
+
     /// Pop and watch a random video from the queue
     #[command(alias = "r", alias = "lucky")]
-    Random,
+    Random {
+        #[command(flatten)]
+        play: PlayArgs,
+    },
+
+    /// Manage named queues
+    #[command(alias = "q")]
+    Queue {
+        #[command(subcommand)]
+        action: QueueAction,
+    },
+
+    /// Follow a channel; `ytq sync` adds its new uploads to the selected queue
+    Subscribe {
+        /// Channel URL, @handle, or channel ID (UC...)
+        channel: String,
+    },
+
+    /// Stop following a channel
+    Unsubscribe {
+        /// Channel URL, @handle, channel ID, or title
+        channel: String,
+    },
+
+    /// List followed channels
+    #[command(alias = "subs")]
+    Subscriptions,
+
+    /// Add new uploads from followed channels (reads their RSS feeds)
+    Sync,
+}
+
+/// Output options shared by `next` and `random`.
+#[derive(Args)]
+struct PlayArgs {
+    /// Player command for this run, e.g. "mpv {url}" (placeholders: {url}, {id}, {start})
+    #[arg(long, value_name = "COMMAND", conflicts_with_all = ["print", "json"])]
+    player: Option<String>,
+
+    /// Print the URL (or the ID with --print=id) instead of opening the video
+    #[arg(
+        long,
+        value_name = "FIELD",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "url",
+        conflicts_with = "json"
+    )]
+    print: Option<String>,
+
+    /// Print the video and its metadata as JSON instead of opening it
+    #[arg(long)]
+    json: bool,
+
+    /// Show what would be picked without changing the queue or history
+    #[arg(long)]
+    dry_run: bool,
+}
+
+impl PlayArgs {
+    fn options(&self) -> Result<PlayOptions<'_>> {
+        Ok(PlayOptions {
+            player: self.player.as_deref(),
+            print: self
+                .print
+                .as_deref()
+                .map(commands::parse_print_field)
+                .transpose()?,
+            json: self.json,
+            dry_run: self.dry_run,
+        })
+    }
+}
+
+/// Filter, sort and paging options for `list`.
+#[derive(Args)]
+struct ListArgs {
+    /// Only videos from channels whose name contains this text (or a channel ID)
+    #[arg(long)]
+    channel: Option<String>,
+
+    /// Only videos in this category (name or ID)
+    #[arg(long)]
+    category: Option<String>,
+
+    /// Only videos with this tag (repeatable or comma-separated; all must match)
+    #[arg(long = "tag", value_name = "TAG", value_delimiter = ',')]
+    tags: Vec<String>,
+
+    /// Only videos at least this long (e.g. 90, 20m, 1h30m, 1:30:00)
+    #[arg(long, value_name = "DURATION")]
+    min_duration: Option<String>,
+
+    /// Only videos at most this long
+    #[arg(long, value_name = "DURATION")]
+    max_duration: Option<String>,
+
+    /// Only videos added before this date (YYYY-MM-DD)
+    #[arg(long, value_name = "DATE")]
+    added_before: Option<String>,
+
+    /// Only videos added on or after this date (YYYY-MM-DD)
+    #[arg(long, value_name = "DATE")]
+    added_after: Option<String>,
+
+    /// Only videos without fetched metadata
+    #[arg(long, conflicts_with = "unavailable")]
+    unfetched: bool,
+
+    /// Only videos YouTube reported as deleted or private
+    #[arg(long)]
+    unavailable: bool,
+
+    /// Sort by: added, duration, title, channel or published (default: queue order)
+    #[arg(long, value_name = "KEY")]
+    sort: Option<String>,
+
+    /// Reverse the order
+    #[arg(long)]
+    reverse: bool,
+
+    /// Show at most this many videos
+    #[arg(long)]
+    limit: Option<usize>,
+
+    /// Skip this many videos (after filtering and sorting)
+    #[arg(long, default_value_t = 0)]
+    offset: usize,
+}
+
+impl ListArgs {
+    fn filter(&self) -> Result<ListFilter> {
+        let duration = |flag, value: &Option<String>| {
+            value
+                .as_deref()
+                .map(|v| filter::parse_duration(flag, v))
+                .transpose()
+        };
+        let date = |flag, value: &Option<String>| {
+            value
+                .as_deref()
+                .map(|v| filter::parse_date(flag, v))
+                .transpose()
+        };
+
+        Ok(ListFilter {
+            channel: self.channel.clone(),
+            category: self.category.clone(),
+            tags: self.tags.clone(),
+            min_duration: duration("--min-duration", &self.min_duration)?,
+            max_duration: duration("--max-duration", &self.max_duration)?,
+            added_after: date("--added-after", &self.added_after)?,
+            added_before: date("--added-before", &self.added_before)?,
+            unfetched: self.unfetched,
+            unavailable: self.unavailable,
+            sort: self
+                .sort
+                .as_deref()
+                .map(filter::parse_list_sort)
+                .transpose()?,
+            reverse: self.reverse,
+            limit: self.limit,
+            offset: self.offset,
+        })
+    }
+}
+
+#[derive(Subcommand)]
+enum QueueAction {
+    /// List all queues with their size and mode
+    #[command(alias = "ls")]
+    List,
+
+    /// Create a new empty queue
+    Create {
+        /// Queue name (lowercase letters, digits, '-' and '_')
+        name: String,
+
+        /// Selection mode for this queue (see `ytq config mode`)
+        #[arg(long)]
+        mode: Option<String>,
+    },
+
+    /// Rename a queue
+    Rename {
+        /// Current queue name
+        name: String,
+        /// New queue name
+        new_name: String,
+    },
+
+    /// Delete a queue
+    #[command(visible_alias = "rm")]
+    Delete {
+        /// Queue name
+        name: String,
+
+        /// Delete even if the queue still has videos (they are logged as skipped)
+        #[arg(long)]
+        force: bool,
+    },
+
+    /// Move a video from the selected queue (--queue) to another queue
+    #[command(visible_alias = "mv")]
+    Move {
+        /// The ID or URL to move
+        target: String,
+        /// Destination queue
+        destination: String,
+    },
 }
 
 fn main() {
-    if let Err(e) = run() {
+    let result = run();
+    commands::print_notices();
+    if let Err(e) = result {
         eprintln!("{} {e:#}", "error:".red());
         std::process::exit(1);
     }
@@ -150,13 +615,109 @@ fn main() {
 
 fn run() -> Result<()> {
     let cli = Cli::parse();
+    let queue = cli.queue.as_deref();
+    if let Some(queue) = queue {
+        models::validate_queue_name(queue)?;
+    }
+    let format = output::parse_format(&cli.format)?;
+    paths::set_overrides(paths::Overrides {
+        home: cli.home,
+        config_dir: cli.config_dir,
+        data_dir: cli.data_dir,
+    });
 
     match cli.command {
-        Commands::Add { input } => commands::add(&input),
-        Commands::Next { target } => commands::next(target.as_deref()),
-        Commands::List => commands::list(),
-        Commands::Peek { n } => commands::peek(n),
-        Commands::Remove { target } => commands::remove(&target),
+        Commands::Add {
+            input,
+            file,
+            video_only,
+            playlist,
+            from_here,
+            tags,
+            note,
+            priority,
+        } => {
+            let scope = if video_only {
+                PlaylistScope::VideoOnly
+            } else if playlist {
+                PlaylistScope::Whole
+            } else if from_here {
+                PlaylistScope::FromHere
+            } else {
+                PlaylistScope::Unspecified
+            };
+            match (input.as_deref(), file) {
+                (Some(input), None) if input != "-" => commands::add(
+                    input,
+                    queue,
+                    scope,
+                    &tags,
+                    note.as_deref(),
+                    priority.as_deref(),
+                ),
+                (_, file) => {
+                    if scope != PlaylistScope::Unspecified {
+                        anyhow::bail!(
+                            "--video-only, --playlist and --from-here only apply to a single URL"
+                        );
+                    }
+                    commands::add_bulk(
+                        file.as_deref(),
+                        queue,
+                        &tags,
+                        note.as_deref(),
+                        priority.as_deref(),
+                    )
+                }
+            }
+        }
+        Commands::Edit {
+            target,
+            tags,
+            untags,
+            note,
+            clear_note,
+            priority,
+        } => commands::edit(
+            &target,
+            queue,
+            &tags,
+            &untags,
+            note.as_deref(),
+            clear_note,
+            priority.as_deref(),
+        ),
+        Commands::Next {
+            target,
+            fits,
+            mode,
+            play,
+        } => {
+            let fits = fits
+                .as_deref()
+                .map(|v| filter::parse_duration("--fits", v))
+                .transpose()?;
+            let mode = mode.as_deref().map(commands::parse_mode).transpose()?;
+            commands::next(target.as_deref(), fits, mode, queue, &play.options()?)
+        }
+        Commands::List { filter } => commands::list(queue, format, &filter.filter()?),
+        Commands::Peek { n, mode } => {
+            let mode = mode.as_deref().map(commands::parse_mode).transpose()?;
+            commands::peek(n, mode, queue, format)
+        }
+        Commands::Plan { budget, mode } => {
+            let budget = filter::parse_duration("budget", &budget)?;
+            let mode = mode.as_deref().map(commands::parse_mode).transpose()?;
+            commands::plan(budget, mode, queue, format)
+        }
+        Commands::Remove { target } => commands::remove(&target, queue),
+        Commands::Move { target, position } => commands::move_to(&target, position, queue),
+        Commands::Seek { target, time } => commands::seek(&target, &time, queue),
+        Commands::Top { target } => commands::top(&target, queue),
+        Commands::Bottom { target } => commands::bottom(&target, queue),
+        Commands::Swap { a, b } => commands::swap(&a, &b, queue),
+        Commands::Undo { n } => commands::undo(n),
+        Commands::Redo { n } => commands::redo(n),
         Commands::Stats {
             wrapped,
             all,
@@ -165,12 +726,31 @@ fn run() -> Result<()> {
             year,
             from,
             to,
-        } => commands::stats(wrapped, all, week, month, year, from, to),
-        Commands::Config { key, value } => commands::config(&key, &value),
-        Commands::Info => commands::info(),
+        } => commands::stats(queue, wrapped, all, week, month, year, from, to),
+        Commands::Config { key, value } => commands::config(&key, &value, queue),
+        Commands::Info => commands::info(format),
+        Commands::Doctor { repair } => commands::doctor(repair),
+        Commands::MigrateStorage { target } => commands::migrate_storage(&target),
+        Commands::Export { file, bookmarks } => {
+            if bookmarks {
+                commands::export_bookmarks(file.as_deref(), queue)
+            } else {
+                commands::export(file.as_deref())
+            }
+        }
+        Commands::Import {
+            file,
+            replace,
+            takeout,
+            bookmarks,
+        } => match (takeout, bookmarks, file) {
+            (Some(takeout), _, _) => commands::import_takeout(&takeout, queue),
+            (None, Some(bookmarks), _) => commands::import_bookmarks(&bookmarks, queue),
+            (None, None, file) => commands::import(&file.unwrap_or_default(), replace),
+        },
         Commands::Fetch {
             target,
-            queue,
+            queue_only,
             history,
             all,
             limit,
@@ -179,12 +759,47 @@ fn run() -> Result<()> {
         } => commands::fetch(
             target.as_deref(),
             queue,
+            queue_only,
             history,
             all,
             limit,
             force,
             refresh_categories,
         ),
-        Commands::Random => commands::random(),
+        Commands::Search {
+            query,
+            queued,
+            watched,
+            limit,
+            next,
+            remove,
+        } => {
+            let scope = match (queued, watched) {
+                (true, _) => SearchScope::Queued,
+                (_, true) => SearchScope::Watched,
+                _ => SearchScope::All,
+            };
+            let action = match (next, remove) {
+                (true, _) => Some(SearchAction::Next),
+                (_, true) => Some(SearchAction::Remove),
+                _ => None,
+            };
+            commands::search(&query.join(" "), queue, scope, limit, action)
+        }
+        Commands::Random { play } => commands::random(queue, &play.options()?),
+        Commands::Subscribe { channel } => commands::subscribe(&channel, queue),
+        Commands::Unsubscribe { channel } => commands::unsubscribe(&channel),
+        Commands::Subscriptions => commands::subscriptions(),
+        Commands::Sync => commands::sync(queue),
+        Commands::Queue { action } => match action {
+            QueueAction::List => commands::queue_list(),
+            QueueAction::Create { name, mode } => commands::queue_create(&name, mode.as_deref()),
+            QueueAction::Rename { name, new_name } => commands::queue_rename(&name, &new_name),
+            QueueAction::Delete { name, force } => commands::queue_delete(&name, force),
+            QueueAction::Move {
+                target,
+                destination,
+            } => commands::queue_move(&target, queue, &destination),
+        },
     }
 }
@@ -1,14 +1,56 @@
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::env;
 
+use anyhow::{Result, bail};
 use chrono::{DateTime, Utc};
+use rand::{Rng, RngExt};
 use serde::{Deserialize, Serialize};
 
+/// Name of the queue backed by the original `queue.json` file.
+/// It always exists and cannot be renamed or deleted.
+pub const DEFAULT_QUEUE: &str = "default";
+
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
-#[serde(rename_all = "lowercase")]
+#[serde(rename_all = "kebab-case")]
 pub enum Mode {
     #[default]
     Queue, // First In, First Out (FIFO)
     Stack, // Last In, First Out (LIFO)
+    /// Shortest video first; videos without a known duration go last.
+    Shortest,
+    /// Longest video first; videos without a known duration go last.
+    Longest,
+    /// Earliest upload date first; unfetched videos go last.
+    OldestPublished,
+    /// FIFO, but never two videos from the same channel in a row while
+    /// another channel is waiting.
+    RoundRobin,
+    /// Random, weighted by time spent in the queue.
+    WeightedAge,
+    /// Random, weighted by priority instead of always taking the highest.
+    WeightedPriority,
+}
+
+/// Where queues, history and caches are stored. Changed with
+/// `ytq migrate-storage`, which moves the data along.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum StorageKind {
+    /// JSON files plus monthly JSONL history partitions.
+    #[default]
+    Json,
+    /// A single SQLite database, `ytq.sqlite3`.
+    Sqlite,
+}
+
+impl StorageKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            StorageKind::Json => "json",
+            StorageKind::Sqlite => "sqlite",
+        }
+    }
 }
 
 fn default_true() -> bool {
@@ -23,6 +65,23 @@ pub struct Config {
     pub offline: bool,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub youtube_api_key: Option<String>,
+    /// Per-queue settings, keyed by queue name. Queues without an entry
+    /// inherit the global settings above.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub queues: BTreeMap<String, QueueConfig>,
+    /// Base URL for channel pages and RSS feeds. Defaults to youtube.com;
+    /// can point at a local server for testing.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub feed_base_url: Option<String>,
+    /// Command template used to open videos (`{url}`, `{id}`, `{start}`).
+    /// Unset means the system's default handler (usually the browser).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub player: Option<String>,
+    /// Player overrides keyed by lowercase category name or category ID.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub category_players: BTreeMap<String, String>,
+    #[serde(default)]
+    pub storage: StorageKind,
 }
 
 impl Default for Config {
@@ -31,10 +90,24 @@ impl Default for Config {
             mode: Mode::Queue,
             offline: true,
             youtube_api_key: None,
+            queues: BTreeMap::new(),
+            feed_base_url: None,
+            player: None,
+            category_players: BTreeMap::new(),
+            storage: StorageKind::Json,
         }
     }
 }
 
+/// Settings that can be overridden for an individual named queue.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct QueueConfig {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mode: Option<Mode>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub player: Option<String>,
+}
+
 impl Config {
     /// Returns the effective API key, checking the environment variable first,
     /// then falling back to the config file value.
@@ -49,13 +122,311 @@ impl Config {
             .filter(|k| !k.is_empty())
             .or_else(|| config_val.clone())
     }
+
+    /// Returns the selection mode for a queue, falling back to the global mode
+    /// when the queue has no override.
+    pub fn mode_for(&self, queue: &str) -> Mode {
+        self.queues
+            .get(queue)
+            .and_then(|q| q.mode.clone())
+            .unwrap_or_else(|| self.mode.clone())
+    }
+
+    /// Returns the player command for a video: a category override wins over
+    /// a per-queue override, which wins over the global setting. `None`
+    /// means the system default handler.
+    pub fn player_for(
+        &self,
+        queue: &str,
+        category_id: Option<&str>,
+        category_name: Option<&str>,
+    ) -> Option<&str> {
+        let by_category = || {
+            category_name
+                .map(str::to_lowercase)
+                .and_then(|name| self.category_players.get(&name))
+                .or_else(|| category_id.and_then(|id| self.category_players.get(id)))
+        };
+        by_category()
+            .or_else(|| self.queues.get(queue).and_then(|q| q.player.as_ref()))
+            .or(self.player.as_ref())
+            .map(String::as_str)
+    }
+
+    /// Returns the base URL used for channel pages and RSS feeds.
+    pub fn feed_base_url(&self) -> &str {
+        self.feed_base_url
+            .as_deref()
+            .unwrap_or(DEFAULT_FEED_BASE_URL)
+            .trim_end_matches('/')
+    }
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+/// Where channel pages and `feeds/videos.xml` are served from.
+pub const DEFAULT_FEED_BASE_URL: &str = "https://www.youtube.com";
+
+/// Validates a user-supplied queue name. Names become file names, so they are
+/// restricted to lowercase ASCII letters, digits, `-` and `_`.
+pub fn validate_queue_name(name: &str) -> Result<()> {
+    if name.is_empty() || name.len() > 64 {
+        bail!("invalid queue name '{name}': must be 1-64 characters");
+    }
+    if !name
+        .chars()
+        .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-' || c == '_')
+    {
+        bail!("invalid queue name '{name}': use lowercase letters, digits, '-' or '_'");
+    }
+    Ok(())
+}
+
+/// What the metadata-aware modes need besides the queue itself. The default
+/// (no metadata, no history) is enough for `Queue` and `Stack`.
+#[derive(Debug, Default)]
+pub struct SelectionContext {
+    pub metadata: HashMap<String, VideoMeta>,
+    /// Channel ID of the video watched last from this queue, so round-robin
+    /// doesn't start with the same channel again.
+    pub last_channel: Option<String>,
+}
+
+impl Mode {
+    /// Names accepted by `ytq config mode` and `--mode`.
+    pub const NAMES: [&str; 8] = [
+        "queue",
+        "stack",
+        "shortest",
+        "longest",
+        "oldest-published",
+        "round-robin",
+        "weighted-age",
+        "weighted-priority",
+    ];
+
+    /// The name used in config.json and on the command line.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Mode::Queue => "queue",
+            Mode::Stack => "stack",
+            Mode::Shortest => "shortest",
+            Mode::Longest => "longest",
+            Mode::OldestPublished => "oldest-published",
+            Mode::RoundRobin => "round-robin",
+            Mode::WeightedAge => "weighted-age",
+            Mode::WeightedPriority => "weighted-priority",
+        }
+    }
+
+    /// Whether `selection_order` needs cached metadata.
+    pub fn needs_metadata(&self) -> bool {
+        matches!(
+            self,
+            Mode::Shortest | Mode::Longest | Mode::OldestPublished | Mode::RoundRobin
+        )
+    }
+
+    /// Whether each call may produce a different order.
+    pub fn is_random(&self) -> bool {
+        matches!(self, Mode::WeightedAge | Mode::WeightedPriority)
+    }
+
+    /// Returns queue indices in the order videos would be picked: highest
+    /// priority first, then the mode's order within the same priority.
+    /// `WeightedPriority` is the exception: priority only weights the draw.
+    pub fn selection_order(&self, videos: &[Video], context: &SelectionContext) -> Vec<usize> {
+        self.selection_order_with(videos, context, &mut rand::rng())
+    }
+
+    fn selection_order_with(
+        &self,
+        videos: &[Video],
+        context: &SelectionContext,
+        rng: &mut impl Rng,
+    ) -> Vec<usize> {
+        let meta = |i: usize| {
+            context
+                .metadata
+                .get(&videos[i].id)
+                .filter(|m| !m.unavailable)
+        };
+        // Live streams report a zero duration, which says nothing about length
+        let duration = |i: usize| meta(i).map(|m| m.duration_seconds).filter(|&d| d > 0);
+
+        let mut order: Vec<usize> = (0..videos.len()).collect();
+        match self {
+            Mode::Queue | Mode::RoundRobin => {}
+            Mode::Stack => order.reverse(),
+            Mode::Shortest => sort_known_first(&mut order, duration),
+            Mode::Longest => sort_known_first(&mut order, |i| duration(i).map(Reverse)),
+            Mode::OldestPublished => {
+                sort_known_first(&mut order, |i| meta(i).map(|m| m.published_at))
+            }
+            Mode::WeightedAge => {
+                let now = Utc::now();
+                weighted_shuffle(&mut order, rng, |i| {
+                    let age = now.signed_duration_since(videos[i].added_at);
+                    // One day in the queue counts as much as being added
+                    1.0 + age.num_seconds().max(0) as f64 / 86_400.0
+                });
+            }
+            Mode::WeightedPriority => {
+                weighted_shuffle(&mut order, rng, |i| match videos[i].priority {
+                    Priority::Low => 1.0,
+                    Priority::Normal => 2.0,
+                    Priority::High => 4.0,
+                });
+                return order;
+            }
+        }
+
+        // Stable sort keeps the mode order within each priority level
+        order.sort_by_key(|&i| Reverse(videos[i].priority));
+
+        if *self == Mode::RoundRobin {
+            let channel = |i: usize| meta(i).map(|m| m.channel_id.as_str());
+            order = spread_channels(&order, channel, context.last_channel.as_deref(), |i| {
+                videos[i].priority
+            });
+        }
+        order
+    }
+}
+
+/// Sorts by `key`, keeping entries without a key at the end in their
+/// current order.
+fn sort_known_first<K: Ord>(order: &mut [usize], key: impl Fn(usize) -> Option<K>) {
+    order.sort_by_key(|&i| {
+        let key = key(i);
+        (key.is_none(), key)
+    });
+}
+
+/// Weighted random permutation (Efraimidis-Spirakis): each entry draws
+/// `u^(1/weight)` and the largest draws go first.
+fn weighted_shuffle(order: &mut [usize], rng: &mut impl Rng, weight: impl Fn(usize) -> f64) {
+    let mut keyed: Vec<(f64, usize)> = order
+        .iter()
+        .map(|&i| {
+            // 1 - [0, 1) keeps u away from zero
+            let u: f64 = 1.0 - rng.random::<f64>();
+            (u.powf(1.0 / weight(i)), i)
+        })
+        .collect();
+    keyed.sort_by(|a, b| b.0.total_cmp(&a.0));
+    for (slot, (_, i)) in order.iter_mut().zip(keyed) {
+        *slot = i;
+    }
+}
+
+/// Reorders `order` so that consecutive videos come from different channels
+/// whenever possible: each step takes the earliest remaining video of the
+/// same priority whose channel differs from the previous one. Videos without
+/// a known channel never clash.
+fn spread_channels<'a>(
+    order: &[usize],
+    channel: impl Fn(usize) -> Option<&'a str>,
+    last_channel: Option<&'a str>,
+    priority: impl Fn(usize) -> Priority,
+) -> Vec<usize> {
+    let mut remaining = order.to_vec();
+    let mut result = Vec::with_capacity(order.len());
+    let mut previous = last_channel;
+
+    while let Some(&first) = remaining.first() {
+        let tier = priority(first);
+        let pos = remaining
+            .iter()
+            .take_while(|&&i| priority(i) == tier)
+            .position(|&i| channel(i).is_none() || channel(i) != previous)
+            .unwrap_or(0);
+        let i = remaining.remove(pos);
+        previous = channel(i);
+        result.push(i);
+    }
+    result
+}
+
+/// User-assigned importance of a queued video. Higher priorities are picked
+/// first by `next`, regardless of mode.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum Priority {
+    Low,
+    #[default]
+    Normal,
+    High,
+}
+
+impl Priority {
+    fn is_normal(&self) -> bool {
+        *self == Priority::Normal
+    }
+
+    /// Short label for list output. Normal priority is left blank.
+    pub fn label(&self) -> &'static str {
+        match self {
+            Priority::Low => "low",
+            Priority::Normal => "",
+            Priority::High => "high",
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct Video {
     pub id: String,
     pub url: String,
     pub added_at: DateTime<Utc>,
+    /// User-owned tags (distinct from the YouTube tags in `VideoMeta`).
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+    #[serde(default, skip_serializing_if = "Priority::is_normal")]
+    pub priority: Priority,
+    /// Where to resume playback, from a `t=` link or `ytq seek`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub start_seconds: Option<u64>,
+}
+
+impl Video {
+    /// Creates a video with no user annotations.
+    pub fn new(id: String, url: String, added_at: DateTime<Utc>) -> Self {
+        Self {
+            id,
+            url,
+            added_at,
+            tags: Vec::new(),
+            note: None,
+            priority: Priority::Normal,
+            start_seconds: None,
+        }
+    }
+
+    /// The URL to open, including the start offset if one is set.
+    pub fn watch_url(&self) -> String {
+        match self.start_seconds {
+            Some(start) => crate::youtube::url_with_start(&self.url, start),
+            None => self.url.clone(),
+        }
+    }
+
+    /// Adds tags, normalized to trimmed lowercase. Empty and duplicate tags
+    /// are ignored.
+    pub fn add_tags(&mut self, tags: &[String]) {
+        for tag in tags {
+            let tag = tag.trim().to_lowercase();
+            if !tag.is_empty() && !self.tags.contains(&tag) {
+                self.tags.push(tag);
+            }
+        }
+    }
+
+    /// Removes tags, matching case-insensitively.
+    pub fn remove_tags(&mut self, tags: &[String]) {
+        let remove: Vec<String> = tags.iter().map(|t| t.trim().to_lowercase()).collect();
+        self.tags.retain(|t| !remove.contains(t));
+    }
 }
 
 /// Video metadata fetched from the YouTube Data API v3.
@@ -78,11 +449,18 @@ pub struct VideoMeta {
     pub unavailable: bool,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
 pub enum Action {
     Queued,
     Watched,
     Skipped,
+    /// The video changed position within its queue (move/top/bottom/swap).
+    Reordered,
+    /// `ytq undo` reverted the most recent Queued/Watched/Skipped event for
+    /// this video in this queue.
+    Undone,
+    /// `ytq redo` reinstated the most recently undone event for this video.
+    Redone,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -91,11 +469,88 @@ pub struct Event {
     pub action: Action,
     pub video_id: String,
     pub time_in_queue_sec: Option<i64>,
+    /// Queue the event happened in. Events logged before named queues
+    /// existed have no queue and belong to the default queue.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub queue: Option<String>,
+}
+
+impl Event {
+    /// Returns the queue this event belongs to.
+    pub fn queue_name(&self) -> &str {
+        self.queue.as_deref().unwrap_or(DEFAULT_QUEUE)
+    }
+}
+
+/// A followed channel whose new uploads `ytq sync` adds to a queue.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Subscription {
+    pub channel_id: String,
+    /// Channel title from its feed, filled in on the first sync.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    /// The `@handle` the user subscribed with, if any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub handle: Option<String>,
+    /// Queue that new uploads are added to.
+    pub queue: String,
+    pub subscribed_at: DateTime<Utc>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_synced_at: Option<DateTime<Utc>>,
+    /// Video IDs already seen in the feed. They are never offered again, so
+    /// videos removed from the queue don't come back.
+    #[serde(default)]
+    pub offered: BTreeSet<String>,
+}
+
+impl Subscription {
+    pub fn new(channel_id: String, handle: Option<String>, queue: String) -> Self {
+        Self {
+            channel_id,
+            title: None,
+            handle,
+            queue,
+            subscribed_at: Utc::now(),
+            last_synced_at: None,
+            offered: BTreeSet::new(),
+        }
+    }
+
+    /// Whether a feed entry should be added to the queue: it must not have
+    /// been offered before and must be published after subscribing.
+    pub fn is_new_upload(&self, video_id: &str, published: DateTime<Utc>) -> bool {
+        !self.offered.contains(video_id) && published > self.subscribed_at
+    }
+
+    /// Name shown to the user: the title, else the handle, else the ID.
+    pub fn label(&self) -> String {
+        match (&self.title, &self.handle) {
+            (Some(title), _) => title.clone(),
+            (None, Some(handle)) => format!("@{handle}"),
+            (None, None) => self.channel_id.clone(),
+        }
+    }
+
+    /// Whether the user-supplied text names this subscription (channel ID,
+    /// `@handle` or title, case-insensitive).
+    pub fn matches(&self, query: &str) -> bool {
+        let query = query.trim();
+        self.channel_id == query
+            || self
+                .handle
+                .as_deref()
+                .is_some_and(|h| h.eq_ignore_ascii_case(query.trim_start_matches('@')))
+            || self
+                .title
+                .as_deref()
+                .is_some_and(|t| t.eq_ignore_ascii_case(query))
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::test_util;
 
     #[test]
     fn mode_default_is_queue() {
@@ -116,6 +571,11 @@ mod tests {
             mode: Mode::Stack,
             offline: false,
             youtube_api_key: Some("test-key-123".to_string()),
+            queues: BTreeMap::new(),
+            feed_base_url: None,
+            player: Some("mpv {url}".to_string()),
+            category_players: BTreeMap::new(),
+            storage: StorageKind::Json,
         };
 
         let json = serde_json::to_string(&cfg).unwrap();
@@ -124,6 +584,7 @@ mod tests {
         assert_eq!(parsed.mode, Mode::Stack);
         assert!(!parsed.offline);
         assert_eq!(parsed.youtube_api_key.as_deref(), Some("test-key-123"));
+        assert_eq!(parsed.player.as_deref(), Some("mpv {url}"));
     }
 
     #[test]
@@ -187,6 +648,52 @@ mod tests {
         assert!(result.is_none());
     }
 
+    #[test]
+    fn mode_for_uses_queue_override() {
+        let mut cfg = Config::default();
+        cfg.queues.insert(
+            "music".to_string(),
+            QueueConfig {
+                mode: Some(Mode::Stack),
+                ..Default::default()
+            },
+        );
+        cfg.queues
+            .insert("talks".to_string(), QueueConfig::default());
+
+        assert_eq!(cfg.mode_for("music"), Mode::Stack);
+        assert_eq!(cfg.mode_for("talks"), Mode::Queue);
+        assert_eq!(cfg.mode_for(DEFAULT_QUEUE), Mode::Queue);
+    }
+
+    #[test]
+    fn config_queues_not_serialized_when_empty() {
+        let json = serde_json::to_string(&Config::default()).unwrap();
+        assert!(!json.contains("queues"));
+    }
+
+    #[test]
+    fn validate_queue_name_accepts_simple_names() {
+        assert!(validate_queue_name("music").is_ok());
+        assert!(validate_queue_name("work-talks_2").is_ok());
+    }
+
+    #[test]
+    fn validate_queue_name_rejects_bad_names() {
+        assert!(validate_queue_name("").is_err());
+        assert!(validate_queue_name("Music").is_err());
+        assert!(validate_queue_name("../etc").is_err());
+        assert!(validate_queue_name("long form").is_err());
+    }
+
+    #[test]
+    fn event_without_queue_belongs_to_default() {
+        let json = r#"{"timestamp":"2026-01-01T00:00:00Z","action":"Watched","video_id":"dQw4w9WgXcQ","time_in_queue_sec":5}"#;
+        let event: Event = serde_json::from_str(json).unwrap();
+        assert!(event.queue.is_none());
+        assert_eq!(event.queue_name(), DEFAULT_QUEUE);
+    }
+
     #[test]
     fn mode_serializes_lowercase() {
         let json = serde_json::to_string(&Mode::Queue).unwrap();
@@ -198,11 +705,11 @@ mod tests {
 
     #[test]
     fn video_serde_roundtrip() {
-        let video = Video {
-            id: "dQw4w9WgXcQ".to_string(),
-            url: "https://youtube.com/watch?v=dQw4w9WgXcQ".to_string(),
-            added_at: Utc::now(),
-        };
+        let video = Video::new(
+            "dQw4w9WgXcQ".to_string(),
+            "https://youtube.com/watch?v=dQw4w9WgXcQ".to_string(),
+            Utc::now(),
+        );
 
         let json = serde_json::to_string(&video).unwrap();
         let parsed: Video = serde_json::from_str(&json).unwrap();
@@ -211,6 +718,223 @@ mod tests {
         assert_eq!(parsed.url, video.url);
     }
 
+    #[test]
+    fn video_annotations_not_serialized_when_empty() {
+        let video = Video::new("dQw4w9WgXcQ".to_string(), String::new(), Utc::now());
+        let json = serde_json::to_string(&video).unwrap();
+        assert!(!json.contains("tags"));
+        assert!(!json.contains("note"));
+        assert!(!json.contains("priority"));
+    }
+
+    #[test]
+    fn video_legacy_format_defaults_annotations() {
+        let json = r#"{"id":"dQw4w9WgXcQ","url":"u","added_at":"2026-01-01T00:00:00Z"}"#;
+        let video: Video = serde_json::from_str(json).unwrap();
+        assert!(video.tags.is_empty());
+        assert!(video.note.is_none());
+        assert_eq!(video.priority, Priority::Normal);
+    }
+
+    #[test]
+    fn video_annotations_roundtrip() {
+        let mut video = Video::new("dQw4w9WgXcQ".to_string(), String::new(), Utc::now());
+        video.tags = vec!["rust".to_string()];
+        video.note = Some("recommended by Sam".to_string());
+        video.priority = Priority::High;
+
+        let json = serde_json::to_string(&video).unwrap();
+        assert!(json.contains(r#""priority":"high""#));
+        let parsed: Video = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed.tags, vec!["rust"]);
+        assert_eq!(parsed.note.as_deref(), Some("recommended by Sam"));
+        assert_eq!(parsed.priority, Priority::High);
+    }
+
+    #[test]
+    fn add_tags_normalizes_and_dedupes() {
+        let mut video = Video::new("dQw4w9WgXcQ".to_string(), String::new(), Utc::now());
+        video.add_tags(&[" Rust ".to_string(), "rust".to_string(), String::new()]);
+        video.add_tags(&["Talks".to_string()]);
+        assert_eq!(video.tags, vec!["rust", "talks"]);
+
+        video.remove_tags(&["RUST".to_string()]);
+        assert_eq!(video.tags, vec!["talks"]);
+    }
+
+    fn video_with_priority(id: &str, priority: Priority) -> Video {
+        let mut video = Video::new(id.to_string(), String::new(), Utc::now());
+        video.priority = priority;
+        video
+    }
+
+    #[test]
+    fn selection_order_without_priorities_follows_mode() {
+        let videos = vec![
+            video_with_priority("a", Priority::Normal),
+            video_with_priority("b", Priority::Normal),
+            video_with_priority("c", Priority::Normal),
+        ];
+        assert_eq!(
+            Mode::Queue.selection_order(&videos, &SelectionContext::default()),
+            vec![0, 1, 2]
+        );
+        assert_eq!(
+            Mode::Stack.selection_order(&videos, &SelectionContext::default()),
+            vec![2, 1, 0]
+        );
+    }
+
+    #[test]
+    fn selection_order_prefers_higher_priority() {
+        let videos = vec![
+            video_with_priority("a", Priority::Low),
+            video_with_priority("b", Priority::Normal),
+            video_with_priority("c", Priority::High),
+            video_with_priority("d", Priority::Normal),
+            video_with_priority("e", Priority::High),
+        ];
+        assert_eq!(
+            Mode::Queue.selection_order(&videos, &SelectionContext::default()),
+            vec![2, 4, 1, 3, 0]
+        );
+        assert_eq!(
+            Mode::Stack.selection_order(&videos, &SelectionContext::default()),
+            vec![4, 2, 3, 1, 0]
+        );
+    }
+
+    fn meta_for(id: &str, channel_id: &str, duration_seconds: u64, published: &str) -> VideoMeta {
+        VideoMeta {
+            channel_id: channel_id.to_string(),
+            duration_seconds,
+            published_at: published.parse().unwrap(),
+            ..test_util::meta(id)
+        }
+    }
+
+    /// Videos a-e: c has no metadata, and a/b/d share a channel.
+    fn strategy_fixture() -> (Vec<Video>, SelectionContext) {
+        let videos: Vec<Video> = ["a", "b", "c", "d", "e"]
+            .iter()
+            .map(|id| video_with_priority(id, Priority::Normal))
+            .collect();
+        let metadata = HashMap::from([
+            (
+                "a".to_string(),
+                meta_for("a", "UC1", 600, "2020-01-01T00:00:00Z"),
+            ),
+            (
+                "b".to_string(),
+                meta_for("b", "UC1", 60, "2018-01-01T00:00:00Z"),
+            ),
+            (
+                "d".to_string(),
+                meta_for("d", "UC1", 0, "2022-01-01T00:00:00Z"),
+            ),
+            (
+                "e".to_string(),
+                meta_for("e", "UC2", 300, "2019-01-01T00:00:00Z"),
+            ),
+        ]);
+        let context = SelectionContext {
+            metadata,
+            last_channel: None,
+        };
+        (videos, context)
+    }
+
+    #[test]
+    fn duration_and_published_strategies_put_unknown_last() {
+        let (videos, context) = strategy_fixture();
+        // d is a live stream (zero duration), so its length is unknown too
+        assert_eq!(
+            Mode::Shortest.selection_order(&videos, &context),
+            vec![1, 4, 0, 2, 3]
+        );
+        assert_eq!(
+            Mode::Longest.selection_order(&videos, &context),
+            vec![0, 4, 1, 2, 3]
+        );
+        assert_eq!(
+            Mode::OldestPublished.selection_order(&videos, &context),
+            vec![1, 4, 0, 3, 2]
+        );
+    }
+
+    #[test]
+    fn strategies_still_respect_priority() {
+        let (mut videos, context) = strategy_fixture();
+        videos[0].priority = Priority::High;
+        assert_eq!(Mode::Shortest.selection_order(&videos, &context)[0], 0);
+    }
+
+    #[test]
+    fn round_robin_avoids_same_channel_twice() {
+        let (videos, mut context) = strategy_fixture();
+        // a, b, d are UC1; c has no channel; e is UC2
+        assert_eq!(
+            Mode::RoundRobin.selection_order(&videos, &context),
+            vec![0, 2, 1, 4, 3]
+        );
+
+        // Having just watched UC1, start with another channel
+        context.last_channel = Some("UC1".to_string());
+        assert_eq!(
+            Mode::RoundRobin.selection_order(&videos, &context),
+            vec![2, 0, 4, 1, 3]
+        );
+    }
+
+    #[test]
+    fn weighted_modes_return_a_permutation_favouring_heavy_entries() {
+        use rand::SeedableRng;
+        let mut rng = rand::rngs::StdRng::seed_from_u64(7);
+        let now = Utc::now();
+        let by_age = {
+            let mut videos = vec![
+                video_with_priority("a", Priority::Normal),
+                video_with_priority("b", Priority::Normal),
+            ];
+            videos[0].added_at = now;
+            videos[1].added_at = now - chrono::Duration::days(3);
+            videos
+        };
+        let by_priority = vec![
+            video_with_priority("a", Priority::Low),
+            video_with_priority("b", Priority::High),
+        ];
+
+        // b weighs 4 against a's 1 in both cases, so it should come first
+        // about 80% of the time
+        let context = SelectionContext::default();
+        for (mode, videos) in [
+            (Mode::WeightedAge, by_age),
+            (Mode::WeightedPriority, by_priority),
+        ] {
+            let mut heavy_first = 0;
+            for _ in 0..200 {
+                let order = mode.selection_order_with(&videos, &context, &mut rng);
+                let mut sorted = order.clone();
+                sorted.sort();
+                assert_eq!(sorted, vec![0, 1]);
+                if order[0] == 1 {
+                    heavy_first += 1;
+                }
+            }
+            assert!((130..190).contains(&heavy_first), "{mode:?}: {heavy_first}");
+        }
+    }
+
+    #[test]
+    fn mode_names_roundtrip_through_serde() {
+        for name in Mode::NAMES {
+            let mode: Mode = serde_json::from_value(serde_json::json!(name)).unwrap();
+            assert_eq!(serde_json::to_value(&mode).unwrap(), name);
+            assert_eq!(mode.name(), name);
+        }
+    }
+
     #[test]
     fn video_meta_serde_roundtrip() {
         let meta = VideoMeta {
@@ -283,4 +1007,73 @@ mod tests {
         let result = serde_json::from_str::<VideoMeta>(old_json);
         assert!(result.is_err());
     }
+
+    #[test]
+    fn subscription_offers_only_unseen_uploads_after_subscribing() {
+        let mut sub = Subscription::new("UCxxxxxxxxxxxxxxxxxxxxxx".into(), None, "default".into());
+        sub.subscribed_at = "2026-01-10T00:00:00Z".parse().unwrap();
+        sub.offered.insert("seen1234567".to_string());
+
+        let after: DateTime<Utc> = "2026-01-11T00:00:00Z".parse().unwrap();
+        let before: DateTime<Utc> = "2026-01-09T00:00:00Z".parse().unwrap();
+        assert!(sub.is_new_upload("new12345678", after));
+        assert!(!sub.is_new_upload("seen1234567", after));
+        assert!(!sub.is_new_upload("old12345678", before));
+    }
+
+    #[test]
+    fn subscription_matches_id_handle_and_title() {
+        let mut sub = Subscription::new(
+            "UCxxxxxxxxxxxxxxxxxxxxxx".into(),
+            Some("Some.Handle".into()),
+            "default".into(),
+        );
+        sub.title = Some("Some Channel".to_string());
+        assert!(sub.matches("UCxxxxxxxxxxxxxxxxxxxxxx"));
+        assert!(sub.matches("@some.handle"));
+        assert!(sub.matches("some channel"));
+        assert!(!sub.matches("other"));
+        assert_eq!(sub.label(), "Some Channel");
+    }
+
+    #[test]
+    fn feed_base_url_defaults_and_trims() {
+        let mut cfg = Config::default();
+        assert_eq!(cfg.feed_base_url(), DEFAULT_FEED_BASE_URL);
+        cfg.feed_base_url = Some("http://127.0.0.1:8080/".to_string());
+        assert_eq!(cfg.feed_base_url(), "http://127.0.0.1:8080");
+    }
+
+    #[test]
+    fn player_for_prefers_category_then_queue_then_global() {
+        let mut cfg = Config::default();
+        assert_eq!(cfg.player_for("default", None, None), None);
+
+        cfg.player = Some("mpv {url}".to_string());
+        cfg.queues.insert(
+            "work".to_string(),
+            QueueConfig {
+                player: Some("firefox -P work {url}".to_string()),
+                ..Default::default()
+            },
+        );
+        cfg.category_players
+            .insert("music".to_string(), "mpv --no-video {url}".to_string());
+        cfg.category_players
+            .insert("27".to_string(), "chromium {url}".to_string());
+
+        assert_eq!(cfg.player_for("default", None, None), Some("mpv {url}"));
+        assert_eq!(
+            cfg.player_for("work", Some("22"), Some("People & Blogs")),
+            Some("firefox -P work {url}")
+        );
+        assert_eq!(
+            cfg.player_for("work", Some("10"), Some("Music")),
+            Some("mpv --no-video {url}")
+        );
+        assert_eq!(
+            cfg.player_for("default", Some("27"), None),
+            Some("chromium {url}")
+        );
+    }
 }
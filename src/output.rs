@@ -0,0 +1,356 @@
+use std::collections::HashMap;
+use std::io::{self, Write};
+
+use crate::models::{Priority, Video, VideoMeta};
+
+use anyhow::{Result, bail};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+/// Output format selected with the global `--format` flag.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Format {
+    /// Colored, human-readable output.
+    #[default]
+    Table,
+    Json,
+    Csv,
+    Tsv,
+}
+
+impl Format {
+    /// Whether the output is meant for other programs.
+    pub fn is_machine(&self) -> bool {
+        *self != Format::Table
+    }
+}
+
+pub fn parse_format(value: &str) -> Result<Format> {
+    match value.to_lowercase().as_str() {
+        "table" => Ok(Format::Table),
+        "json" => Ok(Format::Json),
+        "csv" => Ok(Format::Csv),
+        "tsv" => Ok(Format::Tsv),
+        _ => bail!("invalid format '{value}': use 'table', 'json', 'csv' or 'tsv'"),
+    }
+}
+
+/// A queue entry joined with its cached metadata. Field names are part of
+/// the output format; only add new fields, never rename existing ones.
+#[derive(Serialize, Debug)]
+pub struct VideoRow {
+    /// 1-based position in the queue, as used by `ytq move`.
+    pub position: usize,
+    pub queue: String,
+    pub id: String,
+    pub url: String,
+    pub added_at: DateTime<Utc>,
+    pub start_seconds: Option<u64>,
+    pub priority: Priority,
+    pub tags: Vec<String>,
+    pub note: Option<String>,
+    pub title: Option<String>,
+    pub channel: Option<String>,
+    pub channel_id: Option<String>,
+    pub duration_seconds: Option<u64>,
+    pub published_at: Option<DateTime<Utc>>,
+    pub category_id: Option<String>,
+    pub category: Option<String>,
+    /// `None` when metadata was never fetched.
+    pub unavailable: Option<bool>,
+}
+
+/// Column order for CSV and TSV output.
+const VIDEO_FIELDS: [&str; 17] = [
+    "position",
+    "queue",
+    "id",
+    "url",
+    "added_at",
+    "start_seconds",
+    "priority",
+    "tags",
+    "note",
+    "title",
+    "channel",
+    "channel_id",
+    "duration_seconds",
+    "published_at",
+    "category_id",
+    "category",
+    "unavailable",
+];
+
+impl VideoRow {
+    pub fn new(
+        position: usize,
+        queue: &str,
+        video: &Video,
+        meta: Option<&VideoMeta>,
+        categories: &HashMap<String, String>,
+    ) -> Self {
+        // Tombstones carry no real metadata, only the unavailable flag
+        let meta_ok = meta.filter(|m| !m.unavailable);
+
+        Self {
+            position,
+            queue: queue.to_string(),
+            id: video.id.clone(),
+            url: video.watch_url(),
+            added_at: video.added_at,
+            start_seconds: video.start_seconds,
+            priority: video.priority,
+            tags: video.tags.clone(),
+            note: video.note.clone(),
+            title: meta_ok.map(|m| m.title.clone()),
+            channel: meta_ok.map(|m| m.channel.clone()),
+            channel_id: meta_ok.map(|m| m.channel_id.clone()),
+            duration_seconds: meta_ok.map(|m| m.duration_seconds),
+            published_at: meta_ok.map(|m| m.published_at),
+            category_id: meta_ok.map(|m| m.category_id.clone()),
+            category: meta_ok.and_then(|m| categories.get(&m.category_id).cloned()),
+            unavailable: meta.map(|m| m.unavailable),
+        }
+    }
+
+    fn cells(&self) -> Vec<String> {
+        fn opt<T: ToString>(value: &Option<T>) -> String {
+            value.as_ref().map(T::to_string).unwrap_or_default()
+        }
+
+        vec![
+            self.position.to_string(),
+            self.queue.clone(),
+            self.id.clone(),
+            self.url.clone(),
+            self.added_at.to_rfc3339(),
+            opt(&self.start_seconds),
+            format!("{:?}", self.priority).to_lowercase(),
+            self.tags.join(","),
+            opt(&self.note),
+            opt(&self.title),
+            opt(&self.channel),
+            opt(&self.channel_id),
+            opt(&self.duration_seconds),
+            self.published_at
+                .map(|t| t.to_rfc3339())
+                .unwrap_or_default(),
+            opt(&self.category_id),
+            opt(&self.category),
+            opt(&self.unavailable),
+        ]
+    }
+}
+
+/// Writes video rows as a JSON array, or CSV/TSV with a header line.
+pub fn write_video_rows(out: &mut impl Write, format: Format, rows: &[VideoRow]) -> io::Result<()> {
+    match format {
+        Format::Json => write_json(out, rows),
+        Format::Csv | Format::Tsv => {
+            write_record(out, format, VIDEO_FIELDS.iter().map(|f| f.to_string()))?;
+            for row in rows {
+                write_record(out, format, row.cells())?;
+            }
+            Ok(())
+        }
+        Format::Table => unreachable!("tables are printed by the commands themselves"),
+    }
+}
+
+/// Writes `(key, value)` pairs as a JSON object, or as two-column CSV/TSV.
+pub fn write_key_values(
+    out: &mut impl Write,
+    format: Format,
+    pairs: &[(&str, serde_json::Value)],
+) -> io::Result<()> {
+    match format {
+        Format::Json => {
+            let object: serde_json::Map<String, serde_json::Value> = pairs
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.clone()))
+                .collect();
+            write_json(out, &object)
+        }
+        Format::Csv | Format::Tsv => {
+            write_record(out, format, ["key".to_string(), "value".to_string()])?;
+            for (key, value) in pairs {
+                let value = match value {
+                    serde_json::Value::String(s) => s.clone(),
+                    other => other.to_string(),
+                };
+                write_record(out, format, [key.to_string(), value])?;
+            }
+            Ok(())
+        }
+        Format::Table => unreachable!("tables are printed by the commands themselves"),
+    }
+}
+
+/// Runs `f` against stdout. A closed pipe (e.g. `ytq list --format csv | head`)
+/// is not an error.
+pub fn to_stdout(f: impl FnOnce(&mut io::StdoutLock) -> io::Result<()>) -> Result<()> {
+    let mut out = io::stdout().lock();
+    match f(&mut out).and_then(|()| out.flush()) {
+        Err(e) if e.kind() == io::ErrorKind::BrokenPipe => Ok(()),
+        result => Ok(result?),
+    }
+}
+
+fn write_json(out: &mut impl Write, value: &(impl Serialize + ?Sized)) -> io::Result<()> {
+    serde_json::to_writer_pretty(&mut *out, value)?;
+    writeln!(out)
+}
+
+fn write_record(
+    out: &mut impl Write,
+    format: Format,
+    fields: impl IntoIterator<Item = String>,
+) -> io::Result<()> {
+    let (separator, escape): (&str, fn(&str) -> String) = match format {
+        Format::Tsv => ("\t", escape_tsv),
+        _ => (",", escape_csv),
+    };
+    let line: Vec<String> = fields.into_iter().map(|f| escape(&f)).collect();
+    writeln!(out, "{}", line.join(separator))
+}
+
+/// Quotes a CSV field when it contains a comma, quote or line break (RFC 4180).
+fn escape_csv(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// TSV has no quoting, so tabs and line breaks become spaces.
+fn escape_tsv(field: &str) -> String {
+    field.replace(['\t', '\n', '\r'], " ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util;
+
+    fn video() -> Video {
+        let mut video = Video::new(
+            "dQw4w9WgXcQ".to_string(),
+            "https://www.youtube.com/watch?v=dQw4w9WgXcQ".to_string(),
+            "2026-01-01T00:00:00Z".parse().unwrap(),
+        );
+        video.tags = vec!["music".to_string(), "classics".to_string()];
+        video.note = Some("from \"Sam\", on chat".to_string());
+        video
+    }
+
+    fn meta() -> VideoMeta {
+        VideoMeta {
+            title: "Never Gonna Give You Up".to_string(),
+            channel: "Rick Astley".to_string(),
+            channel_id: "UCuAXFkgsw1L7xaCfnd5JJOw".to_string(),
+            duration: "PT3M33S".to_string(),
+            duration_seconds: 213,
+            published_at: "2009-10-25T06:57:33Z".parse().unwrap(),
+            category_id: "10".to_string(),
+            fetched_at: "2026-01-02T00:00:00Z".parse().unwrap(),
+            ..test_util::meta("dQw4w9WgXcQ")
+        }
+    }
+
+    fn categories() -> HashMap<String, String> {
+        HashMap::from([("10".to_string(), "Music".to_string())])
+    }
+
+    fn render(format: Format, rows: &[VideoRow]) -> String {
+        let mut out = Vec::new();
+        write_video_rows(&mut out, format, rows).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn parse_format_accepts_known_values() {
+        assert_eq!(parse_format("JSON").unwrap(), Format::Json);
+        assert_eq!(parse_format("tsv").unwrap(), Format::Tsv);
+        assert!(parse_format("xml").is_err());
+    }
+
+    #[test]
+    fn video_row_joins_metadata_and_category_name() {
+        let meta = meta();
+        let row = VideoRow::new(1, "default", &video(), Some(&meta), &categories());
+        assert_eq!(row.title.as_deref(), Some("Never Gonna Give You Up"));
+        assert_eq!(row.category.as_deref(), Some("Music"));
+        assert_eq!(row.duration_seconds, Some(213));
+        assert_eq!(row.unavailable, Some(false));
+    }
+
+    #[test]
+    fn video_row_without_metadata_or_with_tombstone() {
+        let row = VideoRow::new(1, "default", &video(), None, &categories());
+        assert!(row.title.is_none());
+        assert!(row.unavailable.is_none());
+
+        let mut tombstone = meta();
+        tombstone.unavailable = true;
+        let row = VideoRow::new(1, "default", &video(), Some(&tombstone), &categories());
+        assert!(row.title.is_none());
+        assert_eq!(row.unavailable, Some(true));
+    }
+
+    #[test]
+    fn json_output_has_stable_field_names() {
+        let meta = meta();
+        let rows = [VideoRow::new(
+            1,
+            "default",
+            &video(),
+            Some(&meta),
+            &categories(),
+        )];
+        let parsed: serde_json::Value = serde_json::from_str(&render(Format::Json, &rows)).unwrap();
+        let row = &parsed[0];
+        for field in VIDEO_FIELDS {
+            assert!(row.get(field).is_some(), "missing field {field}");
+        }
+        assert_eq!(row["priority"], "normal");
+        assert_eq!(row["tags"][1], "classics");
+    }
+
+    #[test]
+    fn csv_output_quotes_special_characters() {
+        let rows = [VideoRow::new(1, "default", &video(), None, &categories())];
+        let csv = render(Format::Csv, &rows);
+        let mut lines = csv.lines();
+        assert_eq!(lines.next().unwrap(), VIDEO_FIELDS.join(","));
+        let row = lines.next().unwrap();
+        assert!(row.contains(",\"music,classics\","));
+        assert!(row.contains(",\"from \"\"Sam\"\", on chat\","));
+    }
+
+    #[test]
+    fn tsv_output_flattens_tabs_and_newlines() {
+        let mut video = video();
+        video.note = Some("line one\nline\ttwo".to_string());
+        let rows = [VideoRow::new(1, "default", &video, None, &categories())];
+        let tsv = render(Format::Tsv, &rows);
+        let row = tsv.lines().nth(1).unwrap();
+        assert_eq!(row.split('\t').count(), VIDEO_FIELDS.len());
+        assert!(row.contains("line one line two"));
+    }
+
+    #[test]
+    fn key_values_as_csv() {
+        let mut out = Vec::new();
+        write_key_values(
+            &mut out,
+            Format::Csv,
+            &[("config", "/tmp/a,b".into()), ("exists", true.into())],
+        )
+        .unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "key,value\nconfig,\"/tmp/a,b\"\nexists,true\n"
+        );
+    }
+}
@@ -1,7 +1,10 @@
 use anyhow::{Context, Result};
 use etcetera::app_strategy::{AppStrategy, AppStrategyArgs};
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use crate::models::DEFAULT_QUEUE;
 
 // Choose the Strategy based on OS
 // Windows -> AppData\Roaming\ytq
@@ -12,30 +15,87 @@ use etcetera::app_strategy::Windows as Strategy;
 #[cfg(not(target_os = "windows"))]
 use etcetera::app_strategy::Xdg as Strategy;
 
+/// Directories chosen with the global `--home`, `--config-dir` and
+/// `--data-dir` flags or their `YTQ_*` environment variables. The more
+/// specific directory wins; whatever is unset uses the platform default.
+#[derive(Debug, Default, Clone)]
+pub struct Overrides {
+    /// Holds both the config and the data.
+    pub home: Option<PathBuf>,
+    pub config_dir: Option<PathBuf>,
+    pub data_dir: Option<PathBuf>,
+}
+
+static OVERRIDES: OnceLock<Overrides> = OnceLock::new();
+
+/// Records the overrides for every later `AppPaths::init`. Only the first
+/// call has any effect.
+pub fn set_overrides(overrides: Overrides) {
+    let _ = OVERRIDES.set(overrides);
+}
+
 pub struct AppPaths {
+    pub config_dir: PathBuf,
+    pub data_dir: PathBuf,
+    /// Which flag or variable moved each directory, if any, for `ytq info`.
+    pub config_dir_source: Option<&'static str>,
+    pub data_dir_source: Option<&'static str>,
     pub config_file: PathBuf,
     pub queue_file: PathBuf,
+    pub queues_dir: PathBuf,
     pub history_dir: PathBuf,
     pub lock_file: PathBuf,
     pub metadata_file: PathBuf,
     pub categories_file: PathBuf,
+    pub journal_file: PathBuf,
+    pub subscriptions_file: PathBuf,
+    /// Used instead of the JSON files when `storage` is `sqlite`.
+    pub database_file: PathBuf,
 }
 
 impl AppPaths {
     pub fn init() -> Result<Self> {
+        let overrides = OVERRIDES.get().cloned().unwrap_or_default();
+        let home = overrides.home.map(|dir| (dir, "--home / YTQ_HOME"));
+        let config = overrides
+            .config_dir
+            .map(|dir| (dir, "--config-dir / YTQ_CONFIG_DIR"))
+            .or(home.clone());
+        let data = overrides
+            .data_dir
+            .map(|dir| (dir, "--data-dir / YTQ_DATA_DIR"))
+            .or(home);
+
         let args = AppStrategyArgs {
             top_level_domain: "com".to_string(),
             author: "ytq".to_string(),
             app_name: "ytq".to_string(),
         };
-
-        let strategy =
-            Strategy::new(args).map_err(|_| anyhow::anyhow!("Could not determine system paths"))?;
+        // Only needed when something isn't overridden
+        let strategy = || {
+            Strategy::new(args.clone())
+                .map_err(|_| anyhow::anyhow!("Could not determine system paths"))
+        };
 
         // Resolve base directories
-        let config_dir = strategy.config_dir();
-        let data_dir = strategy.data_dir();
+        let (config_dir, config_source) = match config {
+            Some((dir, source)) => (absolute(&dir)?, Some(source)),
+            None => (strategy()?.config_dir(), None),
+        };
+        let (data_dir, data_source) = match data {
+            Some((dir, source)) => (absolute(&dir)?, Some(source)),
+            None => (strategy()?.data_dir(), None),
+        };
 
+        let mut paths = Self::at(config_dir, data_dir)?;
+        paths.config_dir_source = config_source;
+        paths.data_dir_source = data_source;
+        Ok(paths)
+    }
+
+    /// Lays out the files under the given config and data directories,
+    /// creating the directories as needed.
+    pub fn at(config_dir: PathBuf, data_dir: PathBuf) -> Result<Self> {
         fs::create_dir_all(&config_dir)
             .with_context(|| format!("failed to create config dir: {}", config_dir.display()))?;
 
@@ -46,6 +106,10 @@ impl AppPaths {
         fs::create_dir_all(&history_dir)
             .with_context(|| format!("failed to create history dir: {}", history_dir.display()))?;
 
+        let queues_dir = data_dir.join("queues");
+        fs::create_dir_all(&queues_dir)
+            .with_context(|| format!("failed to create queues dir: {}", queues_dir.display()))?;
+
         // Return the specific file paths we need
         Ok(Self {
             config_file: config_dir.join("config.json"),
@@ -53,7 +117,29 @@ impl AppPaths {
             lock_file: data_dir.join("queue.json.lock"),
             metadata_file: data_dir.join("metadata.json"),
             categories_file: data_dir.join("categories.json"),
+            journal_file: data_dir.join("journal.json"),
+            subscriptions_file: data_dir.join("subscriptions.json"),
+            database_file: data_dir.join("ytq.sqlite3"),
+            queues_dir,
             history_dir,
+            config_dir,
+            data_dir,
+            config_dir_source: None,
+            data_dir_source: None,
         })
     }
+
+    /// Returns the file backing a named queue. The default queue keeps using
+    /// `queue.json`; every other queue lives in `queues/<name>.json`.
+    pub fn queue_file_for(&self, name: &str) -> PathBuf {
+        if name == DEFAULT_QUEUE {
+            self.queue_file.clone()
+        } else {
+            self.queues_dir.join(format!("{name}.json"))
+        }
+    }
+}
+
+fn absolute(dir: &Path) -> Result<PathBuf> {
+    std::path::absolute(dir).with_context(|| format!("invalid directory: {}", dir.display()))
 }
@@ -0,0 +1,147 @@
+use std::io::ErrorKind;
+use std::process::Command;
+
+use anyhow::{Result, anyhow, bail};
+
+/// Values substituted into a player command template.
+pub struct PlayerArgs<'a> {
+    /// Watch URL, including the start offset if any.
+    pub url: &'a str,
+    pub id: &'a str,
+    /// Start offset in seconds (0 when the video starts from the beginning).
+    pub start: u64,
+}
+
+/// Splits a player template into arguments and fills in the `{url}`, `{id}`
+/// and `{start}` placeholders. Arguments are split on whitespace; single or
+/// double quotes group words, as in a shell. No shell is involved, so URLs
+/// never need escaping.
+pub fn build_command(template: &str, args: &PlayerArgs) -> Result<Vec<String>> {
+    let words = split_words(template)?;
+    if words.is_empty() {
+        bail!("player command is empty");
+    }
+
+    Ok(words
+        .into_iter()
+        .map(|word| {
+            word.replace("{url}", args.url)
+                .replace("{id}", args.id)
+                .replace("{start}", &args.start.to_string())
+        })
+        .collect())
+}
+
+/// Starts the player without waiting for it to exit.
+pub fn launch(template: &str, args: &PlayerArgs) -> Result<()> {
+    let command = build_command(template, args)?;
+    let (program, rest) = command.split_first().expect("command is not empty");
+
+    match Command::new(program).args(rest).spawn() {
+        Ok(_) => Ok(()),
+        Err(e) if e.kind() == ErrorKind::NotFound => Err(anyhow!(
+            "player '{program}' was not found. Check the `player` setting or --player flag."
+        )),
+        Err(e) => Err(anyhow!(e).context(format!("failed to start player '{program}'"))),
+    }
+}
+
+/// Shell-like word splitting supporting single and double quotes.
+fn split_words(input: &str) -> Result<Vec<String>> {
+    let mut words = Vec::new();
+    let mut current = String::new();
+    let mut in_word = false;
+    let mut quote: Option<char> = None;
+
+    for c in input.chars() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), c) => current.push(c),
+            (None, '\'' | '"') => {
+                quote = Some(c);
+                in_word = true;
+            }
+            (None, c) if c.is_whitespace() => {
+                if in_word {
+                    words.push(std::mem::take(&mut current));
+                    in_word = false;
+                }
+            }
+            (None, c) => {
+                current.push(c);
+                in_word = true;
+            }
+        }
+    }
+
+    if let Some(q) = quote {
+        bail!("unterminated {q} quote in player command '{input}'");
+    }
+    if in_word {
+        words.push(current);
+    }
+
+    Ok(words)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args() -> PlayerArgs<'static> {
+        PlayerArgs {
+            url: "https://www.youtube.com/watch?v=dQw4w9WgXcQ&t=90s",
+            id: "dQw4w9WgXcQ",
+            start: 90,
+        }
+    }
+
+    #[test]
+    fn build_command_substitutes_placeholders() {
+        let cmd = build_command("mpv --start={start} {url}", &args()).unwrap();
+        assert_eq!(
+            cmd,
+            vec![
+                "mpv",
+                "--start=90",
+                "https://www.youtube.com/watch?v=dQw4w9WgXcQ&t=90s"
+            ]
+        );
+    }
+
+    #[test]
+    fn build_command_respects_quotes() {
+        let cmd = build_command(
+            r#"firefox -P "Work Profile" 'https://youtu.be/{id}'"#,
+            &args(),
+        )
+        .unwrap();
+        assert_eq!(
+            cmd,
+            vec![
+                "firefox",
+                "-P",
+                "Work Profile",
+                "https://youtu.be/dQw4w9WgXcQ"
+            ]
+        );
+    }
+
+    #[test]
+    fn build_command_rejects_empty_and_unterminated() {
+        assert!(build_command("   ", &args()).is_err());
+        assert!(build_command("mpv \"{url}", &args()).is_err());
+    }
+
+    #[test]
+    fn build_command_keeps_empty_quoted_argument() {
+        let cmd = build_command(r#"player "" {id}"#, &args()).unwrap();
+        assert_eq!(cmd, vec!["player", "", "dQw4w9WgXcQ"]);
+    }
+
+    #[test]
+    fn launch_reports_missing_program() {
+        let err = launch("ytq-no-such-player-binary {url}", &args()).unwrap_err();
+        assert!(err.to_string().contains("was not found"));
+    }
+}
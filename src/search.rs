@@ -0,0 +1,163 @@
+/// Text of one video that `ytq search` matches against.
+pub struct SearchFields<'a> {
+    pub id: &'a str,
+    pub title: Option<&'a str>,
+    pub channel: Option<&'a str>,
+    /// User tags from the queue entry plus the uploader's tags from metadata.
+    pub tags: Vec<&'a str>,
+}
+
+/// Scores `fields` against a query. Every whitespace-separated term must
+/// match at least one field; the best match of each term is added up.
+/// Returns `None` when some term matches nothing.
+pub fn score(query: &str, fields: &SearchFields) -> Option<u32> {
+    let mut total = 0;
+    let mut any_term = false;
+
+    for term in query.split_whitespace() {
+        any_term = true;
+        let term = term.to_lowercase();
+
+        // Pasting a full video ID should always find that video first
+        let id_score = (fields.id.to_lowercase() == term).then_some(3000);
+        let weighted = [(fields.title, 3), (fields.channel, 2)]
+            .into_iter()
+            .filter_map(|(text, weight)| Some(term_score(&term, text?)? * weight))
+            .chain(
+                fields
+                    .tags
+                    .iter()
+                    .filter_map(|tag| term_score(&term, tag).map(|s| s * 2)),
+            )
+            .chain(term_score(&term, fields.id))
+            .chain(id_score)
+            .max()?;
+
+        total += weighted;
+    }
+
+    any_term.then_some(total)
+}
+
+/// Scores one lowercase term against a piece of text: substrings beat
+/// scattered letters, and matches at the start of a word beat ones in the
+/// middle. Scattered matches must keep the letters reasonably close together.
+fn term_score(term: &str, text: &str) -> Option<u32> {
+    let text = text.to_lowercase();
+    if term.is_empty() {
+        return None;
+    }
+
+    if text == term {
+        return Some(1000);
+    }
+    if let Some(pos) = text.find(term) {
+        let boundary = if is_word_start(&text, pos) { 200 } else { 0 };
+        // Earlier matches rank slightly higher
+        let position_penalty = text[..pos].chars().count().min(100) as u32;
+        return Some(500 + boundary - position_penalty);
+    }
+
+    subsequence_score(term, &text)
+}
+
+/// Matches the term's letters in order, allowing gaps. Returns `None` when a
+/// letter is missing or the gaps add up to more than twice the term length.
+fn subsequence_score(term: &str, text: &str) -> Option<u32> {
+    let term: Vec<char> = term.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+
+    let mut score = 0u32;
+    let mut gaps = 0usize;
+    let mut next = 0;
+    let mut previous: Option<usize> = None;
+
+    for &wanted in &term {
+        let found = (next..text.len()).find(|&i| text[i] == wanted)?;
+        if let Some(prev) = previous {
+            gaps += found - prev - 1;
+        }
+        score += match previous {
+            Some(prev) if found == prev + 1 => 10,
+            _ if found == 0 || !text[found - 1].is_alphanumeric() => 8,
+            _ => 2,
+        };
+        previous = Some(found);
+        next = found + 1;
+    }
+
+    if gaps > term.len() * 2 {
+        return None;
+    }
+    Some(score.saturating_sub(gaps as u32).min(400))
+}
+
+fn is_word_start(text: &str, byte_pos: usize) -> bool {
+    text[..byte_pos]
+        .chars()
+        .next_back()
+        .is_none_or(|c| !c.is_alphanumeric())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fields<'a>(title: &'a str, channel: &'a str, tags: Vec<&'a str>) -> SearchFields<'a> {
+        SearchFields {
+            id: "dQw4w9WgXcQ",
+            title: Some(title),
+            channel: Some(channel),
+            tags,
+        }
+    }
+
+    #[test]
+    fn substring_beats_scattered_letters() {
+        let substring = term_score("rust", "Learning Rust in 2026").unwrap();
+        let scattered = term_score("rust", "run fast").unwrap();
+        assert!(substring > scattered);
+    }
+
+    #[test]
+    fn word_start_beats_mid_word() {
+        let start = term_score("cat", "Cat videos").unwrap();
+        let middle = term_score("cat", "Education").unwrap();
+        assert!(start > middle);
+    }
+
+    #[test]
+    fn scattered_match_needs_close_letters() {
+        assert!(term_score("gnvr", "Never Gonna Give You Up").is_none());
+        assert!(term_score("nvr", "never").is_some());
+        assert!(term_score("xyz", "never").is_none());
+    }
+
+    #[test]
+    fn every_term_must_match() {
+        let f = fields("Never Gonna Give You Up", "Rick Astley", vec!["80s"]);
+        assert!(score("rick never", &f).is_some());
+        assert!(score("rick 80s", &f).is_some());
+        assert!(score("rick zebra", &f).is_none());
+        assert!(score("   ", &f).is_none());
+    }
+
+    #[test]
+    fn title_outweighs_channel() {
+        let in_title = fields("Rust tutorial", "Someone", vec![]);
+        let in_channel = fields("Tutorial", "Rust Channel", vec![]);
+        assert!(score("rust", &in_title) > score("rust", &in_channel));
+    }
+
+    #[test]
+    fn exact_id_ranks_highest() {
+        let f = SearchFields {
+            id: "dQw4w9WgXcQ",
+            title: None,
+            channel: None,
+            tags: vec![],
+        };
+        assert_eq!(score("dQw4w9WgXcQ", &f), Some(3000));
+        assert!(score("dqw4", &f).is_some());
+    }
+}
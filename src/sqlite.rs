@@ -0,0 +1,625 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::time::Duration;
+
+use crate::journal::Journal;
+use crate::models::{DEFAULT_QUEUE, Event, Subscription, Video, VideoMeta};
+use crate::stats::DateRange;
+use crate::store::{self, Notice, Storage};
+
+use anyhow::{Context, Result, anyhow, bail};
+use chrono::{DateTime, SecondsFormat, Utc};
+use rusqlite::{Connection, ErrorCode, OptionalExtension, params};
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+
+/// `MIGRATIONS[n]` upgrades the database from `PRAGMA user_version` n to
+/// n + 1. Rows hold the same JSON as the files of the JSON backend, so
+/// conversions between the two are lossless.
+const MIGRATIONS: &[&str] = &["
+    CREATE TABLE queues (name TEXT PRIMARY KEY) WITHOUT ROWID;
+    CREATE TABLE videos (
+        queue TEXT NOT NULL,
+        position INTEGER NOT NULL,
+        data TEXT NOT NULL,
+        PRIMARY KEY (queue, position)
+    );
+    CREATE TABLE events (
+        id INTEGER PRIMARY KEY,
+        timestamp TEXT NOT NULL,
+        data TEXT NOT NULL
+    );
+    CREATE INDEX events_by_time ON events (timestamp, id);
+    CREATE TABLE metadata (id TEXT PRIMARY KEY, data TEXT NOT NULL);
+    CREATE TABLE categories (id TEXT PRIMARY KEY, name TEXT NOT NULL);
+    CREATE TABLE subscriptions (position INTEGER PRIMARY KEY, data TEXT NOT NULL);
+    CREATE TABLE journal (id INTEGER PRIMARY KEY CHECK (id = 1), data TEXT NOT NULL);
+"];
+
+/// Tables holding JSON rows, with the column identifying each row.
+const JSON_TABLES: [(&str, &str); 5] = [
+    ("videos", "queue || ' #' || (position + 1)"),
+    ("events", "id"),
+    ("metadata", "id"),
+    ("subscriptions", "position + 1"),
+    ("journal", "id"),
+];
+
+/// A single SQLite database (`ytq.sqlite3`). Saves rewrite only the rows
+/// whose content or position changed, and history reads come back sorted
+/// from an index. Clones share one connection.
+#[derive(Clone)]
+pub struct SqliteStorage {
+    conn: Rc<Connection>,
+    path: PathBuf,
+}
+
+impl SqliteStorage {
+    /// Opens the database, creating it or upgrading its tables as needed.
+    pub fn open(path: &Path) -> Result<Self> {
+        let conn =
+            Connection::open(path).with_context(|| format!("failed to open {}", path.display()))?;
+        // Another ytq process may be writing; wait for it instead of failing
+        conn.busy_timeout(Duration::from_secs(10))?;
+
+        let version: usize = conn.pragma_query_value(None, "user_version", |row| row.get(0))?;
+        if version > MIGRATIONS.len() {
+            bail!(
+                "{} was written by a newer version of ytq (schema version {version}, this one \
+                 reads up to {}); upgrade ytq to use it",
+                path.display(),
+                MIGRATIONS.len()
+            );
+        }
+        if version < MIGRATIONS.len() {
+            let tx = conn.unchecked_transaction()?;
+            for migration in &MIGRATIONS[version..] {
+                tx.execute_batch(migration)?;
+            }
+            tx.pragma_update(None, "user_version", MIGRATIONS.len())?;
+            tx.commit()?;
+        }
+
+        Ok(Self {
+            conn: Rc::new(conn),
+            path: path.to_path_buf(),
+        })
+    }
+
+    /// Parses the JSON in one row, naming the row if it is damaged.
+    fn parse<T: DeserializeOwned>(&self, table: &str, key: &str, data: &str) -> Result<T> {
+        serde_json::from_str(data).map_err(|e| {
+            anyhow!(
+                "{table} row {key} in {} is unreadable: {e}; run `ytq doctor`",
+                self.path.display()
+            )
+        })
+    }
+
+    fn load_rows<T: DeserializeOwned>(&self, table: &str, sql: &str) -> Result<Vec<T>> {
+        let mut stmt = self.conn.prepare(sql)?;
+        let rows = stmt.query_map([], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+        })?;
+        let mut items = Vec::new();
+        for row in rows {
+            let (key, data) = row?;
+            items.push(self.parse(table, &key, &data)?);
+        }
+        Ok(items)
+    }
+
+    /// Events with `start <= timestamp < end`, oldest first.
+    fn events_between(&self, start: Option<String>, end: Option<String>) -> Result<Vec<Event>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, data FROM events
+             WHERE (?1 IS NULL OR timestamp >= ?1) AND (?2 IS NULL OR timestamp < ?2)
+             ORDER BY timestamp, id",
+        )?;
+        let rows = stmt.query_map(params![start, end], |row| {
+            Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?))
+        })?;
+
+        // Like the JSON backend, skip unreadable events rather than failing stats
+        let mut events = Vec::new();
+        let mut unreadable = 0;
+        for row in rows {
+            let (_, data) = row?;
+            match serde_json::from_str(&data) {
+                Ok(event) => events.push(event),
+                Err(_) => unreadable += 1,
+            }
+        }
+        if unreadable > 0 {
+            store::notice(Notice::UnreadableHistory {
+                path: self.path.clone(),
+                count: unreadable,
+            });
+        }
+        Ok(events)
+    }
+}
+
+fn to_json(value: &impl Serialize) -> Result<String> {
+    Ok(serde_json::to_string(value)?)
+}
+
+/// Fixed-width UTC timestamps sort correctly as text.
+fn timestamp_text(timestamp: DateTime<Utc>) -> String {
+    timestamp.to_rfc3339_opts(SecondsFormat::Nanos, true)
+}
+
+impl Storage for SqliteStorage {
+    fn named_queues(&self) -> Result<Vec<String>> {
+        let mut stmt = self.conn.prepare("SELECT name FROM queues")?;
+        let names = stmt.query_map([], |row| row.get(0))?;
+        Ok(names.collect::<rusqlite::Result<_>>()?)
+    }
+
+    fn has_queue(&self, queue: &str) -> Result<bool> {
+        Ok(self
+            .conn
+            .query_row("SELECT 1 FROM queues WHERE name = ?1", [queue], |_| Ok(()))
+            .optional()?
+            .is_some())
+    }
+
+    fn load_queue(&self, queue: &str) -> Result<Vec<Video>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT position, data FROM videos WHERE queue = ?1 ORDER BY position")?;
+        let rows = stmt.query_map([queue], |row| {
+            Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?))
+        })?;
+        let mut videos = Vec::new();
+        for row in rows {
+            let (position, data) = row?;
+            videos.push(self.parse("videos", &format!("{queue} #{}", position + 1), &data)?);
+        }
+        Ok(videos)
+    }
+
+    fn save_queue(&self, queue: &str, videos: &[Video]) -> Result<()> {
+        let tx = self.conn.unchecked_transaction()?;
+        tx.execute("INSERT OR IGNORE INTO queues (name) VALUES (?1)", [queue])?;
+        {
+            // Keyed by position, since rows deleted by `ytq doctor` leave gaps
+            let mut select = tx.prepare("SELECT position, data FROM videos WHERE queue = ?1")?;
+            let stored = select
+                .query_map([queue], |row| {
+                    Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?))
+                })?
+                .collect::<rusqlite::Result<HashMap<_, _>>>()?;
+
+            let mut upsert = tx.prepare(
+                "INSERT OR REPLACE INTO videos (queue, position, data) VALUES (?1, ?2, ?3)",
+            )?;
+            for (position, video) in videos.iter().enumerate() {
+                let data = to_json(video)?;
+                if stored.get(&(position as i64)) != Some(&data) {
+                    upsert.execute(params![queue, position as i64, data])?;
+                }
+            }
+        }
+        tx.execute(
+            "DELETE FROM videos WHERE queue = ?1 AND position >= ?2",
+            params![queue, videos.len() as i64],
+        )?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    fn rename_queue(&self, from: &str, to: &str) -> Result<()> {
+        let tx = self.conn.unchecked_transaction()?;
+        tx.execute("UPDATE queues SET name = ?2 WHERE name = ?1", [from, to])?;
+        tx.execute("UPDATE videos SET queue = ?2 WHERE queue = ?1", [from, to])?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    fn delete_queue(&self, queue: &str) -> Result<()> {
+        let tx = self.conn.unchecked_transaction()?;
+        tx.execute("DELETE FROM videos WHERE queue = ?1", [queue])?;
+        tx.execute("DELETE FROM queues WHERE name = ?1", [queue])?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    fn load_journal(&self) -> Result<Journal> {
+        let data: Option<String> = self
+            .conn
+            .query_row("SELECT data FROM journal WHERE id = 1", [], |row| {
+                row.get(0)
+            })
+            .optional()?;
+        match data {
+            Some(data) => self.parse("journal", "1", &data),
+            None => Ok(Journal::default()),
+        }
+    }
+
+    fn save_journal(&self, journal: &Journal) -> Result<()> {
+        self.conn.execute(
+            "INSERT OR REPLACE INTO journal (id, data) VALUES (1, ?1)",
+            [to_json(journal)?],
+        )?;
+        Ok(())
+    }
+
+    fn log_events(&self, events: &[Event]) -> Result<()> {
+        let tx = self.conn.unchecked_transaction()?;
+        {
+            let mut insert = tx.prepare("INSERT INTO events (timestamp, data) VALUES (?1, ?2)")?;
+            for event in events {
+                insert.execute(params![timestamp_text(event.timestamp), to_json(event)?])?;
+            }
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    fn history(&self) -> Result<Vec<Event>> {
+        self.events_between(None, None)
+    }
+
+    fn history_for(&self, range: &DateRange, queue: Option<&str>) -> Result<Vec<Event>> {
+        let Some(start) = range.start.map(timestamp_text) else {
+            return self.history();
+        };
+
+        // Undo and redo since the start can cancel events inside the range
+        let action = "json_extract(data, '$.action') IN ('Undone', 'Redone')";
+        let compensated: bool = match queue {
+            Some(queue) => self.conn.query_row(
+                &format!(
+                    "SELECT EXISTS (SELECT 1 FROM events WHERE timestamp >= ?1 AND {action} \
+                     AND coalesce(json_extract(data, '$.queue'), ?3) = ?2)"
+                ),
+                params![start, queue, DEFAULT_QUEUE],
+                |row| row.get(0),
+            )?,
+            None => self.conn.query_row(
+                &format!("SELECT EXISTS (SELECT 1 FROM events WHERE timestamp >= ?1 AND {action})"),
+                [&start],
+                |row| row.get(0),
+            )?,
+        };
+        if compensated {
+            return self.history();
+        }
+        self.events_between(Some(start), range.end.map(timestamp_text))
+    }
+
+    fn load_metadata(&self) -> Result<HashMap<String, VideoMeta>> {
+        let entries: Vec<VideoMeta> =
+            self.load_rows("metadata", "SELECT id, data FROM metadata")?;
+        Ok(entries.into_iter().map(|m| (m.id.clone(), m)).collect())
+    }
+
+    fn upsert_metadata(&self, entries: &[VideoMeta]) -> Result<()> {
+        let tx = self.conn.unchecked_transaction()?;
+        {
+            let mut upsert =
+                tx.prepare("INSERT OR REPLACE INTO metadata (id, data) VALUES (?1, ?2)")?;
+            for meta in entries {
+                upsert.execute(params![meta.id, to_json(meta)?])?;
+            }
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    fn load_categories(&self) -> Result<HashMap<String, String>> {
+        let mut stmt = self.conn.prepare("SELECT id, name FROM categories")?;
+        let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+        Ok(rows.collect::<rusqlite::Result<_>>()?)
+    }
+
+    fn save_categories(&self, categories: &HashMap<String, String>) -> Result<()> {
+        let tx = self.conn.unchecked_transaction()?;
+        tx.execute("DELETE FROM categories", [])?;
+        {
+            let mut insert = tx.prepare("INSERT INTO categories (id, name) VALUES (?1, ?2)")?;
+            for (id, name) in categories {
+                insert.execute([id, name])?;
+            }
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    fn load_subscriptions(&self) -> Result<Vec<Subscription>> {
+        self.load_rows(
+            "subscriptions",
+            "SELECT position + 1, data FROM subscriptions ORDER BY position",
+        )
+    }
+
+    fn save_subscriptions(&self, subscriptions: &[Subscription]) -> Result<()> {
+        let tx = self.conn.unchecked_transaction()?;
+        tx.execute("DELETE FROM subscriptions", [])?;
+        {
+            let mut insert =
+                tx.prepare("INSERT INTO subscriptions (position, data) VALUES (?1, ?2)")?;
+            for (position, subscription) in subscriptions.iter().enumerate() {
+                insert.execute(params![position as i64, to_json(subscription)?])?;
+            }
+        }
+        tx.commit()?;
+        Ok(())
+    }
+}
+
+/// A row whose JSON no longer parses, found by [`check`].
+#[derive(Debug, PartialEq)]
+pub struct BadRow {
+    pub table: &'static str,
+    pub rowid: i64,
+    /// Human-readable row name, e.g. `music #3` for a queue entry.
+    pub key: String,
+    pub error: String,
+}
+
+/// Runs SQLite's own integrity check and parses every JSON row. Returns the
+/// integrity problems and the unreadable rows.
+pub fn check(path: &Path) -> Result<(Vec<String>, Vec<BadRow>)> {
+    let storage = SqliteStorage::open(path)?;
+    let conn = &storage.conn;
+
+    let mut stmt = conn.prepare("PRAGMA integrity_check")?;
+    let integrity: Vec<String> = stmt
+        .query_map([], |row| row.get(0))?
+        .collect::<rusqlite::Result<_>>()?;
+    let problems = integrity.into_iter().filter(|p| p != "ok").collect();
+
+    let mut bad_rows = Vec::new();
+    for (table, key) in JSON_TABLES {
+        let sql = format!("SELECT rowid, {key}, data FROM {table}");
+        let mut stmt = conn.prepare(&sql)?;
+        let rows = stmt.query_map([], |row| {
+            Ok((
+                row.get::<_, i64>(0)?,
+                row.get::<_, rusqlite::types::Value>(1)?,
+                row.get::<_, String>(2)?,
+            ))
+        })?;
+        for row in rows {
+            let (rowid, key, data) = row?;
+            let key = match key {
+                rusqlite::types::Value::Text(text) => text,
+                rusqlite::types::Value::Integer(n) => n.to_string(),
+                other => format!("{other:?}"),
+            };
+            let parsed = match table {
+                "videos" => serde_json::from_str::<Video>(&data).map(drop),
+                "events" => serde_json::from_str::<Event>(&data).map(drop),
+                "metadata" => serde_json::from_str::<VideoMeta>(&data).map(drop),
+                "subscriptions" => serde_json::from_str::<Subscription>(&data).map(drop),
+                _ => serde_json::from_str::<Journal>(&data).map(drop),
+            };
+            if let Err(e) = parsed {
+                bad_rows.push(BadRow {
+                    table,
+                    rowid,
+                    key,
+                    error: e.to_string(),
+                });
+            }
+        }
+    }
+    Ok((problems, bad_rows))
+}
+
+/// Whether `error` means the database file itself is damaged.
+pub fn is_corrupt(error: &anyhow::Error) -> bool {
+    error.chain().any(|cause| {
+        matches!(
+            cause.downcast_ref::<rusqlite::Error>(),
+            Some(rusqlite::Error::SqliteFailure(e, _))
+                if matches!(e.code, ErrorCode::DatabaseCorrupt | ErrorCode::NotADatabase)
+        )
+    })
+}
+
+/// Deletes the given rows in one transaction. Queues skip over the gaps
+/// and close them on their next save.
+pub fn delete_rows(path: &Path, rows: &[BadRow]) -> Result<()> {
+    let storage = SqliteStorage::open(path)?;
+    let tx = storage.conn.unchecked_transaction()?;
+    for row in rows {
+        let sql = format!("DELETE FROM {} WHERE rowid = ?1", row.table);
+        tx.execute(&sql, [row.rowid])?;
+    }
+    tx.commit()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Action;
+    use crate::test_util::TestDir;
+
+    fn video(id: &str) -> Video {
+        Video::new(id.to_string(), String::new(), chrono::Utc::now())
+    }
+
+    fn event(timestamp: &str, id: &str) -> Event {
+        Event {
+            timestamp: timestamp.parse().unwrap(),
+            action: Action::Watched,
+            video_id: id.to_string(),
+            time_in_queue_sec: Some(60),
+            queue: None,
+        }
+    }
+
+    #[test]
+    fn queues_roundtrip_in_order() {
+        let dir = TestDir::new("queues");
+        let path = dir.join("ytq.sqlite3");
+        let storage = SqliteStorage::open(&path).unwrap();
+        storage
+            .save_queue("music", &[video("b"), video("a")])
+            .unwrap();
+        storage
+            .save_queue("music", &[video("c"), video("b")])
+            .unwrap();
+
+        let ids: Vec<String> = storage
+            .load_queue("music")
+            .unwrap()
+            .into_iter()
+            .map(|v| v.id)
+            .collect();
+        assert_eq!(ids, vec!["c", "b"]);
+        assert!(storage.has_queue("music").unwrap());
+
+        storage.rename_queue("music", "songs").unwrap();
+        assert_eq!(storage.named_queues().unwrap(), vec!["songs"]);
+        assert_eq!(storage.load_queue("songs").unwrap().len(), 2);
+
+        storage.delete_queue("songs").unwrap();
+        assert!(!storage.has_queue("songs").unwrap());
+        assert!(storage.load_queue("songs").unwrap().is_empty());
+    }
+
+    #[test]
+    fn saving_a_queue_rewrites_only_changed_rows() {
+        let dir = TestDir::new("changes");
+        let path = dir.join("ytq.sqlite3");
+        let storage = SqliteStorage::open(&path).unwrap();
+        let videos = [video("a"), video("b"), video("c")];
+        storage.save_queue(DEFAULT_QUEUE, &videos[..2]).unwrap();
+
+        // Appending writes one row; the queues row is already there
+        let before = storage.conn.total_changes();
+        storage.save_queue(DEFAULT_QUEUE, &videos).unwrap();
+        assert_eq!(storage.conn.total_changes() - before, 1);
+
+        // Dropping the last video deletes one row
+        let before = storage.conn.total_changes();
+        storage.save_queue(DEFAULT_QUEUE, &videos[..2]).unwrap();
+        assert_eq!(storage.conn.total_changes() - before, 1);
+
+        // Popping the first video shifts the rest
+        storage.save_queue(DEFAULT_QUEUE, &videos[1..2]).unwrap();
+        let ids: Vec<String> = storage
+            .load_queue(DEFAULT_QUEUE)
+            .unwrap()
+            .into_iter()
+            .map(|v| v.id)
+            .collect();
+        assert_eq!(ids, vec!["b"]);
+    }
+
+    #[test]
+    fn history_comes_back_sorted_by_time() {
+        let dir = TestDir::new("history");
+        let path = dir.join("ytq.sqlite3");
+        let storage = SqliteStorage::open(&path).unwrap();
+        storage
+            .log_events(&[
+                event("2026-03-01T00:00:00Z", "late"),
+                event("2025-12-31T23:59:59.5Z", "early"),
+            ])
+            .unwrap();
+        storage
+            .log_events(&[event("2026-03-01T00:00:00Z", "same-time")])
+            .unwrap();
+
+        let ids: Vec<String> = storage
+            .history()
+            .unwrap()
+            .into_iter()
+            .map(|e| e.video_id)
+            .collect();
+        assert_eq!(ids, vec!["early", "late", "same-time"]);
+    }
+
+    #[test]
+    fn history_for_reads_the_range_unless_undo_followed() {
+        let dir = TestDir::new("range");
+        let path = dir.join("ytq.sqlite3");
+        let storage = SqliteStorage::open(&path).unwrap();
+        storage
+            .log_events(&[
+                event("2026-01-10T12:00:00Z", "january"),
+                event("2026-02-10T12:00:00Z", "february"),
+                event("2026-03-10T12:00:00Z", "march"),
+            ])
+            .unwrap();
+        let february = DateRange::specific_month(2026, 2).unwrap();
+        let ids = |events: Vec<Event>| -> Vec<String> {
+            events.into_iter().map(|e| e.video_id).collect()
+        };
+        assert_eq!(
+            ids(storage.history_for(&february, None).unwrap()),
+            vec!["february"]
+        );
+
+        let mut undone = event("2026-03-11T12:00:00Z", "march");
+        undone.action = Action::Undone;
+        undone.queue = Some("music".to_string());
+        storage.log_events(&[undone]).unwrap();
+        assert_eq!(storage.history_for(&february, None).unwrap().len(), 4);
+        assert_eq!(
+            ids(storage.history_for(&february, Some(DEFAULT_QUEUE)).unwrap()),
+            vec!["february"]
+        );
+    }
+
+    #[test]
+    fn reopening_keeps_data_and_schema_version() {
+        let dir = TestDir::new("reopen");
+        let path = dir.join("ytq.sqlite3");
+        SqliteStorage::open(&path)
+            .unwrap()
+            .save_categories(&HashMap::from([("10".to_string(), "Music".to_string())]))
+            .unwrap();
+
+        let storage = SqliteStorage::open(&path).unwrap();
+        assert_eq!(storage.load_categories().unwrap()["10"], "Music");
+
+        storage
+            .conn
+            .pragma_update(None, "user_version", 99)
+            .unwrap();
+        drop(storage);
+        let err = SqliteStorage::open(&path).err().unwrap();
+        assert!(err.to_string().contains("newer version of ytq"));
+    }
+
+    #[test]
+    fn check_finds_and_deletes_unreadable_rows() {
+        let dir = TestDir::new("check");
+        let path = dir.join("ytq.sqlite3");
+        let storage = SqliteStorage::open(&path).unwrap();
+        storage
+            .save_queue("default", &[video("a"), video("b"), video("c")])
+            .unwrap();
+        storage
+            .conn
+            .execute(
+                "UPDATE videos SET data = '{\"id\": 1}' WHERE position = 1",
+                [],
+            )
+            .unwrap();
+        assert!(storage.load_queue("default").is_err());
+
+        let (problems, bad_rows) = check(&path).unwrap();
+        assert!(problems.is_empty());
+        assert_eq!(bad_rows.len(), 1);
+        assert_eq!(bad_rows[0].key, "default #2");
+
+        delete_rows(&path, &bad_rows).unwrap();
+        let ids: Vec<String> = storage
+            .load_queue("default")
+            .unwrap()
+            .into_iter()
+            .map(|v| v.id)
+            .collect();
+        assert_eq!(ids, vec!["a", "c"]);
+    }
+}
@@ -112,6 +112,49 @@ impl DateRange {
     }
 }
 
+/// Resolves `Undone`/`Redone` events against the history they compensate.
+///
+/// Each `Undone` cancels the most recent still-active Queued, Watched or
+/// Skipped event for the same video in the same queue; each `Redone`
+/// reinstates the most recently cancelled one. `Reordered` events never
+/// count, so undo leaves them alone. The returned history contains only the events that still
+/// count, without the compensating events themselves.
+pub fn apply_undo(events: Vec<Event>) -> Vec<Event> {
+    let mut active: HashMap<(String, String), Vec<usize>> = HashMap::new();
+    let mut cancelled_stack: HashMap<(String, String), Vec<usize>> = HashMap::new();
+    let mut keep = vec![true; events.len()];
+
+    for (i, e) in events.iter().enumerate() {
+        let key = (e.queue_name().to_string(), e.video_id.clone());
+        match e.action {
+            Action::Undone => {
+                keep[i] = false;
+                if let Some(idx) = active.get_mut(&key).and_then(|v| v.pop()) {
+                    keep[idx] = false;
+                    cancelled_stack.entry(key).or_default().push(idx);
+                }
+            }
+            Action::Redone => {
+                keep[i] = false;
+                if let Some(idx) = cancelled_stack.get_mut(&key).and_then(|v| v.pop()) {
+                    keep[idx] = true;
+                    active.entry(key).or_default().push(idx);
+                }
+            }
+            Action::Queued | Action::Watched | Action::Skipped => {
+                active.entry(key).or_default().push(i);
+            }
+            Action::Reordered => {}
+        }
+    }
+
+    events
+        .into_iter()
+        .zip(keep)
+        .filter_map(|(e, keep)| keep.then_some(e))
+        .collect()
+}
+
 /// Filters events to those within the given date range.
 pub fn filter_events<'a>(events: &'a [Event], range: &DateRange) -> Vec<&'a Event> {
     events
@@ -1681,6 +1724,7 @@ mod tests {
             action,
             video_id: video_id.to_string(),
             time_in_queue_sec: queue_secs,
+            queue: None,
         }
     }
 
@@ -1830,7 +1874,7 @@ mod tests {
 
     #[test]
     fn basic_stats_counts() {
-        let events = vec![
+        let events = [
             make_event(Action::Queued, "a", Utc::now(), None),
             make_event(Action::Queued, "b", Utc::now(), None),
             make_event(Action::Watched, "a", Utc::now(), Some(3600)),
@@ -1848,8 +1892,80 @@ mod tests {
     }
 
     #[test]
-    fn basic_stats_avg_queue_time() {
+    fn basic_stats_ignores_reorders() {
+        let events = [
+            make_event(Action::Queued, "a", Utc::now(), None),
+            make_event(Action::Reordered, "a", Utc::now(), None),
+            make_event(Action::Reordered, "a", Utc::now(), None),
+            make_event(Action::Watched, "a", Utc::now(), Some(100)),
+        ];
+        let refs: Vec<&Event> = events.iter().collect();
+        let stats = compute_basic(&refs, &[], &HashMap::new());
+        assert_eq!(stats.added, 1);
+        assert_eq!(stats.watched, 1);
+        assert_eq!(stats.skipped, 0);
+        assert!((stats.completion_rate - 1.0).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn apply_undo_cancels_latest_event() {
+        let events = vec![
+            make_event(Action::Queued, "a", Utc::now(), None),
+            make_event(Action::Watched, "a", Utc::now(), Some(10)),
+            make_event(Action::Undone, "a", Utc::now(), None),
+        ];
+        let resolved = apply_undo(events);
+        assert_eq!(resolved.len(), 1);
+        assert!(matches!(resolved[0].action, Action::Queued));
+    }
+
+    #[test]
+    fn apply_undo_redo_reinstates_event() {
+        let events = vec![
+            make_event(Action::Queued, "a", Utc::now(), None),
+            make_event(Action::Skipped, "a", Utc::now(), None),
+            make_event(Action::Undone, "a", Utc::now(), None),
+            make_event(Action::Redone, "a", Utc::now(), None),
+        ];
+        let resolved = apply_undo(events);
+        let actions: Vec<String> = resolved.iter().map(|e| format!("{:?}", e.action)).collect();
+        assert_eq!(actions, vec!["Queued", "Skipped"]);
+    }
+
+    #[test]
+    fn apply_undo_skips_over_reorders() {
+        // Added, moved, then both undone: the move's undo logs a Reordered
+        // and the next Undone still reaches the Queued
         let events = vec![
+            make_event(Action::Queued, "a", Utc::now(), None),
+            make_event(Action::Reordered, "a", Utc::now(), None),
+            make_event(Action::Reordered, "a", Utc::now(), None),
+            make_event(Action::Undone, "a", Utc::now(), None),
+        ];
+        let resolved = apply_undo(events);
+        let actions: Vec<String> = resolved.iter().map(|e| format!("{:?}", e.action)).collect();
+        assert_eq!(actions, vec!["Reordered", "Reordered"]);
+    }
+
+    #[test]
+    fn apply_undo_is_scoped_to_queue_and_video() {
+        let mut other_queue = make_event(Action::Watched, "a", Utc::now(), Some(5));
+        other_queue.queue = Some("music".to_string());
+        let events = vec![
+            make_event(Action::Watched, "a", Utc::now(), Some(5)),
+            make_event(Action::Watched, "b", Utc::now(), Some(5)),
+            other_queue,
+            make_event(Action::Undone, "a", Utc::now(), None),
+        ];
+        let resolved = apply_undo(events);
+        assert_eq!(resolved.len(), 2);
+        assert_eq!(resolved[0].video_id, "b");
+        assert_eq!(resolved[1].queue.as_deref(), Some("music"));
+    }
+
+    #[test]
+    fn basic_stats_avg_queue_time() {
+        let events = [
             make_event(Action::Watched, "a", Utc::now(), Some(100)),
             make_event(Action::Watched, "b", Utc::now(), Some(200)),
         ];
@@ -1888,7 +2004,7 @@ mod tests {
             make_meta("c", "Channel B", "28", 100, vec![]),
         );
 
-        let events = vec![
+        let events = [
             make_event(Action::Watched, "a", Utc::now(), Some(100)),
             make_event(Action::Watched, "b", Utc::now(), Some(200)),
             make_event(Action::Watched, "c", Utc::now(), Some(50)),
@@ -1938,7 +2054,7 @@ mod tests {
         );
 
         // Same video watched twice — should only count once for metadata stats
-        let events = vec![
+        let events = [
             make_event(Action::Watched, "a", Utc::now(), Some(100)),
             make_event(Action::Watched, "a", Utc::now(), Some(200)),
         ];
@@ -1956,7 +2072,7 @@ mod tests {
 
     #[test]
     fn unique_ids_deduplicates() {
-        let events = vec![
+        let events = [
             make_event(Action::Watched, "a", Utc::now(), Some(0)),
             make_event(Action::Watched, "b", Utc::now(), Some(0)),
             make_event(Action::Watched, "a", Utc::now(), Some(0)),
@@ -1970,7 +2086,7 @@ mod tests {
 
     #[test]
     fn streak_consecutive_days() {
-        let events = vec![
+        let events = [
             make_event(
                 Action::Watched,
                 "a",
@@ -2003,14 +2119,14 @@ mod tests {
 
     #[test]
     fn streak_no_watches() {
-        let events = vec![make_event(Action::Queued, "a", Utc::now(), None)];
+        let events = [make_event(Action::Queued, "a", Utc::now(), None)];
         let refs: Vec<&Event> = events.iter().collect();
         assert_eq!(longest_streak(&refs), 0);
     }
 
     #[test]
     fn streak_single_day() {
-        let events = vec![make_event(Action::Watched, "a", Utc::now(), Some(0))];
+        let events = [make_event(Action::Watched, "a", Utc::now(), Some(0))];
         let refs: Vec<&Event> = events.iter().collect();
         assert_eq!(longest_streak(&refs), 1);
     }
@@ -2018,7 +2134,7 @@ mod tests {
     #[test]
     fn streak_multiple_watches_same_day() {
         let day = Utc.with_ymd_and_hms(2025, 1, 1, 10, 0, 0).unwrap();
-        let events = vec![
+        let events = [
             make_event(Action::Watched, "a", day, Some(0)),
             make_event(Action::Watched, "b", day, Some(0)),
             make_event(Action::Watched, "c", day, Some(0)),
@@ -2031,7 +2147,7 @@ mod tests {
 
     #[test]
     fn time_of_day_buckets() {
-        let events = vec![
+        let events = [
             make_event(
                 Action::Watched,
                 "a",
@@ -2192,7 +2308,7 @@ mod tests {
 
     #[test]
     fn monthly_buckets_groups_correctly() {
-        let events = vec![
+        let events = [
             make_event(
                 Action::Queued,
                 "a",
@@ -2295,7 +2411,7 @@ mod tests {
         let day1c = Utc.with_ymd_and_hms(2025, 3, 14, 18, 0, 0).unwrap();
         let day2 = Utc.with_ymd_and_hms(2025, 3, 15, 10, 0, 0).unwrap();
 
-        let events = vec![
+        let events = [
             make_event(Action::Watched, "a", day1, Some(0)),
             make_event(Action::Watched, "b", day1b, Some(0)),
             make_event(Action::Watched, "c", day1c, Some(0)),
@@ -2320,7 +2436,7 @@ mod tests {
     fn discovery_day_none_when_single_channel_per_day() {
         let day1 = Utc.with_ymd_and_hms(2025, 3, 14, 10, 0, 0).unwrap();
 
-        let events = vec![make_event(Action::Watched, "a", day1, Some(0))];
+        let events = [make_event(Action::Watched, "a", day1, Some(0))];
 
         let mut metadata = HashMap::new();
         metadata.insert("a".to_string(), make_meta("a", "Chan A", "10", 100, vec![]));
@@ -2334,7 +2450,7 @@ mod tests {
 
     #[test]
     fn comfort_video_finds_most_rewatched() {
-        let events = vec![
+        let events = [
             make_event(Action::Watched, "a", Utc::now(), Some(0)),
             make_event(Action::Watched, "b", Utc::now(), Some(0)),
             make_event(Action::Watched, "a", Utc::now(), Some(0)),
@@ -2354,7 +2470,7 @@ mod tests {
 
     #[test]
     fn comfort_video_none_when_no_rewatches() {
-        let events = vec![
+        let events = [
             make_event(Action::Watched, "a", Utc::now(), Some(0)),
             make_event(Action::Watched, "b", Utc::now(), Some(0)),
         ];
@@ -2368,7 +2484,7 @@ mod tests {
 
     #[test]
     fn queue_patience_impulsive() {
-        let events = vec![
+        let events = [
             make_event(Action::Watched, "a", Utc::now(), Some(300)), // 5 min
             make_event(Action::Watched, "b", Utc::now(), Some(600)), // 10 min
         ];
@@ -2381,7 +2497,7 @@ mod tests {
 
     #[test]
     fn queue_patience_thoughtful() {
-        let events = vec![
+        let events = [
             make_event(Action::Watched, "a", Utc::now(), Some(7200)), // 2h
             make_event(Action::Watched, "b", Utc::now(), Some(14400)), // 4h
         ];
@@ -2393,7 +2509,7 @@ mod tests {
 
     #[test]
     fn queue_patience_fermenter() {
-        let events = vec![
+        let events = [
             make_event(Action::Watched, "a", Utc::now(), Some(172800)), // 2 days
             make_event(Action::Watched, "b", Utc::now(), Some(259200)), // 3 days
         ];
@@ -2405,7 +2521,7 @@ mod tests {
 
     #[test]
     fn queue_patience_aged() {
-        let events = vec![
+        let events = [
             make_event(Action::Watched, "a", Utc::now(), Some(700000)), // > 1 week
             make_event(Action::Watched, "b", Utc::now(), Some(800000)),
         ];
@@ -2417,7 +2533,7 @@ mod tests {
 
     #[test]
     fn queue_patience_none_when_no_watches() {
-        let events = vec![make_event(Action::Queued, "a", Utc::now(), None)];
+        let events = [make_event(Action::Queued, "a", Utc::now(), None)];
         let refs: Vec<&Event> = events.iter().collect();
         let result = compute_queue_patience(&refs);
         assert!(result.is_none());
@@ -2427,7 +2543,7 @@ mod tests {
 
     #[test]
     fn total_throughput_counts_unique_ids() {
-        let events = vec![
+        let events = [
             make_event(Action::Queued, "a", Utc::now(), None),
             make_event(Action::Queued, "b", Utc::now(), None),
             make_event(Action::Watched, "a", Utc::now(), Some(0)),
@@ -2506,7 +2622,7 @@ mod tests {
         let sun = Utc.with_ymd_and_hms(2025, 1, 5, 10, 0, 0).unwrap();
         let mon = Utc.with_ymd_and_hms(2025, 1, 6, 10, 0, 0).unwrap();
 
-        let events = vec![
+        let events = [
             make_event(Action::Watched, "a", sat, Some(0)),
             make_event(Action::Watched, "b", sun, Some(0)),
             make_event(Action::Watched, "c", sat, Some(0)),
@@ -2527,7 +2643,7 @@ mod tests {
         let tue = Utc.with_ymd_and_hms(2025, 1, 7, 10, 0, 0).unwrap();
         let wed = Utc.with_ymd_and_hms(2025, 1, 8, 10, 0, 0).unwrap();
 
-        let events = vec![
+        let events = [
             make_event(Action::Watched, "a", mon, Some(0)),
             make_event(Action::Watched, "b", tue, Some(0)),
             make_event(Action::Watched, "c", wed, Some(0)),
@@ -2541,7 +2657,7 @@ mod tests {
 
     #[test]
     fn weekend_weekday_none_when_no_watches() {
-        let events = vec![make_event(Action::Queued, "a", Utc::now(), None)];
+        let events = [make_event(Action::Queued, "a", Utc::now(), None)];
         let refs: Vec<&Event> = events.iter().collect();
         let result = compute_weekend_weekday(&refs);
         assert!(result.is_none());
@@ -2562,7 +2678,7 @@ mod tests {
         categories.insert("20".to_string(), "Gaming".to_string());
 
         // Watches span a full year, first half Music, second half Gaming
-        let events = vec![
+        let events = [
             make_event(
                 Action::Watched,
                 "a",
@@ -2603,7 +2719,7 @@ mod tests {
 
     #[test]
     fn category_evolution_empty_when_span_too_short() {
-        let events = vec![
+        let events = [
             make_event(
                 Action::Watched,
                 "a",
@@ -2636,7 +2752,7 @@ mod tests {
         let mut categories = HashMap::new();
         categories.insert("10".to_string(), "Music".to_string());
 
-        let events = vec![
+        let events = [
             make_event(
                 Action::Watched,
                 "a",
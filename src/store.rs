@@ -1,178 +1,1639 @@
-use std::collections::HashMap;
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap};
 use std::fs::{self, OpenOptions};
-use std::io::{BufRead, BufReader, Write};
-use std::path::Path;
+use std::io::{BufRead, BufReader, ErrorKind, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
 
-use crate::models::{Config, Event, Video, VideoMeta};
+use crate::history_index::{HistoryIndex, MonthSummary};
+use crate::journal::{Journal, JournalEntry, QueueSplice};
+use crate::models::{
+    Config, DEFAULT_QUEUE, Event, StorageKind, Subscription, Video, VideoMeta, validate_queue_name,
+};
 use crate::paths::AppPaths;
+use crate::sqlite::SqliteStorage;
+use crate::stats::DateRange;
 
-use anyhow::Result;
-use chrono::Datelike;
+use anyhow::{Context, Result, anyhow, bail};
+use chrono::{Datelike, Utc};
 use fd_lock::RwLock;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// Where queues, history, metadata, categories, the undo journal and
+/// subscriptions are kept. The config always stays in `config.json`, since it
+/// chooses the backend.
+///
+/// The functions in this module hold the queue lock around every
+/// read-modify-write, so implementations don't need locking of their own.
+pub trait Storage {
+    /// Names of the named queues (not the default queue), in any order.
+    fn named_queues(&self) -> Result<Vec<String>>;
+    /// Whether a named queue exists. The default queue always does.
+    fn has_queue(&self, queue: &str) -> Result<bool>;
+    /// Loads a queue; a queue that was never saved is empty.
+    fn load_queue(&self, queue: &str) -> Result<Vec<Video>>;
+    /// Saves a queue, creating it if needed.
+    fn save_queue(&self, queue: &str, videos: &[Video]) -> Result<()>;
+    fn rename_queue(&self, from: &str, to: &str) -> Result<()>;
+    fn delete_queue(&self, queue: &str) -> Result<()>;
+
+    fn load_journal(&self) -> Result<Journal>;
+    fn save_journal(&self, journal: &Journal) -> Result<()>;
+
+    /// Appends events to the history, in the given order.
+    fn log_events(&self, events: &[Event]) -> Result<()>;
+    /// Every readable event, oldest first.
+    fn history(&self) -> Result<Vec<Event>>;
+    /// The events in `range`, for one queue or all of them, plus whatever
+    /// else resolving undo and redo takes, oldest first. May return more,
+    /// up to the whole history; callers still filter.
+    fn history_for(&self, range: &DateRange, queue: Option<&str>) -> Result<Vec<Event>> {
+        let _ = (range, queue);
+        self.history()
+    }
+
+    fn load_metadata(&self) -> Result<HashMap<String, VideoMeta>>;
+    /// Adds or replaces metadata entries, leaving the others alone.
+    fn upsert_metadata(&self, entries: &[VideoMeta]) -> Result<()>;
+
+    fn load_categories(&self) -> Result<HashMap<String, String>>;
+    fn save_categories(&self, categories: &HashMap<String, String>) -> Result<()>;
+
+    fn load_subscriptions(&self) -> Result<Vec<Subscription>>;
+    fn save_subscriptions(&self, subscriptions: &[Subscription]) -> Result<()>;
+}
+
+/// The backend `open` last picked, with its database connection.
+struct Opened {
+    config_file: PathBuf,
+    database_file: PathBuf,
+    kind: StorageKind,
+    sqlite: Option<SqliteStorage>,
+}
+
+thread_local! {
+    /// Lets the helpers a command calls one after another share a config
+    /// read and a database connection. Cleared by whatever changes the
+    /// backend or moves its files.
+    static OPENED: RefCell<Option<Opened>> = const { RefCell::new(None) };
+}
+
+/// Opens the backend selected in the config.
+pub fn open(paths: &AppPaths) -> Result<Box<dyn Storage + '_>> {
+    let cached = OPENED.with_borrow(|opened| {
+        opened
+            .as_ref()
+            .filter(|opened| {
+                opened.config_file == paths.config_file
+                    && opened.database_file == paths.database_file
+            })
+            .map(|opened| (opened.kind, opened.sqlite.clone()))
+    });
+    if let Some((kind, sqlite)) = cached {
+        return Ok(match sqlite {
+            Some(storage) => Box::new(storage),
+            None => open_kind(paths, kind)?,
+        });
+    }
+
+    let kind = load_config(&paths.config_file)?.storage;
+    let sqlite = match kind {
+        StorageKind::Json => None,
+        StorageKind::Sqlite => Some(SqliteStorage::open(&paths.database_file)?),
+    };
+    OPENED.set(Some(Opened {
+        config_file: paths.config_file.clone(),
+        database_file: paths.database_file.clone(),
+        kind,
+        sqlite: sqlite.clone(),
+    }));
+    Ok(match sqlite {
+        Some(storage) => Box::new(storage),
+        None => open_kind(paths, kind)?,
+    })
+}
+
+/// Makes the next `open` read the config and open the database again.
+pub fn forget_opened() {
+    OPENED.set(None);
+}
+
+/// Something the store ran into that didn't stop it but that the user
+/// should hear about. The command layer prints these; see [`take_notices`].
+#[derive(Debug, PartialEq)]
+pub enum Notice {
+    /// A data file was upgraded in place; the original is kept as `backup`.
+    Upgraded {
+        path: PathBuf,
+        version: u32,
+        backup: PathBuf,
+    },
+    /// Metadata entries in an outdated format were dropped while upgrading.
+    DroppedMetadata(usize),
+    /// History entries that no longer parse were skipped.
+    UnreadableHistory { path: PathBuf, count: usize },
+}
+
+thread_local! {
+    static NOTICES: RefCell<Vec<Notice>> = const { RefCell::new(Vec::new()) };
+}
+
+pub fn notice(notice: Notice) {
+    NOTICES.with_borrow_mut(|notices| {
+        // A command may read the same file more than once
+        if !notices.contains(&notice) {
+            notices.push(notice);
+        }
+    });
+}
+
+/// The notices collected since the last call, oldest first.
+pub fn take_notices() -> Vec<Notice> {
+    NOTICES.take()
+}
+
+/// Opens a specific backend, e.g. as the target of `ytq migrate-storage`.
+pub fn open_kind(paths: &AppPaths, kind: StorageKind) -> Result<Box<dyn Storage + '_>> {
+    Ok(match kind {
+        StorageKind::Json => Box::new(JsonStorage { paths }),
+        StorageKind::Sqlite => Box::new(SqliteStorage::open(&paths.database_file)?),
+    })
+}
 
 /// Acquires an exclusive lock on the queue, loads it, runs the callback with
 /// mutable access, and saves the result. The lock is held for the entire operation.
 ///
 /// Use this for any operation that modifies the queue (add, remove, next).
-pub fn with_queue<T, F>(paths: &AppPaths, f: F) -> Result<T>
+pub fn with_queue<T, F>(paths: &AppPaths, queue: &str, f: F) -> Result<T>
 where
     F: FnOnce(&mut Vec<Video>) -> Result<T>,
 {
-    // Open/create the lock file
-    let lock_file = OpenOptions::new()
-        .read(true)
-        .write(true)
-        .create(true)
-        .truncate(false)
-        .open(&paths.lock_file)?;
+    let storage = open(paths)?;
+    with_exclusive_lock(paths, || {
+        ensure_queue_exists(&*storage, queue)?;
 
-    // Acquire exclusive lock (blocks until available)
-    let mut lock = RwLock::new(lock_file);
-    let _guard = lock.write()?;
+        // Load, modify, save while holding the lock
+        let mut videos = storage.load_queue(queue)?;
+        let before = videos.clone();
+        let result = f(&mut videos)?;
+        storage.save_queue(queue, &videos)?;
 
-    // Load, modify, save while holding the lock
-    let mut queue = load_queue(&paths.queue_file);
-    let result = f(&mut queue)?;
-    save_queue(&paths.queue_file, &queue)?;
+        record_changes(
+            &*storage,
+            QueueSplice::diff(queue, &before, &videos).into_iter(),
+        )?;
 
-    Ok(result)
-    // Lock released when _guard drops
+        Ok(result)
+    })
+}
+
+/// Like [`with_queue`], but loads two different queues under the same lock.
+/// Used for moving videos between queues so neither side can be lost.
+pub fn with_queue_pair<T, F>(paths: &AppPaths, first: &str, second: &str, f: F) -> Result<T>
+where
+    F: FnOnce(&mut Vec<Video>, &mut Vec<Video>) -> Result<T>,
+{
+    if first == second {
+        bail!("source and destination queue are both '{first}'");
+    }
+
+    let storage = open(paths)?;
+    with_exclusive_lock(paths, || {
+        ensure_queue_exists(&*storage, first)?;
+        ensure_queue_exists(&*storage, second)?;
+
+        let mut first_videos = storage.load_queue(first)?;
+        let mut second_videos = storage.load_queue(second)?;
+        let (first_before, second_before) = (first_videos.clone(), second_videos.clone());
+        let result = f(&mut first_videos, &mut second_videos)?;
+        storage.save_queue(first, &first_videos)?;
+        storage.save_queue(second, &second_videos)?;
+
+        let changes = QueueSplice::diff(first, &first_before, &first_videos)
+            .into_iter()
+            .chain(QueueSplice::diff(second, &second_before, &second_videos));
+        record_changes(&*storage, changes)?;
+
+        Ok(result)
+    })
 }
 
 /// Acquires a shared lock on the queue and loads it for read-only access.
 ///
 /// Use this for operations that only read the queue (list, peek).
-pub fn with_queue_read<T, F>(paths: &AppPaths, f: F) -> Result<T>
+pub fn with_queue_read<T, F>(paths: &AppPaths, queue: &str, f: F) -> Result<T>
 where
     F: FnOnce(&[Video]) -> T,
 {
-    // Open/create the lock file
-    let lock_file = OpenOptions::new()
-        .read(true)
-        .write(true)
-        .create(true)
-        .truncate(false)
-        .open(&paths.lock_file)?;
+    let storage = open(paths)?;
 
     // Acquire shared lock (blocks if exclusive lock held, allows multiple readers)
-    let lock = RwLock::new(lock_file);
+    let lock = RwLock::new(open_lock_file(paths)?);
     let _guard = lock.read()?;
 
+    ensure_queue_exists(&*storage, queue)?;
+
     // Load and process while holding the lock
-    let queue = load_queue(&paths.queue_file);
-    let result = f(&queue);
+    let videos = storage.load_queue(queue)?;
+    let result = f(&videos);
 
     Ok(result)
     // Lock released when _guard drops
 }
 
-fn load_queue(path: &Path) -> Vec<Video> {
-    if let Ok(data) = fs::read_to_string(path) {
-        serde_json::from_str(&data).unwrap_or_default()
-    } else {
-        Vec::new()
+/// Returns the names of all queues: the default queue first, then named
+/// queues in alphabetical order.
+pub fn list_queues(paths: &AppPaths) -> Result<Vec<String>> {
+    queue_names(&*open(paths)?)
+}
+
+fn queue_names(storage: &dyn Storage) -> Result<Vec<String>> {
+    let mut names: Vec<String> = storage
+        .named_queues()?
+        .into_iter()
+        .filter(|name| name != DEFAULT_QUEUE)
+        .collect();
+    names.sort();
+    names.insert(0, DEFAULT_QUEUE.to_string());
+    Ok(names)
+}
+
+pub fn queue_exists(paths: &AppPaths, queue: &str) -> Result<bool> {
+    validate_queue_name(queue)?;
+    Ok(queue == DEFAULT_QUEUE || open(paths)?.has_queue(queue)?)
+}
+
+/// Creates a new, empty named queue.
+pub fn create_queue(paths: &AppPaths, queue: &str) -> Result<()> {
+    validate_queue_name(queue)?;
+
+    let storage = open(paths)?;
+    with_exclusive_lock(paths, || {
+        if queue == DEFAULT_QUEUE || storage.has_queue(queue)? {
+            bail!("queue '{queue}' already exists");
+        }
+        storage.save_queue(queue, &[])
+    })
+}
+
+/// Renames a named queue. The default queue cannot be renamed.
+pub fn rename_queue(paths: &AppPaths, from: &str, to: &str) -> Result<()> {
+    if from == DEFAULT_QUEUE {
+        bail!("the default queue cannot be renamed");
+    }
+    validate_queue_name(to)?;
+
+    let storage = open(paths)?;
+    with_exclusive_lock(paths, || {
+        ensure_queue_exists(&*storage, from)?;
+        if to == DEFAULT_QUEUE || storage.has_queue(to)? {
+            bail!("queue '{to}' already exists");
+        }
+        storage.rename_queue(from, to)?;
+
+        // Keep undo history pointing at the queue under its new name
+        let mut journal = storage.load_journal()?;
+        for entry in journal.undo.iter_mut().chain(journal.redo.iter_mut()) {
+            for change in entry.changes.iter_mut().filter(|c| c.queue == from) {
+                change.queue = to.to_string();
+            }
+        }
+        storage.save_journal(&journal)
+    })
+}
+
+/// Deletes a named queue and returns the videos it contained.
+/// The default queue cannot be deleted.
+pub fn delete_queue(paths: &AppPaths, queue: &str) -> Result<Vec<Video>> {
+    if queue == DEFAULT_QUEUE {
+        bail!("the default queue cannot be deleted");
     }
+
+    let storage = open(paths)?;
+    with_exclusive_lock(paths, || {
+        ensure_queue_exists(&*storage, queue)?;
+        let videos = storage.load_queue(queue)?;
+        storage.delete_queue(queue)?;
+        Ok(videos)
+    })
+}
+
+/// What `undo` or `redo` got through.
+pub struct Replayed {
+    /// The entries reverted or re-applied, in that order.
+    pub entries: Vec<JournalEntry>,
+    /// Why it stopped short of `n` entries, if a conflict stopped it.
+    pub stopped_early: Option<anyhow::Error>,
+}
+
+/// Reverts up to `n` recorded queue mutations, most recent first, and moves
+/// them to the redo stack.
+///
+/// Stops early (keeping what was already reverted) if a queue has since been
+/// changed in a conflicting way or no longer exists.
+pub fn undo(paths: &AppPaths, n: usize) -> Result<Replayed> {
+    let storage = open(paths)?;
+    with_exclusive_lock(paths, || {
+        let mut journal = storage.load_journal()?;
+        let mut reverted = Vec::new();
+        let mut stopped_early = None;
+
+        while reverted.len() < n {
+            let Some(entry) = journal.undo.pop() else {
+                break;
+            };
+            let inverse: Vec<QueueSplice> =
+                entry.changes.iter().rev().map(|c| c.inverse()).collect();
+            if let Err(e) = apply_splices(&*storage, &inverse) {
+                journal.undo.push(entry);
+                if reverted.is_empty() {
+                    return Err(e.context("cannot undo"));
+                }
+                stopped_early = Some(e);
+                break;
+            }
+            journal.redo.push(entry.clone());
+            reverted.push(entry);
+        }
+
+        storage.save_journal(&journal)?;
+        Ok(Replayed {
+            entries: reverted,
+            stopped_early,
+        })
+    })
+}
+
+/// Re-applies up to `n` undone mutations and moves them back to the undo
+/// stack.
+pub fn redo(paths: &AppPaths, n: usize) -> Result<Replayed> {
+    let storage = open(paths)?;
+    with_exclusive_lock(paths, || {
+        let mut journal = storage.load_journal()?;
+        let mut reapplied = Vec::new();
+        let mut stopped_early = None;
+
+        while reapplied.len() < n {
+            let Some(entry) = journal.redo.pop() else {
+                break;
+            };
+            if let Err(e) = apply_splices(&*storage, &entry.changes) {
+                journal.redo.push(entry);
+                if reapplied.is_empty() {
+                    return Err(e.context("cannot redo"));
+                }
+                stopped_early = Some(e);
+                break;
+            }
+            journal.undo.push(entry.clone());
+            reapplied.push(entry);
+        }
+
+        storage.save_journal(&journal)?;
+        Ok(Replayed {
+            entries: reapplied,
+            stopped_early,
+        })
+    })
 }
 
-fn save_queue(path: &Path, queue: &[Video]) -> Result<()> {
-    let data = serde_json::to_string_pretty(queue)?;
-    fs::write(path, data)?;
+/// Applies splices to their queues. All splices are checked before anything
+/// is written, so a conflict leaves every queue untouched.
+fn apply_splices(storage: &dyn Storage, splices: &[QueueSplice]) -> Result<()> {
+    let mut updated: Vec<(String, Vec<Video>)> = Vec::new();
+    for splice in splices {
+        ensure_queue_exists(storage, &splice.queue)?;
+        let idx = match updated.iter().position(|(q, _)| *q == splice.queue) {
+            Some(idx) => idx,
+            None => {
+                let videos = storage.load_queue(&splice.queue)?;
+                updated.push((splice.queue.clone(), videos));
+                updated.len() - 1
+            }
+        };
+        splice.apply(&mut updated[idx].1)?;
+    }
+
+    for (queue, videos) in &updated {
+        storage.save_queue(queue, videos)?;
+    }
     Ok(())
 }
 
-pub fn load_config(path: &Path) -> Config {
-    if let Ok(data) = fs::read_to_string(path) {
-        serde_json::from_str(&data).unwrap_or_default()
-    } else {
-        Config::default()
+/// Appends a journal entry for the given changes, if there are any.
+/// Must be called while holding the exclusive lock.
+fn record_changes(storage: &dyn Storage, changes: impl Iterator<Item = QueueSplice>) -> Result<()> {
+    let changes: Vec<QueueSplice> = changes.collect();
+    if changes.is_empty() {
+        return Ok(());
     }
+
+    let mut journal = storage.load_journal()?;
+    journal.record(JournalEntry {
+        timestamp: Utc::now(),
+        changes,
+    });
+    storage.save_journal(&journal)
 }
 
-pub fn save_config(path: &Path, config: &Config) -> Result<()> {
-    let data = serde_json::to_string_pretty(config)?;
-    fs::write(path, data)?;
+fn ensure_queue_exists(storage: &dyn Storage, queue: &str) -> Result<()> {
+    // Names become file paths, so never look one up that could point
+    // outside the queues directory
+    validate_queue_name(queue)?;
+    if queue != DEFAULT_QUEUE && !storage.has_queue(queue)? {
+        bail!("queue '{queue}' does not exist. Create it with `ytq queue create {queue}`.");
+    }
     Ok(())
 }
 
-pub fn log_event(history_dir: &Path, event: &Event) -> Result<()> {
-    let year = event.timestamp.year();
-    let month = event.timestamp.month();
+fn open_lock_file(paths: &AppPaths) -> Result<fs::File> {
+    // Open/create the lock file
+    let lock_file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(&paths.lock_file)?;
+    Ok(lock_file)
+}
+
+/// Runs the callback while holding the exclusive queue lock. A single lock
+/// file guards every queue so that cross-queue operations stay consistent.
+pub fn with_exclusive_lock<T>(paths: &AppPaths, f: impl FnOnce() -> Result<T>) -> Result<T> {
+    // Acquire exclusive lock (blocks until available)
+    let mut lock = RwLock::new(open_lock_file(paths)?);
+    let _guard = lock.write()?;
 
-    // Partition: ~/.local/share/ytq/history/2026-01.jsonl
-    let log_file_path = history_dir.join(format!("{year}-{month:02}.jsonl"));
+    f()
+    // Lock released when _guard drops
+}
 
-    let mut file = OpenOptions::new()
-        .create(true)
-        .append(true)
-        .open(log_file_path)?;
+pub fn load_config(path: &Path) -> Result<Config> {
+    load_versioned(path, Schema::Config)
+}
 
-    let log_entry = serde_json::to_string(&event)?;
-    writeln!(file, "{log_entry}")?;
+pub fn save_config(path: &Path, config: &Config) -> Result<()> {
+    // The backend may have changed
+    forget_opened();
+    save_versioned(path, Schema::Config, config)
+}
 
-    Ok(())
+pub fn log_event(paths: &AppPaths, event: &Event) -> Result<()> {
+    open(paths)?.log_events(std::slice::from_ref(event))
 }
 
-pub fn stream_history(history_dir: &Path) -> Vec<Event> {
-    let mut events = Vec::new();
+/// Like [`log_event`] for many events at once, e.g. history back-filled by
+/// an import; each partition is written once.
+pub fn log_events(paths: &AppPaths, events: &[Event]) -> Result<()> {
+    open(paths)?.log_events(events)
+}
+
+/// Returns every readable history event, oldest first.
+pub fn stream_history(paths: &AppPaths) -> Result<Vec<Event>> {
+    open(paths)?.history()
+}
+
+/// Returns the history `ytq stats` needs for `range`; see
+/// [`Storage::history_for`].
+pub fn history_for(paths: &AppPaths, range: &DateRange, queue: Option<&str>) -> Result<Vec<Event>> {
+    open(paths)?.history_for(range, queue)
+}
+
+/// Loads cached video metadata, keyed by video ID.
+pub fn load_metadata(paths: &AppPaths) -> Result<HashMap<String, VideoMeta>> {
+    open(paths)?.load_metadata()
+}
+
+/// Adds or replaces metadata entries, leaving the rest of the cache alone.
+pub fn upsert_metadata(paths: &AppPaths, entries: &[VideoMeta]) -> Result<()> {
+    open(paths)?.upsert_metadata(entries)
+}
+
+/// Loads YouTube video categories, mapping category ID to category name.
+pub fn load_categories(paths: &AppPaths) -> Result<HashMap<String, String>> {
+    open(paths)?.load_categories()
+}
+
+pub fn save_categories(paths: &AppPaths, categories: &HashMap<String, String>) -> Result<()> {
+    open(paths)?.save_categories(categories)
+}
+
+/// Acquires the exclusive lock, loads the subscriptions, runs the callback
+/// with mutable access, and saves the result.
+pub fn with_subscriptions<T, F>(paths: &AppPaths, f: F) -> Result<T>
+where
+    F: FnOnce(&mut Vec<Subscription>) -> Result<T>,
+{
+    let storage = open(paths)?;
+    with_exclusive_lock(paths, || {
+        let mut subscriptions = storage.load_subscriptions()?;
+        let result = f(&mut subscriptions)?;
+        storage.save_subscriptions(&subscriptions)?;
+        Ok(result)
+    })
+}
+
+pub fn load_subscriptions(paths: &AppPaths) -> Result<Vec<Subscription>> {
+    open(paths)?.load_subscriptions()
+}
+
+/// Item counts of a [`Snapshot`], for reporting.
+#[derive(Debug, Default, PartialEq)]
+pub struct Counts {
+    pub queues: usize,
+    pub videos: usize,
+    pub events: usize,
+    pub metadata: usize,
+    pub subscriptions: usize,
+}
+
+/// Everything a backend holds, in backend-independent form.
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct Snapshot {
+    /// Every queue, including the default one, keyed by name.
+    pub queues: BTreeMap<String, Vec<Video>>,
+    pub history: Vec<Event>,
+    pub metadata: HashMap<String, VideoMeta>,
+    pub categories: HashMap<String, String>,
+    pub subscriptions: Vec<Subscription>,
+    pub journal: Journal,
+}
+
+impl Snapshot {
+    pub fn read(storage: &dyn Storage) -> Result<Self> {
+        let mut queues = BTreeMap::new();
+        for name in queue_names(storage)? {
+            let videos = storage.load_queue(&name)?;
+            queues.insert(name, videos);
+        }
+        Ok(Self {
+            queues,
+            history: storage.history()?,
+            metadata: storage.load_metadata()?,
+            categories: storage.load_categories()?,
+            subscriptions: storage.load_subscriptions()?,
+            journal: storage.load_journal()?,
+        })
+    }
+
+    /// Writes everything into `storage`, which should be empty: history is
+    /// appended and metadata merged, not replaced.
+    pub fn write(&self, storage: &dyn Storage) -> Result<()> {
+        for (name, videos) in &self.queues {
+            storage.save_queue(name, videos)?;
+        }
+        storage.log_events(&self.history)?;
+        let metadata: Vec<VideoMeta> = self.metadata.values().cloned().collect();
+        storage.upsert_metadata(&metadata)?;
+        storage.save_categories(&self.categories)?;
+        storage.save_subscriptions(&self.subscriptions)?;
+        storage.save_journal(&self.journal)
+    }
+
+    pub fn counts(&self) -> Counts {
+        Counts {
+            queues: self.queues.len(),
+            videos: self.queues.values().map(Vec::len).sum(),
+            events: self.history.len(),
+            metadata: self.metadata.len(),
+            subscriptions: self.subscriptions.len(),
+        }
+    }
+}
+
+/// What `migrate_storage` did.
+#[derive(Debug)]
+pub struct MigrationReport {
+    pub from: StorageKind,
+    pub counts: Counts,
+    /// Where the target's earlier data, if any, was moved.
+    pub set_aside: Option<PathBuf>,
+}
+
+/// Copies all data from the configured backend to `to`, checks that it
+/// reads back identically and then switches the config over. Whatever the
+/// target held before is moved aside first. The source is left as it was.
+pub fn migrate_storage(paths: &AppPaths, to: StorageKind) -> Result<MigrationReport> {
+    with_exclusive_lock(paths, || {
+        let mut config = load_config(&paths.config_file)?;
+        let from = config.storage;
+        if from == to {
+            bail!("your data is already stored as {}", to.name());
+        }
+
+        let snapshot = Snapshot::read(&*open_kind(paths, from)?)?;
+        let set_aside = set_aside(paths, to)?;
+
+        let target = open_kind(paths, to)?;
+        snapshot.write(&*target)?;
+        let written = Snapshot::read(&*target)?;
+        if serde_json::to_value(&written)? != serde_json::to_value(&snapshot)? {
+            bail!("the converted data does not match the original; your config was not changed");
+        }
+
+        config.storage = to;
+        save_config(&paths.config_file, &config)?;
+        Ok(MigrationReport {
+            from,
+            counts: snapshot.counts(),
+            set_aside,
+        })
+    })
+}
+
+/// Replaces everything the configured backend holds with `snapshot`, and
+/// the config with `config`, except for the API key (never exported) and
+/// the backend. The current data is moved aside first; the path it went to
+/// is returned.
+pub fn replace_all(
+    paths: &AppPaths,
+    mut config: Config,
+    snapshot: &Snapshot,
+) -> Result<Option<PathBuf>> {
+    with_exclusive_lock(paths, || {
+        let local = load_config(&paths.config_file)?;
+        let set_aside = set_aside(paths, local.storage)?;
+        snapshot.write(&*open_kind(paths, local.storage)?)?;
+
+        config.youtube_api_key = local.youtube_api_key;
+        config.storage = local.storage;
+        save_config(&paths.config_file, &config)?;
+        Ok(set_aside)
+    })
+}
+
+/// Runs `f` on the configured backend while holding the queue lock.
+pub fn with_storage<T>(paths: &AppPaths, f: impl FnOnce(&dyn Storage) -> Result<T>) -> Result<T> {
+    with_exclusive_lock(paths, || f(&*open(paths)?))
+}
+
+/// Moves whatever a backend holds out of the way, so it starts empty.
+fn set_aside(paths: &AppPaths, kind: StorageKind) -> Result<Option<PathBuf>> {
+    // An open connection would keep writing to the moved file
+    forget_opened();
+    match kind {
+        StorageKind::Json => set_aside_json(paths),
+        StorageKind::Sqlite => set_aside_file(&paths.database_file),
+    }
+}
+
+/// Moves the JSON data files, if there are any, into a
+/// `json-backup-<timestamp>` directory.
+fn set_aside_json(paths: &AppPaths) -> Result<Option<PathBuf>> {
+    let has_files =
+        |dir: &Path| fs::read_dir(dir).is_ok_and(|mut entries| entries.next().is_some());
+    let files = [
+        &paths.queue_file,
+        &paths.metadata_file,
+        &paths.categories_file,
+        &paths.journal_file,
+        &paths.subscriptions_file,
+    ];
+    if !files.iter().any(|f| f.exists())
+        && !has_files(&paths.queues_dir)
+        && !has_files(&paths.history_dir)
+    {
+        return Ok(None);
+    }
+
+    let stamp = Utc::now().format("%Y%m%d-%H%M%S");
+    let backup = paths.data_dir.join(format!("json-backup-{stamp}"));
+    fs::create_dir_all(&backup)
+        .with_context(|| format!("failed to create {}", backup.display()))?;
+    let dirs = [&paths.queues_dir, &paths.history_dir];
+    for path in files.into_iter().chain(dirs).filter(|p| p.exists()) {
+        let target = backup.join(path.file_name().unwrap_or_default());
+        fs::rename(path, &target)
+            .with_context(|| format!("failed to move {} aside", path.display()))?;
+    }
+    for dir in dirs {
+        fs::create_dir_all(dir)?;
+    }
+    Ok(Some(backup))
+}
+
+/// Renames a file, if it exists, to `<name>.<timestamp>.bak`.
+fn set_aside_file(path: &Path) -> Result<Option<PathBuf>> {
+    if !path.exists() {
+        return Ok(None);
+    }
+    let stamp = Utc::now().format("%Y%m%d-%H%M%S");
+    let target = path.with_file_name(format!("{}.{stamp}.bak", file_name(path)?));
+    fs::rename(path, &target)
+        .with_context(|| format!("failed to move {} aside", path.display()))?;
+    Ok(Some(target))
+}
+
+/// The original backend: one JSON file per queue plus the caches, the
+/// journal and subscriptions, and monthly JSONL history partitions.
+pub struct JsonStorage<'a> {
+    paths: &'a AppPaths,
+}
+
+impl Storage for JsonStorage<'_> {
+    fn named_queues(&self) -> Result<Vec<String>> {
+        Ok(fs::read_dir(&self.paths.queues_dir)
+            .into_iter()
+            .flatten()
+            .flatten()
+            .filter_map(|entry| {
+                let path = entry.path();
+                if path.extension().is_none_or(|ext| ext != "json") {
+                    return None;
+                }
+                let name = path.file_stem()?.to_str()?.to_string();
+                validate_queue_name(&name).ok()?;
+                Some(name)
+            })
+            .collect())
+    }
+
+    fn has_queue(&self, queue: &str) -> Result<bool> {
+        Ok(self.paths.queue_file_for(queue).exists())
+    }
+
+    fn load_queue(&self, queue: &str) -> Result<Vec<Video>> {
+        load_versioned(&self.paths.queue_file_for(queue), Schema::Queue)
+    }
+
+    fn save_queue(&self, queue: &str, videos: &[Video]) -> Result<()> {
+        save_versioned(&self.paths.queue_file_for(queue), Schema::Queue, videos)
+    }
+
+    fn rename_queue(&self, from: &str, to: &str) -> Result<()> {
+        fs::rename(
+            self.paths.queue_file_for(from),
+            self.paths.queue_file_for(to),
+        )?;
+        Ok(())
+    }
+
+    fn delete_queue(&self, queue: &str) -> Result<()> {
+        fs::remove_file(self.paths.queue_file_for(queue))?;
+        Ok(())
+    }
+
+    fn load_journal(&self) -> Result<Journal> {
+        load_json(&self.paths.journal_file)
+    }
+
+    fn save_journal(&self, journal: &Journal) -> Result<()> {
+        let data = serde_json::to_string(journal)?;
+        write_atomic(&self.paths.journal_file, data.as_bytes())
+    }
+
+    fn log_events(&self, events: &[Event]) -> Result<()> {
+        // Partition: ~/.local/share/ytq/history/2026-01.jsonl
+        let mut partitions: BTreeMap<String, Vec<&Event>> = BTreeMap::new();
+        for event in events {
+            let name = format!(
+                "{}-{:02}.jsonl",
+                event.timestamp.year(),
+                event.timestamp.month()
+            );
+            partitions.entry(name).or_default().push(event);
+        }
+
+        let mut index = HistoryIndex::load(&self.index_file());
+        for (name, events) in partitions {
+            let log_file_path = self.paths.history_dir.join(&name);
+            let mut file = OpenOptions::new()
+                .create(true)
+                .read(true)
+                .append(true)
+                .open(&log_file_path)?;
+            let old_len = file.metadata()?.len();
+
+            let mut lines = String::new();
+            if old_len == 0 {
+                // New partitions start with a header recording the format version
+                let header = HistoryHeader {
+                    version: Schema::History.current_version(),
+                };
+                lines.push_str(&serde_json::to_string(&header)?);
+                lines.push('\n');
+            } else if !ends_with_newline(&mut file)? {
+                // A crash mid-append can leave a partial last line; start on a
+                // fresh line so only that one event is lost
+                lines.push('\n');
+            }
+            for event in &events {
+                lines.push_str(&serde_json::to_string(event)?);
+                lines.push('\n');
+            }
+
+            // One write call per partition, flushed to disk before returning
+            file.write_all(lines.as_bytes())?;
+            file.sync_data()?;
 
-    if let Ok(entries) = fs::read_dir(history_dir) {
-        for entry in entries.flatten() {
-            let path = entry.path();
-
-            if path.is_file()
-                && path
-                    .extension()
-                    .is_some_and(|ext| ext.eq_ignore_ascii_case("jsonl"))
-                && let Ok(file) = fs::File::open(&path)
-            {
-                let reader = BufReader::new(file);
-                for line in reader.lines().map_while(Result::ok) {
-                    // Skip empty lines or bad JSON
-                    if let Ok(event) = serde_json::from_str::<Event>(&line) {
-                        events.push(event);
+            // Add to the summary if it is up to date, otherwise start over
+            let new_len = file.metadata()?.len();
+            match index.months.get_mut(&name) {
+                Some(summary) if summary.bytes == old_len => {
+                    for event in events {
+                        summary.record(event);
                     }
+                    summary.bytes = new_len;
+                }
+                _ => {
+                    let summary = summarize_partition(&log_file_path)?;
+                    index.months.insert(name, summary);
                 }
             }
         }
+        index.save(&self.index_file())
+    }
+
+    fn history(&self) -> Result<Vec<Event>> {
+        let mut events = Vec::new();
+        for path in self.partitions() {
+            if let Ok(file) = fs::File::open(&path) {
+                read_partition(&path, file, &mut events);
+            }
+        }
+
+        // Sort logic is critical now that we read multiple files
+        events.sort_by_key(|e| e.timestamp);
+
+        Ok(events)
+    }
+
+    fn history_for(&self, range: &DateRange, queue: Option<&str>) -> Result<Vec<Event>> {
+        let Some(names) = self.history_index()?.partitions_for(range, queue) else {
+            return self.history();
+        };
+
+        let mut events = Vec::new();
+        for name in names {
+            let path = self.paths.history_dir.join(name);
+            if let Ok(file) = fs::File::open(&path) {
+                read_partition(&path, file, &mut events);
+            }
+        }
+        events.sort_by_key(|e| e.timestamp);
+        Ok(events)
+    }
+
+    fn load_metadata(&self) -> Result<HashMap<String, VideoMeta>> {
+        load_versioned(&self.paths.metadata_file, Schema::Metadata)
+    }
+
+    fn upsert_metadata(&self, entries: &[VideoMeta]) -> Result<()> {
+        let mut metadata = self.load_metadata()?;
+        for meta in entries {
+            metadata.insert(meta.id.clone(), meta.clone());
+        }
+        save_versioned(&self.paths.metadata_file, Schema::Metadata, &metadata)
+    }
+
+    fn load_categories(&self) -> Result<HashMap<String, String>> {
+        load_json(&self.paths.categories_file)
+    }
+
+    fn save_categories(&self, categories: &HashMap<String, String>) -> Result<()> {
+        let data = serde_json::to_string_pretty(categories)?;
+        write_atomic(&self.paths.categories_file, data.as_bytes())
+    }
+
+    fn load_subscriptions(&self) -> Result<Vec<Subscription>> {
+        load_json(&self.paths.subscriptions_file)
+    }
+
+    fn save_subscriptions(&self, subscriptions: &[Subscription]) -> Result<()> {
+        let data = serde_json::to_string_pretty(subscriptions)?;
+        write_atomic(&self.paths.subscriptions_file, data.as_bytes())
+    }
+}
+
+impl JsonStorage<'_> {
+    fn index_file(&self) -> PathBuf {
+        self.paths.history_dir.join("index.json")
+    }
+
+    /// The history partitions, in any order.
+    fn partitions(&self) -> Vec<PathBuf> {
+        let Ok(entries) = fs::read_dir(&self.paths.history_dir) else {
+            return Vec::new();
+        };
+        entries
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|path| {
+                path.is_file()
+                    && path
+                        .extension()
+                        .is_some_and(|ext| ext.eq_ignore_ascii_case("jsonl"))
+            })
+            .collect()
+    }
+
+    /// Loads the history index, first summarizing again every partition
+    /// that is new or changed since it was last summarized.
+    fn history_index(&self) -> Result<HistoryIndex> {
+        let mut index = HistoryIndex::load(&self.index_file());
+        let mut changed = false;
+        let mut names = Vec::new();
+        for path in self.partitions() {
+            let name = file_name(&path)?;
+            let bytes = fs::metadata(&path)?.len();
+            if index.months.get(&name).is_none_or(|m| m.bytes != bytes) {
+                index
+                    .months
+                    .insert(name.clone(), summarize_partition(&path)?);
+                changed = true;
+            }
+            names.push(name);
+        }
+        let before = index.months.len();
+        index.months.retain(|name, _| names.contains(name));
+        if changed || index.months.len() != before {
+            index.save(&self.index_file())?;
+        }
+        Ok(index)
+    }
+}
+
+fn summarize_partition(path: &Path) -> Result<MonthSummary> {
+    let file =
+        fs::File::open(path).with_context(|| format!("failed to read {}", path.display()))?;
+    let bytes = file.metadata()?.len();
+    let mut events = Vec::new();
+    read_partition(path, file, &mut events);
+    Ok(MonthSummary::new(&events, bytes))
+}
+
+/// Reads the events of one history partition into `events`. Unreadable
+/// lines are skipped with a notice so stats still work.
+fn read_partition(path: &Path, file: fs::File, events: &mut Vec<Event>) {
+    let reader = BufReader::new(file);
+    let mut unreadable = 0;
+    // Partitions without a header predate versioning
+    let mut version = 0;
+    for line in reader.lines().map_while(Result::ok) {
+        if line.trim().is_empty() {
+            continue;
+        }
+        match parse_history_line(path, &mut version, &line) {
+            Ok(Some(event)) => events.push(event),
+            Ok(None) => {}
+            Err(_) => unreadable += 1,
+        }
+    }
+    // Keep going, but don't hide the damage
+    if unreadable > 0 {
+        notice(Notice::UnreadableHistory {
+            path: path.to_path_buf(),
+            count: unreadable,
+        });
+    }
+}
+
+/// Whether a file is empty or its last byte is a newline.
+fn ends_with_newline(file: &mut fs::File) -> Result<bool> {
+    let len = file.metadata()?.len();
+    if len == 0 {
+        return Ok(true);
+    }
+    let mut last = [0u8; 1];
+    file.seek(SeekFrom::Start(len - 1))?;
+    file.read_exact(&mut last)?;
+    Ok(last[0] == b'\n')
+}
+
+/// Data files that carry a format version. Files written before versioning
+/// are version 0: the bare data, without an [`Envelope`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Schema {
+    /// `queue.json` and `queues/*.json`.
+    Queue,
+    Metadata,
+    Config,
+    /// Each line of `history/*.jsonl`. The version comes from a
+    /// [`HistoryHeader`] line rather than an envelope, so the log stays
+    /// append-only.
+    History,
+}
+
+/// Upgrades one version of a file's data to the next.
+type Migration = fn(Value) -> Result<Value>;
+
+impl Schema {
+    /// `migrations()[n]` turns version `n` into version `n + 1`. To change a
+    /// format, append a step here; the current version follows from it.
+    fn migrations(self) -> &'static [Migration] {
+        match self {
+            // Version 1 only added the envelope (or, for history, the header)
+            Schema::Queue | Schema::Config | Schema::History => &[unchanged],
+            Schema::Metadata => &[drop_incomplete_metadata],
+        }
+    }
+
+    pub fn current_version(self) -> u32 {
+        self.migrations().len() as u32
     }
+}
+
+/// Wrapper stored around versioned JSON files:
+/// `{"version": 1, "data": ...}`.
+#[derive(Serialize, Deserialize)]
+pub struct Envelope<T> {
+    pub version: u32,
+    pub data: T,
+}
+
+/// First line of history partitions written since versioning.
+#[derive(Serialize, Deserialize)]
+struct HistoryHeader {
+    version: u32,
+}
 
-    // Sort logic is critical now that we read multiple files
-    events.sort_by(|a, b| a.timestamp.cmp(&b.timestamp));
+fn unchanged(data: Value) -> Result<Value> {
+    Ok(data)
+}
+
+/// Metadata cached before channel, category and publish dates were fetched
+/// can't be completed offline. Drop those entries so `ytq fetch` picks them
+/// up again.
+fn drop_incomplete_metadata(data: Value) -> Result<Value> {
+    let Value::Object(entries) = data else {
+        return Ok(data);
+    };
+    let total = entries.len();
+    let kept: serde_json::Map<String, Value> = entries
+        .into_iter()
+        .filter(|(_, meta)| VideoMeta::deserialize(meta).is_ok())
+        .collect();
+    if kept.len() < total {
+        notice(Notice::DroppedMetadata(total - kept.len()));
+    }
+    Ok(Value::Object(kept))
+}
 
-    events
+/// Unwraps a versioned file's envelope and runs the migrations it is missing.
+/// Returns the data in the current format and the version it was stored as.
+pub fn upgrade(path: &Path, schema: Schema, value: Value) -> Result<(Value, u32)> {
+    let (version, data) = match value {
+        Value::Object(mut fields) if fields.len() == 2 && fields.contains_key("data") => {
+            match fields.get("version").and_then(Value::as_u64) {
+                Some(version) => (
+                    u32::try_from(version).unwrap_or(u32::MAX),
+                    fields.remove("data").unwrap_or_default(),
+                ),
+                None => (0, Value::Object(fields)),
+            }
+        }
+        other => (0, other),
+    };
+    Ok((migrate(path, schema, version, data)?, version))
 }
 
-/// Loads video metadata from metadata.json.
-/// Returns a HashMap keyed by video ID. Returns empty map if file is
-/// missing or contains invalid JSON.
-pub fn load_metadata(path: &Path) -> HashMap<String, VideoMeta> {
-    if let Ok(data) = fs::read_to_string(path) {
-        serde_json::from_str(&data).unwrap_or_default()
-    } else {
-        HashMap::new()
+fn migrate(path: &Path, schema: Schema, version: u32, mut data: Value) -> Result<Value> {
+    ensure_supported(path, schema, version)?;
+    for step in &schema.migrations()[version as usize..] {
+        data = step(data)?;
     }
+    Ok(data)
 }
 
-/// Saves the full metadata map to metadata.json.
-pub fn save_metadata(path: &Path, metadata: &HashMap<String, VideoMeta>) -> Result<()> {
-    let data = serde_json::to_string_pretty(metadata)?;
-    fs::write(path, data)?;
+fn ensure_supported(path: &Path, schema: Schema, version: u32) -> Result<()> {
+    let current = schema.current_version();
+    if version > current {
+        bail!(
+            "{} was written by a newer version of ytq (format version {version}, this one \
+             reads up to {current}); upgrade ytq to use it",
+            path.display()
+        );
+    }
     Ok(())
 }
 
-/// Loads YouTube video categories from categories.json.
-/// Returns a HashMap mapping category ID to category name.
-pub fn load_categories(path: &Path) -> HashMap<String, String> {
-    if let Ok(data) = fs::read_to_string(path) {
-        serde_json::from_str(&data).unwrap_or_default()
-    } else {
-        HashMap::new()
+/// Parses one line of the history partition at `path`, tracking the format
+/// `version` of the lines that follow. Header lines update it and yield no
+/// event. Events in older formats are upgraded as they are read, since
+/// rewriting the log could race with another process appending to it.
+pub fn parse_history_line(path: &Path, version: &mut u32, line: &str) -> Result<Option<Event>> {
+    if let Ok(header) = serde_json::from_str::<HistoryHeader>(line) {
+        *version = header.version;
+        ensure_supported(path, Schema::History, header.version)?;
+        return Ok(None);
+    }
+    if *version == Schema::History.current_version() {
+        return Ok(Some(serde_json::from_str(line)?));
+    }
+
+    let data = migrate(path, Schema::History, *version, serde_json::from_str(line)?)?;
+    Ok(Some(Event::deserialize(data)?))
+}
+
+/// Reads a versioned JSON data file, upgrading it in place when it is in an
+/// older format. The original is kept as `<name>.v<version>.bak`.
+fn load_versioned<T>(path: &Path, schema: Schema) -> Result<T>
+where
+    T: DeserializeOwned + Serialize + Default,
+{
+    let Some(text) = read_if_exists(path)? else {
+        return Ok(T::default());
+    };
+
+    let typed_error = match serde_json::from_str::<Envelope<T>>(&text) {
+        Ok(envelope) if envelope.version == schema.current_version() => {
+            return Ok(envelope.data);
+        }
+        Ok(_) => None,
+        Err(e) => Some(e),
+    };
+
+    let value: Value = serde_json::from_str(&text).map_err(|e| corrupt_file(path, e))?;
+    let (data, version) = upgrade(path, schema, value)?;
+    if version == schema.current_version() {
+        // The envelope is current, so the typed parse failed on the data;
+        // its error has the line and column
+        let error = typed_error.expect("a current file that parses needs no upgrade");
+        return Err(corrupt_file(path, error));
+    }
+
+    let loaded = T::deserialize(&data).map_err(|e| {
+        corrupt_file(
+            path,
+            format!("{e} (after upgrading from format version {version})"),
+        )
+    })?;
+
+    let backup = path.with_file_name(format!("{}.v{version}.bak", file_name(path)?));
+    if !backup.exists() {
+        fs::copy(path, &backup).with_context(|| format!("failed to back up {}", path.display()))?;
     }
+    save_versioned(path, schema, &loaded)?;
+    notice(Notice::Upgraded {
+        path: path.to_path_buf(),
+        version: schema.current_version(),
+        backup,
+    });
+    Ok(loaded)
 }
 
-/// Saves YouTube video categories to categories.json.
-pub fn save_categories(path: &Path, categories: &HashMap<String, String>) -> Result<()> {
-    let data = serde_json::to_string_pretty(categories)?;
-    fs::write(path, data)?;
+/// Serializes `data` in its [`Envelope`], as stored on disk.
+pub fn to_versioned_json<T: Serialize + ?Sized>(schema: Schema, data: &T) -> Result<String> {
+    let envelope = Envelope {
+        version: schema.current_version(),
+        data,
+    };
+    Ok(serde_json::to_string_pretty(&envelope)?)
+}
+
+fn save_versioned<T: Serialize + ?Sized>(path: &Path, schema: Schema, data: &T) -> Result<()> {
+    write_atomic(path, to_versioned_json(schema, data)?.as_bytes())
+}
+
+/// Reads an unversioned JSON data file. A missing file yields the default
+/// value. A file that does not parse is moved aside by [`quarantine`] and
+/// reported with its line and column, so that the next save can't overwrite
+/// what's left of it.
+pub fn load_json<T: DeserializeOwned + Default>(path: &Path) -> Result<T> {
+    let Some(text) = read_if_exists(path)? else {
+        return Ok(T::default());
+    };
+    serde_json::from_str(&text).map_err(|e| corrupt_file(path, e))
+}
+
+fn read_if_exists(path: &Path) -> Result<Option<String>> {
+    match fs::read_to_string(path) {
+        Ok(text) => Ok(Some(text)),
+        Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(anyhow!(e).context(format!("failed to read {}", path.display()))),
+    }
+}
+
+/// Quarantines a file that failed to parse and builds the error reporting it.
+fn corrupt_file(path: &Path, error: impl std::fmt::Display) -> anyhow::Error {
+    match quarantine(path) {
+        Ok(moved_to) => anyhow!(
+            "{} is corrupt: {error}\n\
+             It was moved to {} so it won't be overwritten; \
+             run `ytq doctor` to check your other data files.",
+            path.display(),
+            moved_to.display()
+        ),
+        Err(e) => e.context(format!("{} is corrupt: {error}", path.display())),
+    }
+}
+
+fn file_name(path: &Path) -> Result<String> {
+    Ok(path
+        .file_name()
+        .ok_or_else(|| anyhow!("invalid data file path {}", path.display()))?
+        .to_string_lossy()
+        .to_string())
+}
+
+/// Moves a damaged file to `<name>.<timestamp>.corrupt` next to it and
+/// returns the new path.
+pub fn quarantine(path: &Path) -> Result<PathBuf> {
+    let target = quarantine_path(path)?;
+    fs::rename(path, &target)
+        .with_context(|| format!("failed to move {} aside", path.display()))?;
+    Ok(target)
+}
+
+/// Picks an unused `<name>.<timestamp>.corrupt` path next to `path`.
+pub fn quarantine_path(path: &Path) -> Result<PathBuf> {
+    let file_name = file_name(path)?;
+    let stamp = Utc::now().format("%Y%m%d-%H%M%S");
+    let mut target = path.with_file_name(format!("{file_name}.{stamp}.corrupt"));
+    // Two quarantines within a second must not overwrite each other
+    let mut n = 1;
+    while target.exists() {
+        n += 1;
+        target = path.with_file_name(format!("{file_name}.{stamp}-{n}.corrupt"));
+    }
+    Ok(target)
+}
+
+/// Replaces `path` with `data` so that readers (and a crash at any point)
+/// see either the old file or the new one, never a truncated mix: the data
+/// goes to a temporary file in the same directory, is flushed to disk, and
+/// then renamed over the original.
+pub fn write_atomic(path: &Path, data: &[u8]) -> Result<()> {
+    write_atomic_with(path, |file| file.write_all(data))
+}
+
+fn write_atomic_with(
+    path: &Path,
+    write: impl FnOnce(&mut fs::File) -> std::io::Result<()>,
+) -> Result<()> {
+    let dir = path.parent().unwrap_or(Path::new("."));
+    let file_name = file_name(path)?;
+    // Unique per process, so concurrent writers never share a temp file
+    let tmp_path = dir.join(format!(".{file_name}.{}.tmp", std::process::id()));
+
+    let result = (|| -> Result<()> {
+        let mut file = fs::File::create(&tmp_path)?;
+        write(&mut file)?;
+        file.sync_all()?;
+        fs::rename(&tmp_path, path)?;
+        Ok(())
+    })();
+
+    if let Err(e) = result {
+        let _ = fs::remove_file(&tmp_path);
+        return Err(e.context(format!("failed to write {}", path.display())));
+    }
+
+    // Persist the rename itself; not every platform can open directories
+    if let Ok(dir) = fs::File::open(dir) {
+        let _ = dir.sync_all();
+    }
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::TestDir;
+    use std::io;
+
+    fn video(id: &str) -> Video {
+        Video::new(id.to_string(), String::new(), Utc::now())
+    }
+
+    fn load_queue(path: &Path) -> Result<Vec<Video>> {
+        load_versioned(path, Schema::Queue)
+    }
+
+    fn save_queue(path: &Path, videos: &[Video]) -> Result<()> {
+        save_versioned(path, Schema::Queue, &videos)
+    }
+
+    fn test_paths(dir: &Path) -> AppPaths {
+        AppPaths::at(dir.join("config"), dir.join("data")).unwrap()
+    }
+
+    fn event(id: &str, timestamp: &str) -> Event {
+        Event {
+            timestamp: timestamp.parse().unwrap(),
+            action: crate::models::Action::Watched,
+            video_id: id.to_string(),
+            time_in_queue_sec: None,
+            queue: None,
+        }
+    }
+
+    fn history_ids(paths: &AppPaths) -> Vec<String> {
+        stream_history(paths)
+            .unwrap()
+            .into_iter()
+            .map(|e| e.video_id)
+            .collect()
+    }
+
+    fn leftover_temp_files(dir: &Path) -> Vec<String> {
+        fs::read_dir(dir)
+            .unwrap()
+            .flatten()
+            .map(|e| e.file_name().to_string_lossy().to_string())
+            .filter(|name| name.ends_with(".tmp"))
+            .collect()
+    }
+
+    #[test]
+    fn save_queue_roundtrip_leaves_no_temp_file() {
+        let dir = TestDir::new("roundtrip");
+        let path = dir.join("queue.json");
+        save_queue(&path, &[video("a"), video("b")]).unwrap();
+        save_queue(&path, &[video("c")]).unwrap();
+
+        let ids: Vec<String> = load_queue(&path)
+            .unwrap()
+            .into_iter()
+            .map(|v| v.id)
+            .collect();
+        assert_eq!(ids, vec!["c"]);
+        assert!(leftover_temp_files(&dir).is_empty());
+    }
+
+    #[test]
+    fn failed_write_keeps_previous_contents() {
+        let dir = TestDir::new("partial");
+        let path = dir.join("queue.json");
+        save_queue(&path, &[video("a")]).unwrap();
+        let before = fs::read_to_string(&path).unwrap();
+
+        // Simulate running out of disk space halfway through the new data
+        let err = write_atomic_with(&path, |file| {
+            file.write_all(b"[{\"id\": \"trunc")?;
+            Err(io::Error::new(io::ErrorKind::StorageFull, "disk full"))
+        })
+        .unwrap_err();
+
+        assert!(format!("{err:#}").contains("disk full"));
+        assert_eq!(fs::read_to_string(&path).unwrap(), before);
+        assert_eq!(load_queue(&path).unwrap().len(), 1);
+        assert!(leftover_temp_files(&dir).is_empty());
+    }
+
+    #[test]
+    fn stale_temp_file_from_a_crash_is_ignored_and_replaced() {
+        let dir = TestDir::new("stale");
+        let path = dir.join("config.json");
+        save_config(&path, &Config::default()).unwrap();
+
+        // A crash after writing part of the temp file, before the rename
+        let tmp = dir.join(format!(".config.json.{}.tmp", std::process::id()));
+        fs::write(&tmp, "{\"mode\": \"sta").unwrap();
+        assert!(load_config(&path).unwrap().offline);
+
+        let config = Config {
+            offline: false,
+            ..Default::default()
+        };
+        save_config(&path, &config).unwrap();
+        assert!(!load_config(&path).unwrap().offline);
+        assert!(leftover_temp_files(&dir).is_empty());
+    }
+
+    #[test]
+    fn corrupt_file_is_reported_and_quarantined() {
+        let dir = TestDir::new("corrupt");
+        let path = dir.join("queue.json");
+        fs::write(&path, "[\n  {\"id\": \"dQw4w9WgXcQ\",\n").unwrap();
+
+        let err = load_queue(&path).unwrap_err().to_string();
+        assert!(err.contains("queue.json is corrupt"), "{err}");
+        assert!(err.contains("line 3 column 0"), "{err}");
+        assert!(!path.exists());
+
+        let quarantined: Vec<String> = fs::read_dir(&dir)
+            .unwrap()
+            .flatten()
+            .map(|e| e.file_name().to_string_lossy().to_string())
+            .collect();
+        assert_eq!(quarantined.len(), 1);
+        assert!(quarantined[0].starts_with("queue.json.") && quarantined[0].ends_with(".corrupt"));
+
+        // With the damaged file out of the way, the queue starts empty
+        assert!(load_queue(&path).unwrap().is_empty());
+    }
+
+    #[test]
+    fn missing_file_loads_default() {
+        let dir = TestDir::new("missing");
+        assert!(load_metadata(&test_paths(&dir)).unwrap().is_empty());
+    }
+
+    #[test]
+    fn legacy_file_is_upgraded_in_place_with_backup() {
+        let dir = TestDir::new("upgrade");
+        let path = dir.join("queue.json");
+        fs::write(&path, serde_json::to_string(&[video("a")]).unwrap()).unwrap();
+        let legacy = fs::read_to_string(&path).unwrap();
+
+        assert_eq!(load_queue(&path).unwrap()[0].id, "a");
+        let envelope: Envelope<Vec<Video>> =
+            serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(envelope.version, Schema::Queue.current_version());
+        assert_eq!(
+            fs::read_to_string(dir.join("queue.json.v0.bak")).unwrap(),
+            legacy
+        );
+        assert_eq!(
+            take_notices(),
+            vec![Notice::Upgraded {
+                path: path.clone(),
+                version: Schema::Queue.current_version(),
+                backup: dir.join("queue.json.v0.bak"),
+            }]
+        );
+    }
+
+    #[test]
+    fn metadata_upgrade_drops_entries_missing_newer_fields() {
+        let dir = TestDir::new("metadata");
+        let path = dir.join("metadata.json");
+        fs::write(
+            &path,
+            r#"{"old": {"id":"old","title":"T","channel":"C","duration_seconds":10,"fetched_at":"2026-01-01T00:00:00Z"}}"#,
+        )
+        .unwrap();
+
+        let metadata: HashMap<String, VideoMeta> = load_versioned(&path, Schema::Metadata).unwrap();
+        assert!(metadata.is_empty());
+        assert!(dir.join("metadata.json.v0.bak").exists());
+    }
+
+    #[test]
+    fn newer_format_is_refused_and_left_alone() {
+        let dir = TestDir::new("newer");
+        let path = dir.join("config.json");
+        fs::write(&path, r#"{"version": 99, "data": {"offline": false}}"#).unwrap();
+
+        let err = load_config(&path).unwrap_err().to_string();
+        assert!(err.contains("newer version of ytq"), "{err}");
+        assert!(path.exists());
+    }
+
+    #[test]
+    fn history_header_is_written_once_and_legacy_lines_still_read() {
+        let dir = TestDir::new("history-version");
+        let paths = test_paths(&dir);
+        let legacy = r#"{"timestamp":"2026-02-01T12:00:00Z","action":"Queued","video_id":"old","time_in_queue_sec":null}"#;
+        fs::write(
+            paths.history_dir.join("2026-02.jsonl"),
+            format!("{legacy}\n"),
+        )
+        .unwrap();
+
+        let new = event("new", "2026-03-01T12:00:00Z");
+        log_event(&paths, &new).unwrap();
+        log_event(&paths, &new).unwrap();
+
+        let written = fs::read_to_string(paths.history_dir.join("2026-03.jsonl")).unwrap();
+        assert_eq!(written.lines().next().unwrap(), r#"{"version":1}"#);
+        assert_eq!(written.lines().count(), 3);
+        assert_eq!(history_ids(&paths), vec!["old", "new", "new"]);
+    }
+
+    #[test]
+    fn log_event_recovers_from_partial_last_line() {
+        let dir = TestDir::new("history");
+        let paths = test_paths(&dir);
+        log_event(&paths, &event("a", "2026-03-01T12:00:00Z")).unwrap();
+
+        // Simulate a crash that cut the next line short
+        let file = paths.history_dir.join("2026-03.jsonl");
+        let mut partial = OpenOptions::new().append(true).open(&file).unwrap();
+        partial.write_all(b"{\"timestamp\":\"2026-03-01T").unwrap();
+
+        log_event(&paths, &event("b", "2026-03-01T12:00:00Z")).unwrap();
+        assert_eq!(history_ids(&paths), vec!["a", "b"]);
+    }
+
+    #[test]
+    fn json_log_events_splits_a_batch_across_partitions() {
+        let dir = TestDir::new("batch");
+        let paths = test_paths(&dir);
+        let storage = JsonStorage { paths: &paths };
+        storage
+            .log_events(&[
+                event("b", "2026-04-02T12:00:00Z"),
+                event("a", "2026-03-31T12:00:00Z"),
+                event("c", "2026-04-01T12:00:00Z"),
+            ])
+            .unwrap();
+
+        let march = fs::read_to_string(paths.history_dir.join("2026-03.jsonl")).unwrap();
+        let april = fs::read_to_string(paths.history_dir.join("2026-04.jsonl")).unwrap();
+        assert_eq!(march.lines().count(), 2);
+        assert_eq!(april.lines().count(), 3);
+        assert_eq!(history_ids(&paths), vec!["a", "c", "b"]);
+    }
+
+    #[test]
+    fn history_for_reads_only_needed_partitions_and_notices_edits() {
+        let dir = TestDir::new("history-for");
+        let paths = test_paths(&dir);
+        let storage = JsonStorage { paths: &paths };
+        storage
+            .log_events(&[
+                event("january", "2026-01-10T12:00:00Z"),
+                event("march", "2026-03-10T12:00:00Z"),
+            ])
+            .unwrap();
+        let index = HistoryIndex::load(&paths.history_dir.join("index.json"));
+        assert_eq!(index.months.len(), 2);
+
+        let ids = |range: &DateRange| -> Vec<String> {
+            storage
+                .history_for(range, None)
+                .unwrap()
+                .into_iter()
+                .map(|e| e.video_id)
+                .collect()
+        };
+        let january = DateRange::specific_month(2026, 1).unwrap();
+        assert_eq!(ids(&january), vec!["january"]);
+        assert!(ids(&DateRange::specific_month(2026, 2).unwrap()).is_empty());
+
+        // A partition changed behind the index's back is summarized again
+        let mut file = OpenOptions::new()
+            .append(true)
+            .open(paths.history_dir.join("2026-01.jsonl"))
+            .unwrap();
+        let edit = event("edited", "2026-01-20T12:00:00Z");
+        writeln!(file, "{}", serde_json::to_string(&edit).unwrap()).unwrap();
+        assert_eq!(ids(&january), vec!["january", "edited"]);
+
+        // An undo after the start of the range needs the whole history
+        let mut undone = event("march", "2026-03-11T12:00:00Z");
+        undone.action = crate::models::Action::Undone;
+        storage.log_events(&[undone]).unwrap();
+        assert_eq!(ids(&DateRange::specific_month(2026, 3).unwrap()).len(), 4);
+    }
+
+    #[test]
+    fn open_reuses_the_backend_until_the_config_is_saved() {
+        let dir = TestDir::new("reopen");
+        let paths = test_paths(&dir);
+        let mut config = Config {
+            storage: StorageKind::Sqlite,
+            ..Config::default()
+        };
+        save_config(&paths.config_file, &config).unwrap();
+        with_queue(&paths, DEFAULT_QUEUE, |videos| {
+            videos.push(video("a"));
+            Ok(())
+        })
+        .unwrap();
+
+        // A config written behind ytq's back isn't read again...
+        config.storage = StorageKind::Json;
+        save_versioned(&paths.config_file, Schema::Config, &config).unwrap();
+        assert_eq!(load_queue_ids(&paths), vec!["a"]);
+
+        // ...but one saved through the store is
+        save_config(&paths.config_file, &config).unwrap();
+        assert!(load_queue_ids(&paths).is_empty());
+    }
+
+    fn load_queue_ids(paths: &AppPaths) -> Vec<String> {
+        with_queue_read(paths, DEFAULT_QUEUE, |videos| {
+            videos.iter().map(|v| v.id.clone()).collect()
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn migrate_storage_roundtrips_between_backends() {
+        let dir = TestDir::new("migrate");
+        let paths = test_paths(&dir);
+        let json = JsonStorage { paths: &paths };
+        json.save_queue(DEFAULT_QUEUE, &[video("a"), video("b")])
+            .unwrap();
+        json.save_queue("music", &[video("c")]).unwrap();
+        json.log_events(&[event("a", "2026-03-01T12:00:00Z")])
+            .unwrap();
+        json.save_categories(&HashMap::from([("10".to_string(), "Music".to_string())]))
+            .unwrap();
+        let original = serde_json::to_value(Snapshot::read(&json).unwrap()).unwrap();
+
+        let report = migrate_storage(&paths, StorageKind::Sqlite).unwrap();
+        assert_eq!(report.from, StorageKind::Json);
+        assert_eq!(report.counts.queues, 2);
+        assert_eq!(report.counts.videos, 3);
+        assert!(report.set_aside.is_none());
+        assert_eq!(
+            load_config(&paths.config_file).unwrap().storage,
+            StorageKind::Sqlite
+        );
+        assert_eq!(list_queues(&paths).unwrap(), vec![DEFAULT_QUEUE, "music"]);
+        assert!(migrate_storage(&paths, StorageKind::Sqlite).is_err());
+
+        // Going back moves the stale JSON files aside and rewrites them
+        let report = migrate_storage(&paths, StorageKind::Json).unwrap();
+        let backup = report.set_aside.unwrap();
+        assert!(backup.join("queue.json").exists());
+        assert!(backup.join("queues").join("music.json").exists());
+        let restored = serde_json::to_value(Snapshot::read(&json).unwrap()).unwrap();
+        assert_eq!(restored, original);
+    }
+}
@@ -0,0 +1,471 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::LazyLock;
+
+use crate::youtube;
+
+use anyhow::{Context, Result, bail};
+use chrono::{DateTime, FixedOffset, NaiveDateTime, Utc};
+use regex::Regex;
+use serde::Deserialize;
+
+/// A Google Takeout file `ytq import --takeout` understands.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TakeoutFile {
+    /// The Watch Later playlist, `playlists/Watch later-videos.csv`.
+    WatchLater(PathBuf),
+    /// `history/watch-history.json`, or the `.html` version.
+    WatchHistory(PathBuf),
+}
+
+impl TakeoutFile {
+    pub fn path(&self) -> &Path {
+        match self {
+            Self::WatchLater(path) | Self::WatchHistory(path) => path,
+        }
+    }
+}
+
+/// The files to import from `path`: a single Takeout file, or a directory
+/// (an extracted Takeout archive or any folder inside it) searched for the
+/// Watch Later playlist and the watch history.
+pub fn find_files(path: &Path) -> Result<Vec<TakeoutFile>> {
+    if !path.is_dir() {
+        let kind = match extension(path).as_str() {
+            "csv" => TakeoutFile::WatchLater(path.to_path_buf()),
+            "json" | "html" => TakeoutFile::WatchHistory(path.to_path_buf()),
+            "zip" | "tgz" | "gz" => bail!(
+                "{} is a compressed Takeout archive; extract it first and pass the folder",
+                path.display()
+            ),
+            _ => bail!(
+                "{} is not a Takeout file: expected the Watch Later playlist (.csv) \
+                 or watch-history (.json or .html)",
+                path.display()
+            ),
+        };
+        if !path.exists() {
+            bail!("{} does not exist", path.display());
+        }
+        return Ok(vec![kind]);
+    }
+
+    let mut files = Vec::new();
+    collect_files(path, &mut files)?;
+    if files.is_empty() {
+        bail!(
+            "no Watch Later playlist or watch history found in {}.\n\
+             Pass the CSV or watch-history file directly if it was renamed.",
+            path.display()
+        );
+    }
+    Ok(files)
+}
+
+fn collect_files(dir: &Path, files: &mut Vec<TakeoutFile>) -> Result<()> {
+    let mut entries: Vec<PathBuf> = fs::read_dir(dir)
+        .with_context(|| format!("failed to read {}", dir.display()))?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .collect();
+    entries.sort();
+
+    // Both history files hold the same events, and the HTML one less
+    // precisely, so it is only read when there is no JSON
+    let has_json_history = entries.iter().any(|p| file_name(p) == "watch-history.json");
+    for path in entries {
+        let name = file_name(&path);
+        if path.is_dir() {
+            collect_files(&path, files)?;
+        } else if name.starts_with("watch later") && extension(&path) == "csv" {
+            files.push(TakeoutFile::WatchLater(path));
+        } else if name == "watch-history.json"
+            || (name == "watch-history.html" && !has_json_history)
+        {
+            files.push(TakeoutFile::WatchHistory(path));
+        }
+    }
+    Ok(())
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|n| n.to_string_lossy().to_lowercase())
+        .unwrap_or_default()
+}
+
+fn extension(path: &Path) -> String {
+    path.extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .unwrap_or_default()
+}
+
+/// A video from a Takeout file, with when it was saved or watched.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TakeoutVideo {
+    pub id: String,
+    pub time: DateTime<Utc>,
+}
+
+/// What one Takeout file held.
+#[derive(Debug, Default)]
+pub struct Parsed {
+    pub videos: Vec<TakeoutVideo>,
+    /// Entries that are not importable videos, counted per reason.
+    pub skipped: BTreeMap<String, usize>,
+}
+
+impl Parsed {
+    fn skip(&mut self, reason: impl Into<String>) {
+        *self.skipped.entry(reason.into()).or_default() += 1;
+    }
+
+    fn push(&mut self, link: &str, time: Option<DateTime<Utc>>) {
+        match (youtube::extract_video_id(link), time) {
+            (Ok(id), Some(time)) => self.videos.push(TakeoutVideo { id, time }),
+            (Ok(_), None) => self.skip("unreadable date"),
+            (Err(e), _) => self.skip(youtube::rejection_reason(&e)),
+        }
+    }
+}
+
+/// Reads a Takeout file of either kind.
+pub fn parse_file(file: &TakeoutFile) -> Result<Parsed> {
+    let path = file.path();
+    let text =
+        fs::read_to_string(path).with_context(|| format!("failed to read {}", path.display()))?;
+    let parsed = match file {
+        TakeoutFile::WatchLater(_) => parse_watch_later(&text),
+        TakeoutFile::WatchHistory(_) if extension(path) == "html" => Ok(parse_history_html(&text)),
+        TakeoutFile::WatchHistory(_) => parse_history_json(&text),
+    };
+    parsed.with_context(|| format!("cannot import {}", path.display()))
+}
+
+/// Parses a Takeout playlist CSV. Current exports have a `Video ID` and a
+/// `Playlist Video Creation Timestamp` column; older ones start with a
+/// block of playlist details before the `Video Id,Time Added` table.
+pub fn parse_watch_later(text: &str) -> Result<Parsed> {
+    let records = csv_records(text);
+    let header = records.iter().position(|row| {
+        row.iter()
+            .any(|cell| cell.trim().eq_ignore_ascii_case("video id"))
+    });
+    let Some(header) = header else {
+        bail!("no 'Video ID' column; is this a playlist CSV from Google Takeout?");
+    };
+    let columns: Vec<String> = records[header]
+        .iter()
+        .map(|c| c.trim().to_lowercase())
+        .collect();
+    let id_column = columns.iter().position(|c| c == "video id").unwrap();
+    let Some(time_column) = columns.iter().position(|c| c.contains("time")) else {
+        bail!("no timestamp column next to 'Video ID'");
+    };
+
+    let mut parsed = Parsed::default();
+    for row in &records[header + 1..] {
+        if row.iter().all(|cell| cell.trim().is_empty()) {
+            continue;
+        }
+        let id = row.get(id_column).map_or("", |c| c.trim());
+        let time = row.get(time_column).and_then(|c| parse_time(c));
+        parsed.push(id, time);
+    }
+    Ok(parsed)
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct HistoryEntry {
+    title_url: Option<String>,
+    time: String,
+    #[serde(default)]
+    details: Vec<HistoryDetail>,
+}
+
+#[derive(Deserialize)]
+struct HistoryDetail {
+    name: String,
+}
+
+const AD: &str = "ad";
+const REMOVED: &str = "video since removed";
+
+/// Parses `watch-history.json`: one entry per view, newest first.
+pub fn parse_history_json(text: &str) -> Result<Parsed> {
+    let entries: Vec<HistoryEntry> = serde_json::from_str(text.trim_start_matches('\u{feff}'))
+        .context("not a Takeout watch history (expected a JSON list of entries)")?;
+
+    let mut parsed = Parsed::default();
+    for entry in entries {
+        if entry.details.iter().any(|d| d.name.contains("Google Ads")) {
+            parsed.skip(AD);
+            continue;
+        }
+        match entry.title_url {
+            Some(url) => parsed.push(&url, parse_time(&entry.time)),
+            None => parsed.skip(REMOVED),
+        }
+    }
+    Ok(parsed)
+}
+
+static HTML_ENTRY_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#"<div class="outer-cell[^"]*">"#).unwrap());
+static HTML_BODY_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"(?s)<div class="content-cell[^"]*body-1">(.*?)</div>"#).unwrap()
+});
+static HTML_LINK_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r#"href="([^"]*)""#).unwrap());
+
+/// Parses `watch-history.html`, the format Takeout offers besides JSON.
+/// Its dates are localized text, so only English month names are read.
+pub fn parse_history_html(text: &str) -> Parsed {
+    let mut parsed = Parsed::default();
+    for entry in HTML_ENTRY_RE.split(text).skip(1) {
+        let Some(body) = HTML_BODY_RE.captures(entry).map(|c| c[1].to_string()) else {
+            continue;
+        };
+        if entry.contains("From Google Ads") {
+            parsed.skip(AD);
+            continue;
+        }
+        let Some(link) = HTML_LINK_RE.captures(&body) else {
+            parsed.skip(REMOVED);
+            continue;
+        };
+        // The date is the last line of the cell
+        let date = body.rsplit("<br>").find(|line| !line.trim().is_empty());
+        let time = date.and_then(|d| parse_time(&unescape_html(d)));
+        parsed.push(&unescape_html(&link[1]), time);
+    }
+    parsed
+}
+
+pub fn unescape_html(text: &str) -> String {
+    text.replace("&nbsp;", " ")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&amp;", "&")
+}
+
+/// Reads the timestamps Takeout writes: RFC 3339 in JSON and current CSVs,
+/// `2021-03-01 12:34:56 UTC` in older CSVs, and `Jan 15, 2023, 12:34:56 PM
+/// EST` in HTML.
+pub fn parse_time(text: &str) -> Option<DateTime<Utc>> {
+    let text = text.replace(['\u{a0}', '\u{202f}'], " ");
+    let text = text.trim();
+    if let Ok(time) = DateTime::parse_from_rfc3339(text) {
+        return Some(time.to_utc());
+    }
+
+    let (local, zone) = text.rsplit_once(' ')?;
+    let offset = zone_offset(zone)?;
+    [
+        "%Y-%m-%d %H:%M:%S",
+        "%b %d, %Y, %I:%M:%S %p",
+        "%d %b %Y, %H:%M:%S",
+        "%b %d, %Y, %H:%M:%S",
+    ]
+    .iter()
+    .find_map(|format| NaiveDateTime::parse_from_str(local, format).ok())
+    .and_then(|local| local.and_local_timezone(offset).single())
+    .map(|time| time.to_utc())
+}
+
+/// The offset of a zone as Takeout names it: `UTC`, `GMT+01:00`, or one of
+/// the common abbreviations.
+fn zone_offset(zone: &str) -> Option<FixedOffset> {
+    let hours = match zone {
+        "UTC" | "GMT" | "Z" => 0,
+        "EST" => -5,
+        "EDT" | "AST" => -4,
+        "CST" | "MDT" => -6,
+        "CDT" => -5,
+        "MST" | "PDT" => -7,
+        "PST" | "AKDT" => -8,
+        "AKST" => -9,
+        "HST" => -10,
+        "WET" => 0,
+        "BST" | "CET" | "WEST" => 1,
+        "CEST" | "EET" => 2,
+        "EEST" | "MSK" => 3,
+        "JST" | "KST" => 9,
+        "AEST" => 10,
+        "AEDT" => 11,
+        _ => return gmt_offset(zone),
+    };
+    FixedOffset::east_opt(hours * 3600)
+}
+
+/// Parses `GMT+1`, `GMT-05:30` or `UTC+02:00`.
+fn gmt_offset(zone: &str) -> Option<FixedOffset> {
+    let rest = zone
+        .strip_prefix("GMT")
+        .or_else(|| zone.strip_prefix("UTC"))?;
+    let sign = match rest.chars().next()? {
+        '+' => 1,
+        '-' => -1,
+        _ => return None,
+    };
+    let (hours, minutes) = rest[1..].split_once(':').unwrap_or((&rest[1..], "0"));
+    // Real offsets are at most 14 hours; anything else is a damaged file
+    let hours = hours.parse::<i32>().ok().filter(|h| (0..=14).contains(h))?;
+    let minutes = minutes
+        .parse::<i32>()
+        .ok()
+        .filter(|m| (0..60).contains(m))?;
+    FixedOffset::east_opt(sign * (hours * 3600 + minutes * 60))
+}
+
+/// Splits CSV text into records, honouring quoted fields (which may hold
+/// commas, doubled quotes and line breaks).
+pub fn csv_records(text: &str) -> Vec<Vec<String>> {
+    let mut records = Vec::new();
+    let mut record = Vec::new();
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut chars = text.trim_start_matches('\u{feff}').chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if in_quotes && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => in_quotes = !in_quotes,
+            ',' if !in_quotes => record.push(std::mem::take(&mut field)),
+            '\r' if !in_quotes => {}
+            '\n' if !in_quotes => {
+                record.push(std::mem::take(&mut field));
+                records.push(std::mem::take(&mut record));
+            }
+            _ => field.push(c),
+        }
+    }
+    if !field.is_empty() || !record.is_empty() {
+        record.push(field);
+        records.push(record);
+    }
+    records
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::TestDir;
+
+    fn ids(parsed: &Parsed) -> Vec<&str> {
+        parsed.videos.iter().map(|v| v.id.as_str()).collect()
+    }
+
+    fn time(text: &str) -> DateTime<Utc> {
+        text.parse().unwrap()
+    }
+
+    #[test]
+    fn reads_current_and_old_watch_later_csvs() {
+        let current = "Video ID,Playlist Video Creation Timestamp\r\n\
+                       dQw4w9WgXcQ,2023-01-15T12:34:56+00:00\r\n\
+                       jNQXAC9IVRw,2023-02-01T08:00:00+01:00\r\n";
+        let parsed = parse_watch_later(current).unwrap();
+        assert_eq!(ids(&parsed), vec!["dQw4w9WgXcQ", "jNQXAC9IVRw"]);
+        assert_eq!(parsed.videos[1].time, time("2023-02-01T07:00:00Z"));
+
+        let old = "Playlist ID,Add new videos to top,Playlist Visibility\n\
+                   WL,False,Private\n\
+                   \n\
+                   Video Id,Time Added\n\
+                   dQw4w9WgXcQ,2021-03-01 12:34:56 UTC\n\
+                   https://www.youtube.com/@channel,2021-03-02 12:34:56 UTC\n";
+        let parsed = parse_watch_later(old).unwrap();
+        assert_eq!(ids(&parsed), vec!["dQw4w9WgXcQ"]);
+        assert_eq!(parsed.videos[0].time, time("2021-03-01T12:34:56Z"));
+        assert_eq!(parsed.skipped.values().sum::<usize>(), 1);
+
+        assert!(parse_watch_later("Title,URL\nfoo,bar\n").is_err());
+    }
+
+    #[test]
+    fn json_history_skips_ads_and_removed_videos() {
+        let json = r#"[
+            {"header": "YouTube", "title": "Watched a video",
+             "titleUrl": "https://www.youtube.com/watch?v=dQw4w9WgXcQ",
+             "time": "2023-01-15T12:34:56.789Z"},
+            {"header": "YouTube", "title": "Watched an ad",
+             "titleUrl": "https://www.youtube.com/watch?v=jNQXAC9IVRw",
+             "time": "2023-01-15T12:30:00Z",
+             "details": [{"name": "From Google Ads"}]},
+            {"header": "YouTube", "title": "Watched a video that has been removed",
+             "time": "2023-01-14T10:00:00Z"},
+            {"header": "YouTube Music", "title": "Watched a song",
+             "titleUrl": "https://music.youtube.com/watch?v=9bZkp7q19f0",
+             "time": "2022-12-31T23:00:00Z"}
+        ]"#;
+        let parsed = parse_history_json(json).unwrap();
+        assert_eq!(ids(&parsed), vec!["dQw4w9WgXcQ", "9bZkp7q19f0"]);
+        assert_eq!(parsed.skipped[AD], 1);
+        assert_eq!(parsed.skipped[REMOVED], 1);
+
+        assert!(parse_history_json(r#"{"version": 1}"#).is_err());
+    }
+
+    #[test]
+    fn html_history_reads_links_and_localized_dates() {
+        let html = r#"<div class="mdl-grid"><div class="outer-cell mdl-cell mdl-cell--12-col mdl-shadow--2dp"><div class="mdl-grid"><div class="header-cell mdl-cell mdl-cell--12-col"><p class="mdl-typography--title">YouTube<br></p></div><div class="content-cell mdl-cell mdl-cell--6-col mdl-typography--body-1">Watched&nbsp;<a href="https://www.youtube.com/watch?v=dQw4w9WgXcQ&amp;t=10s">Never Gonna Give You Up</a><br><a href="https://www.youtube.com/channel/UCuAXFkgsw1L7xaCfnd5JJOw">Rick Astley</a><br>Jan 15, 2023, 12:34:56 PM EST<br></div></div></div><div class="outer-cell mdl-cell mdl-cell--12-col mdl-shadow--2dp"><div class="mdl-grid"><div class="content-cell mdl-cell mdl-cell--6-col mdl-typography--body-1">Watched a video that has been removed<br>Jan 14, 2023, 9:00:00 AM GMT+01:00<br></div></div></div><div class="outer-cell mdl-cell mdl-cell--12-col mdl-shadow--2dp"><div class="mdl-grid"><div class="content-cell mdl-cell mdl-cell--6-col mdl-typography--body-1">Watched&nbsp;<a href="https://www.youtube.com/watch?v=jNQXAC9IVRw">An ad</a><br>Jan 14, 2023, 8:00:00 AM UTC<br></div><div class="content-cell mdl-cell mdl-cell--12-col mdl-typography--caption"><b>Details:</b><br>&emsp;From Google Ads<br></div></div></div></div>"#;
+        let parsed = parse_history_html(html);
+        assert_eq!(ids(&parsed), vec!["dQw4w9WgXcQ"]);
+        assert_eq!(parsed.videos[0].time, time("2023-01-15T17:34:56Z"));
+        assert_eq!(parsed.skipped[AD], 1);
+        assert_eq!(parsed.skipped[REMOVED], 1);
+    }
+
+    #[test]
+    fn parses_takeout_timestamps() {
+        let expected = Some(time("2023-01-15T11:34:56Z"));
+        assert_eq!(parse_time("2023-01-15T12:34:56+01:00"), expected);
+        assert_eq!(parse_time("2023-01-15 11:34:56 UTC"), expected);
+        assert_eq!(parse_time("Jan 15, 2023, 12:34:56\u{202f}PM CET"), expected);
+        assert_eq!(parse_time("15 Jan 2023, 12:34:56 GMT+01:00"), expected);
+        assert_eq!(parse_time("Jan 15, 2023, 6:34:56 AM GMT-05"), expected);
+        assert_eq!(parse_time("Jan 15, 2023, 12:34:56 PM XYZT"), None);
+        assert_eq!(parse_time("Jan 15, 2023, 12:34:56 PM GMT+9999999"), None);
+        assert_eq!(
+            parse_time("Jan 15, 2023, 12:34:56 PM GMT+01:9999999999"),
+            None
+        );
+    }
+
+    #[test]
+    fn csv_fields_may_be_quoted() {
+        let records = csv_records("a,\"b, c\",\"say \"\"hi\"\"\"\n\"multi\nline\",x");
+        assert_eq!(
+            records,
+            vec![vec!["a", "b, c", "say \"hi\""], vec!["multi\nline", "x"]]
+        );
+    }
+
+    #[test]
+    fn finds_takeout_files_in_a_folder() {
+        let dir = TestDir::new("takeout");
+        let youtube = dir.join("Takeout").join("YouTube and YouTube Music");
+        fs::create_dir_all(youtube.join("playlists")).unwrap();
+        fs::create_dir_all(youtube.join("history")).unwrap();
+        fs::write(youtube.join("playlists/Watch later-videos.csv"), "").unwrap();
+        fs::write(youtube.join("playlists/Music-videos.csv"), "").unwrap();
+        fs::write(youtube.join("history/watch-history.json"), "").unwrap();
+        fs::write(youtube.join("history/watch-history.html"), "").unwrap();
+        fs::write(youtube.join("history/search-history.json"), "").unwrap();
+
+        assert_eq!(
+            find_files(&dir).unwrap(),
+            vec![
+                TakeoutFile::WatchHistory(youtube.join("history/watch-history.json")),
+                TakeoutFile::WatchLater(youtube.join("playlists/Watch later-videos.csv")),
+            ]
+        );
+        assert!(find_files(&dir.join("Takeout.zip")).is_err());
+    }
+}
@@ -0,0 +1,26 @@
+//! Helpers shared by the unit tests.
+
+mod test_dir;
+
+pub use test_dir::TestDir;
+
+use crate::models::VideoMeta;
+
+/// Cached metadata for `id` with neutral values: no title, channel or tags,
+/// a zero duration and fixed dates. Tests set the fields they care about
+/// with struct update syntax: `VideoMeta { duration_seconds: 60, ..meta("a") }`.
+pub fn meta(id: &str) -> VideoMeta {
+    VideoMeta {
+        id: id.to_string(),
+        title: String::new(),
+        channel: String::new(),
+        channel_id: String::new(),
+        duration: String::new(),
+        duration_seconds: 0,
+        published_at: "2020-01-01T00:00:00Z".parse().unwrap(),
+        category_id: String::new(),
+        tags: vec![],
+        fetched_at: "2026-01-01T00:00:00Z".parse().unwrap(),
+        unavailable: false,
+    }
+}
@@ -1,6 +1,6 @@
 use std::sync::LazyLock;
 
-use anyhow::{Result, anyhow, bail};
+use anyhow::{Context, Result, anyhow, bail};
 use regex::Regex;
 use url::Url;
 
@@ -77,6 +77,217 @@ pub fn extract_video_id(input: &str) -> Result<String> {
     }
 }
 
+/// The first sentence of an [`extract_video_id`] error, e.g. "Channel URLs
+/// are not supported", for listing many rejected links; the rest says what
+/// is supported instead.
+pub fn rejection_reason(error: &anyhow::Error) -> String {
+    let message = error.to_string();
+    message
+        .split(". ")
+        .next()
+        .unwrap_or_default()
+        .trim_end_matches('.')
+        .to_string()
+}
+
+/// A video reference parsed from user input: the ID plus any start offset.
+#[derive(Debug, Clone, PartialEq)]
+pub struct VideoLink {
+    pub id: String,
+    pub start_seconds: Option<u64>,
+}
+
+impl VideoLink {
+    /// Parses a URL or ID, keeping a `t=`/`start=` offset if present.
+    pub fn parse(input: &str) -> Result<Self> {
+        Ok(Self {
+            id: extract_video_id(input)?,
+            start_seconds: extract_start_offset(input),
+        })
+    }
+
+    pub fn from_id(id: String) -> Self {
+        Self {
+            id,
+            start_seconds: None,
+        }
+    }
+}
+
+/// Finds YouTube links in free text (chat logs, HTML, Markdown). Stops at
+/// whitespace, quotes and brackets that typically surround a link.
+static LINK_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r#"(?i)(?:https?://)?(?:[a-z0-9-]+\.)*(?:youtube\.com|youtu\.be)/[^\s"'<>()\[\]{}|\\^`]+"#,
+    )
+    .unwrap()
+});
+
+/// Extracts every YouTube video link from arbitrary text. A line consisting of
+/// just a video ID also counts. Each candidate yields either a link or the
+/// reason it was rejected (e.g. a channel URL).
+pub fn find_video_links(text: &str) -> Vec<Result<VideoLink>> {
+    let trimmed = text.trim();
+    if is_valid_id_format(trimmed) {
+        return vec![Ok(VideoLink::from_id(trimmed.to_string()))];
+    }
+
+    LINK_RE
+        .find_iter(text)
+        .map(|m| {
+            // Links copied from HTML keep their escaped ampersands
+            let candidate = m
+                .as_str()
+                .trim_end_matches(['.', ',', ';', ':', '!', '?'])
+                .replace("&amp;", "&");
+            VideoLink::parse(&candidate).with_context(|| format!("'{candidate}'"))
+        })
+        .collect()
+}
+
+/// Playlist IDs (PL..., UU..., OLAK5uy_..., etc.) use the same character set
+/// as video IDs but vary in length.
+static PLAYLIST_ID_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^[a-zA-Z0-9_-]{2,64}$").unwrap());
+
+/// Extracts the playlist ID from a playlist page URL
+/// (e.g. `youtube.com/playlist?list=PL...`, or a watch URL with only `list=`).
+///
+/// Returns `None` for anything else, including watch URLs that also name a
+/// video; see [`extract_playlist_context`] for those.
+pub fn extract_playlist_id(input: &str) -> Option<String> {
+    let parsed = parse_youtube_url(input)?;
+    if !is_playlist_page(&parsed) {
+        return None;
+    }
+    list_param(&parsed)
+}
+
+/// The playlist a video link was opened from
+/// (e.g. `youtube.com/watch?v=ID&list=PL...&index=3`).
+#[derive(Debug, PartialEq)]
+pub struct PlaylistContext {
+    pub playlist_id: String,
+    /// 1-based position of the video in the playlist, from `index=`.
+    pub index: Option<usize>,
+}
+
+/// Extracts the `list=` and `index=` parameters from a video URL.
+///
+/// Returns `None` for playlist pages (see [`extract_playlist_id`]) and for
+/// video URLs without a valid `list=` parameter.
+pub fn extract_playlist_context(input: &str) -> Option<PlaylistContext> {
+    let parsed = parse_youtube_url(input)?;
+    if is_playlist_page(&parsed) {
+        return None;
+    }
+
+    let playlist_id = list_param(&parsed)?;
+    let index = parsed
+        .query_pairs()
+        .find(|(k, _)| k == "index")
+        .and_then(|(_, v)| v.parse::<usize>().ok())
+        .filter(|&i| i > 0);
+
+    Some(PlaylistContext { playlist_id, index })
+}
+
+/// Parses a YouTube or youtu.be URL, adding a scheme if missing.
+fn parse_youtube_url(input: &str) -> Option<Url> {
+    let input = input.trim();
+    let url_string = if input.contains("://") {
+        input.to_string()
+    } else {
+        format!("https://{input}")
+    };
+
+    let parsed = Url::parse(&url_string).ok()?;
+    let host = parsed.host_str()?;
+    (host == "youtu.be" || host.ends_with("youtube.com")).then_some(parsed)
+}
+
+/// Whether the URL shows a playlist rather than a video in a playlist.
+fn is_playlist_page(parsed: &Url) -> bool {
+    parsed.path().starts_with("/playlist")
+        || (parsed.path() == "/watch" && !parsed.query_pairs().any(|(k, _)| k == "v"))
+}
+
+/// Returns the `list=` query parameter if it is a well-formed playlist ID.
+fn list_param(parsed: &Url) -> Option<String> {
+    parsed
+        .query_pairs()
+        .find(|(k, _)| k == "list")
+        .map(|(_, v)| v.to_string())
+        .filter(|id| PLAYLIST_ID_RE.is_match(id))
+}
+
+/// Channel IDs are "UC" followed by 22 URL-safe base64 characters.
+static CHANNEL_ID_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^UC[a-zA-Z0-9_-]{22}$").unwrap());
+
+/// A reference to a YouTube channel, as found in channel URLs.
+#[derive(Debug, PartialEq)]
+pub enum ChannelRef {
+    /// A channel ID (`UC...`), usable directly in feed URLs.
+    Id(String),
+    /// An `@handle`, stored without the `@`.
+    Handle(String),
+    /// A legacy `/c/name` custom URL.
+    Custom(String),
+    /// A legacy `/user/name` URL.
+    User(String),
+}
+
+impl ChannelRef {
+    /// Path of the channel page on youtube.com, used to resolve the channel ID.
+    pub fn page_path(&self) -> String {
+        match self {
+            Self::Id(id) => format!("/channel/{id}"),
+            Self::Handle(handle) => format!("/@{handle}"),
+            Self::Custom(name) => format!("/c/{name}"),
+            Self::User(name) => format!("/user/{name}"),
+        }
+    }
+}
+
+/// Extracts a channel reference from a channel URL (`/channel/ID`, `/@handle`,
+/// `/c/name`, `/user/name`), a bare channel ID, or a bare `@handle`.
+pub fn extract_channel_ref(input: &str) -> Option<ChannelRef> {
+    let input = input.trim();
+
+    if CHANNEL_ID_RE.is_match(input) {
+        return Some(ChannelRef::Id(input.to_string()));
+    }
+    if let Some(handle) = input.strip_prefix('@') {
+        return is_valid_channel_name(handle).then(|| ChannelRef::Handle(handle.to_string()));
+    }
+
+    let parsed = parse_youtube_url(input)?;
+    let mut segments = parsed.path_segments()?.filter(|s| !s.is_empty());
+    let first = segments.next()?;
+
+    if let Some(handle) = first.strip_prefix('@') {
+        return is_valid_channel_name(handle).then(|| ChannelRef::Handle(handle.to_string()));
+    }
+
+    let name = segments.next()?;
+    match first {
+        "channel" if CHANNEL_ID_RE.is_match(name) => Some(ChannelRef::Id(name.to_string())),
+        "c" if is_valid_channel_name(name) => Some(ChannelRef::Custom(name.to_string())),
+        "user" if is_valid_channel_name(name) => Some(ChannelRef::User(name.to_string())),
+        _ => None,
+    }
+}
+
+/// Handles and legacy names use letters, digits, `_`, `-` and `.`.
+fn is_valid_channel_name(name: &str) -> bool {
+    !name.is_empty()
+        && name.len() <= 100
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.'))
+}
+
 /// Path prefixes that contain a video ID directly after them.
 /// Order doesn't matter since we check all prefixes.
 /// Note: `/watch/` is a less common format (e.g., youtube.com/watch/ID) distinct from
@@ -105,7 +316,7 @@ fn check_unsupported_url(path: &str, query: Option<&str>) -> Result<()> {
         || path.starts_with("/user/")
     {
         bail!(
-            "Channel URLs are not supported. Please provide a direct video link (e.g., youtube.com/watch?v=ID)"
+            "Channel URLs are not supported. Please provide a direct video link (e.g., youtube.com/watch?v=ID), or follow the channel with `ytq subscribe`"
         );
     }
 
@@ -150,6 +361,72 @@ pub fn build_canonical_url(video_id: &str) -> String {
     format!("https://www.youtube.com/watch?v={video_id}")
 }
 
+/// Matches YouTube's `t=` syntax: `90`, `90s`, `2m`, `1h2m3s`.
+static UNIT_TIMESTAMP_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^(?:(\d+)h)?(?:(\d+)m)?(?:(\d+)s?)?$").unwrap());
+
+/// Parses a timestamp into seconds. Accepts plain seconds (`90`, `90s`),
+/// unit syntax (`1h2m3s`, `2m`) and clock syntax (`1:30`, `1:02:03`).
+pub fn parse_timestamp(input: &str) -> Option<u64> {
+    let input = input.trim().to_lowercase();
+    if input.is_empty() {
+        return None;
+    }
+
+    if input.contains(':') {
+        let parts: Vec<u64> = input
+            .split(':')
+            .map(|p| p.parse().ok())
+            .collect::<Option<_>>()?;
+        return match parts[..] {
+            [m, s] if s < 60 => to_seconds(0, m, s),
+            [h, m, s] if m < 60 && s < 60 => to_seconds(h, m, s),
+            _ => None,
+        };
+    }
+
+    let caps = UNIT_TIMESTAMP_RE.captures(&input)?;
+    let part = |i: usize| {
+        caps.get(i)
+            .map_or(Some(0), |m| m.as_str().parse::<u64>().ok())
+    };
+    to_seconds(part(1)?, part(2)?, part(3)?)
+}
+
+/// Hours, minutes and seconds in seconds, or `None` if that overflows.
+fn to_seconds(hours: u64, minutes: u64, seconds: u64) -> Option<u64> {
+    hours
+        .checked_mul(3600)?
+        .checked_add(minutes.checked_mul(60)?)?
+        .checked_add(seconds)
+}
+
+/// Extracts a start offset in seconds from the `t=` or `start=` query
+/// parameter or a `#t=` fragment. Zero offsets are treated as absent.
+pub fn extract_start_offset(input: &str) -> Option<u64> {
+    let parsed = parse_youtube_url(input)?;
+
+    let from_query = parsed
+        .query_pairs()
+        .find(|(k, _)| k == "t" || k == "start")
+        .and_then(|(_, v)| parse_timestamp(&v));
+    let from_fragment = || {
+        parsed
+            .fragment()?
+            .split('&')
+            .find_map(|p| p.strip_prefix("t="))
+            .and_then(parse_timestamp)
+    };
+
+    from_query.or_else(from_fragment).filter(|&s| s > 0)
+}
+
+/// Appends a `t=` start offset to a watch URL.
+pub fn url_with_start(url: &str, start_seconds: u64) -> String {
+    let separator = if url.contains('?') { '&' } else { '?' };
+    format!("{url}{separator}t={start_seconds}s")
+}
+
 fn is_valid_id_format(id: &str) -> bool {
     VIDEO_ID_RE.is_match(id)
 }
@@ -349,6 +626,116 @@ mod tests {
         );
     }
 
+    // === Playlist ID extraction ===
+    #[test]
+    fn extract_playlist_id_from_playlist_url() {
+        let result = extract_playlist_id("https://www.youtube.com/playlist?list=PLxxxxxxxxxx");
+        assert_eq!(result.as_deref(), Some("PLxxxxxxxxxx"));
+    }
+
+    #[test]
+    fn extract_playlist_id_without_protocol() {
+        let result = extract_playlist_id("youtube.com/playlist?list=PL_abc-123&si=xyz");
+        assert_eq!(result.as_deref(), Some("PL_abc-123"));
+    }
+
+    #[test]
+    fn extract_playlist_id_from_watch_url_without_video() {
+        let result = extract_playlist_id("https://www.youtube.com/watch?list=PLxxxxxxxxxx");
+        assert_eq!(result.as_deref(), Some("PLxxxxxxxxxx"));
+    }
+
+    #[test]
+    fn extract_playlist_id_ignores_watch_urls() {
+        assert!(
+            extract_playlist_id("https://www.youtube.com/watch?v=dQw4w9WgXcQ&list=PLxxx").is_none()
+        );
+        assert!(extract_playlist_id("dQw4w9WgXcQ").is_none());
+    }
+
+    #[test]
+    fn extract_playlist_id_rejects_invalid() {
+        assert!(extract_playlist_id("https://www.youtube.com/playlist").is_none());
+        assert!(extract_playlist_id("https://www.youtube.com/playlist?list=bad%20id").is_none());
+        assert!(extract_playlist_id("https://vimeo.com/playlist?list=PLxxxxxxxxxx").is_none());
+    }
+
+    // === Channel references ===
+    #[test]
+    fn channel_ref_from_urls() {
+        let id = "UCuAXFkgsw1L7xaCfnd5JJOw";
+        assert_eq!(
+            extract_channel_ref(&format!("https://www.youtube.com/channel/{id}/videos")),
+            Some(ChannelRef::Id(id.to_string()))
+        );
+        assert_eq!(
+            extract_channel_ref("youtube.com/@some.handle"),
+            Some(ChannelRef::Handle("some.handle".to_string()))
+        );
+        assert_eq!(
+            extract_channel_ref("https://m.youtube.com/c/SomeName"),
+            Some(ChannelRef::Custom("SomeName".to_string()))
+        );
+        assert_eq!(
+            extract_channel_ref("https://www.youtube.com/user/oldname"),
+            Some(ChannelRef::User("oldname".to_string()))
+        );
+    }
+
+    #[test]
+    fn channel_ref_from_bare_id_and_handle() {
+        assert!(matches!(
+            extract_channel_ref("UCuAXFkgsw1L7xaCfnd5JJOw"),
+            Some(ChannelRef::Id(_))
+        ));
+        assert_eq!(
+            extract_channel_ref("@handle"),
+            Some(ChannelRef::Handle("handle".to_string()))
+        );
+        assert_eq!(
+            ChannelRef::Handle("handle".to_string()).page_path(),
+            "/@handle"
+        );
+    }
+
+    #[test]
+    fn channel_ref_rejects_non_channels() {
+        assert!(extract_channel_ref("https://www.youtube.com/watch?v=dQw4w9WgXcQ").is_none());
+        assert!(extract_channel_ref("https://www.youtube.com/channel/not-an-id").is_none());
+        assert!(extract_channel_ref("https://vimeo.com/@someone").is_none());
+        assert!(extract_channel_ref("dQw4w9WgXcQ").is_none());
+    }
+
+    // === Playlist context on video URLs ===
+    #[test]
+    fn playlist_context_from_watch_url() {
+        let ctx = extract_playlist_context(
+            "https://www.youtube.com/watch?v=dQw4w9WgXcQ&list=PLabc&index=4",
+        );
+        assert_eq!(
+            ctx,
+            Some(PlaylistContext {
+                playlist_id: "PLabc".to_string(),
+                index: Some(4),
+            })
+        );
+    }
+
+    #[test]
+    fn playlist_context_from_short_link_without_index() {
+        let ctx = extract_playlist_context("youtu.be/dQw4w9WgXcQ?list=PLabc");
+        assert_eq!(ctx.unwrap().index, None);
+    }
+
+    #[test]
+    fn playlist_context_ignores_bad_index_and_plain_urls() {
+        let ctx = extract_playlist_context("youtube.com/watch?v=dQw4w9WgXcQ&list=PLabc&index=0");
+        assert_eq!(ctx.unwrap().index, None);
+        assert!(extract_playlist_context("youtube.com/watch?v=dQw4w9WgXcQ").is_none());
+        assert!(extract_playlist_context("youtube.com/playlist?list=PLabc").is_none());
+        assert!(extract_playlist_context("dQw4w9WgXcQ").is_none());
+    }
+
     // === Search URL errors ===
     #[test]
     fn search_results_url_error() {
@@ -371,6 +758,107 @@ mod tests {
         );
     }
 
+    // === Start timestamps ===
+    #[test]
+    fn parse_timestamp_formats() {
+        assert_eq!(parse_timestamp("42"), Some(42));
+        assert_eq!(parse_timestamp("42s"), Some(42));
+        assert_eq!(parse_timestamp("2m"), Some(120));
+        assert_eq!(parse_timestamp("1h2m3s"), Some(3723));
+        assert_eq!(parse_timestamp("1H"), Some(3600));
+        assert_eq!(parse_timestamp("1:30"), Some(90));
+        assert_eq!(parse_timestamp("1:12:00"), Some(4320));
+    }
+
+    #[test]
+    fn parse_timestamp_rejects_garbage() {
+        assert_eq!(parse_timestamp(""), None);
+        assert_eq!(parse_timestamp("abc"), None);
+        assert_eq!(parse_timestamp("1:75"), None);
+        assert_eq!(parse_timestamp("1:2:3:4"), None);
+        assert_eq!(parse_timestamp("3s2m"), None);
+        assert_eq!(parse_timestamp("9999999999999999h"), None);
+        assert_eq!(parse_timestamp("18446744073709551615m"), None);
+        assert_eq!(parse_timestamp("5124095576030432:00:00"), None);
+    }
+
+    #[test]
+    fn extract_start_offset_from_query_and_fragment() {
+        assert_eq!(
+            extract_start_offset("https://www.youtube.com/watch?v=dQw4w9WgXcQ&t=42s"),
+            Some(42)
+        );
+        assert_eq!(
+            extract_start_offset("https://youtu.be/dQw4w9WgXcQ?t=1h12m"),
+            Some(4320)
+        );
+        assert_eq!(
+            extract_start_offset("https://www.youtube.com/embed/dQw4w9WgXcQ?start=90"),
+            Some(90)
+        );
+        assert_eq!(
+            extract_start_offset("https://www.youtube.com/watch?v=dQw4w9WgXcQ#t=1m30s"),
+            Some(90)
+        );
+    }
+
+    #[test]
+    fn extract_start_offset_absent_or_zero() {
+        assert_eq!(
+            extract_start_offset("https://www.youtube.com/watch?v=dQw4w9WgXcQ"),
+            None
+        );
+        assert_eq!(
+            extract_start_offset("https://youtu.be/dQw4w9WgXcQ?t=0"),
+            None
+        );
+        assert_eq!(extract_start_offset("dQw4w9WgXcQ"), None);
+    }
+
+    #[test]
+    fn url_with_start_appends_offset() {
+        assert_eq!(
+            url_with_start("https://www.youtube.com/watch?v=dQw4w9WgXcQ", 90),
+            "https://www.youtube.com/watch?v=dQw4w9WgXcQ&t=90s"
+        );
+    }
+
+    // === Links in free text ===
+    #[test]
+    fn find_video_links_in_chat_text() {
+        let links = find_video_links(
+            "[12:01] sam: watch https://youtu.be/dQw4w9WgXcQ?t=42, then youtube.com/shorts/9bZkp7q19f0!",
+        );
+        let links: Vec<VideoLink> = links.into_iter().map(Result::unwrap).collect();
+        assert_eq!(links.len(), 2);
+        assert_eq!(links[0].id, "dQw4w9WgXcQ");
+        assert_eq!(links[0].start_seconds, Some(42));
+        assert_eq!(links[1].id, "9bZkp7q19f0");
+    }
+
+    #[test]
+    fn find_video_links_in_html() {
+        let html = r#"<a href="https://www.youtube.com/watch?v=dQw4w9WgXcQ&amp;t=90s">Video</a>"#;
+        let links = find_video_links(html);
+        assert_eq!(links.len(), 1);
+        let link = links.into_iter().next().unwrap().unwrap();
+        assert_eq!(link.id, "dQw4w9WgXcQ");
+        assert_eq!(link.start_seconds, Some(90));
+    }
+
+    #[test]
+    fn find_video_links_bare_id_and_rejections() {
+        let links = find_video_links("  dQw4w9WgXcQ  ");
+        assert_eq!(links[0].as_ref().unwrap().id, "dQw4w9WgXcQ");
+
+        let links = find_video_links("see https://www.youtube.com/@somechannel");
+        assert_eq!(links.len(), 1);
+        let err = format!("{:#}", links[0].as_ref().unwrap_err());
+        assert!(err.contains("Channel URLs are not supported"));
+
+        assert!(find_video_links("no links here").is_empty());
+    }
+
     // === Edge cases ===
     #[test]
     fn watch_url_with_playlist_param() {
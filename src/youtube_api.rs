@@ -10,6 +10,7 @@ use serde_json::Value;
 
 const YOUTUBE_API_BASE: &str = "https://www.googleapis.com/youtube/v3/videos";
 const YOUTUBE_CATEGORIES_API: &str = "https://www.googleapis.com/youtube/v3/videoCategories";
+const YOUTUBE_PLAYLIST_ITEMS_API: &str = "https://www.googleapis.com/youtube/v3/playlistItems";
 
 /// Maximum number of video IDs per API request (YouTube API limit).
 const BATCH_SIZE: usize = 50;
@@ -190,6 +191,86 @@ pub fn fetch_categories(api_key: &str) -> Result<HashMap<String, String>> {
     Ok(categories)
 }
 
+/// Fetches the video IDs of every item in a playlist, in playlist order.
+/// Pages through the playlistItems endpoint 50 items at a time.
+pub fn fetch_playlist_video_ids(playlist_id: &str, api_key: &str) -> Result<Vec<String>> {
+    let mut ids = Vec::new();
+    let mut page_token: Option<String> = None;
+
+    loop {
+        let mut url = format!(
+            "{YOUTUBE_PLAYLIST_ITEMS_API}?part=contentDetails&maxResults={BATCH_SIZE}\
+             &playlistId={playlist_id}&key={api_key}"
+        );
+        if let Some(token) = &page_token {
+            url.push_str(&format!("&pageToken={token}"));
+        }
+
+        // ureq 3.x returns Err for non-2xx status codes
+        let mut response = match ureq::get(&url).call() {
+            Ok(resp) => resp,
+            Err(ureq::Error::StatusCode(403)) => {
+                bail!(
+                    "YouTube API returned 403 Forbidden. Check your API key \
+                     and ensure the YouTube Data API v3 is enabled."
+                );
+            }
+            Err(ureq::Error::StatusCode(404)) => {
+                bail!("playlist '{playlist_id}' was not found (it may be private or deleted)");
+            }
+            Err(ureq::Error::StatusCode(code)) => {
+                bail!("YouTube API returned HTTP {code}");
+            }
+            Err(e) => {
+                return Err(anyhow::anyhow!(e).context("failed to reach YouTube Data API"));
+            }
+        };
+
+        let body: Value = response
+            .body_mut()
+            .read_json()
+            .context("failed to parse YouTube API response")?;
+
+        let (page_ids, next_token) = parse_playlist_page(&body)?;
+        let total = body["pageInfo"]["totalResults"].as_u64().unwrap_or(0);
+        if !page_ids.is_empty() {
+            eprintln!(
+                "Fetching {}-{} of {total}...",
+                ids.len() + 1,
+                ids.len() + page_ids.len()
+            );
+        }
+        ids.extend(page_ids);
+
+        match next_token {
+            Some(token) => page_token = Some(token),
+            None => break,
+        }
+    }
+
+    Ok(ids)
+}
+
+/// Extracts video IDs and the next page token from one playlistItems page.
+fn parse_playlist_page(body: &Value) -> Result<(Vec<String>, Option<String>)> {
+    let items = body["items"]
+        .as_array()
+        .context("unexpected API response: missing 'items' array")?;
+
+    let ids = items
+        .iter()
+        .filter_map(|item| item["contentDetails"]["videoId"].as_str())
+        .map(String::from)
+        .collect();
+
+    let next_token = body["nextPageToken"]
+        .as_str()
+        .filter(|t| !t.is_empty())
+        .map(String::from);
+
+    Ok((ids, next_token))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -256,6 +337,34 @@ mod tests {
         assert_eq!(format_duration(0), "0:00");
     }
 
+    #[test]
+    fn parse_playlist_page_with_next_token() {
+        let body = serde_json::json!({
+            "nextPageToken": "CAUQAA",
+            "items": [
+                {"contentDetails": {"videoId": "dQw4w9WgXcQ"}},
+                {"contentDetails": {"videoId": "jNQXAC9IVRw"}},
+                {"contentDetails": {}}
+            ]
+        });
+        let (ids, next) = parse_playlist_page(&body).unwrap();
+        assert_eq!(ids, vec!["dQw4w9WgXcQ", "jNQXAC9IVRw"]);
+        assert_eq!(next.as_deref(), Some("CAUQAA"));
+    }
+
+    #[test]
+    fn parse_playlist_page_last_page() {
+        let body = serde_json::json!({"items": []});
+        let (ids, next) = parse_playlist_page(&body).unwrap();
+        assert!(ids.is_empty());
+        assert!(next.is_none());
+    }
+
+    #[test]
+    fn parse_playlist_page_missing_items() {
+        assert!(parse_playlist_page(&serde_json::json!({})).is_err());
+    }
+
     #[test]
     fn format_duration_exact_hour() {
         assert_eq!(format_duration(3600), "1:00:00");
@@ -0,0 +1,448 @@
+//! End-to-end tests: each one runs the real binary against its own throwaway
+//! directory via `YTQ_HOME`, `--config-dir` and `--data-dir`.
+
+use std::fs;
+use std::path::Path;
+use std::process::{Command, Output};
+
+#[path = "../src/test_util/test_dir.rs"]
+mod test_dir;
+
+use test_dir::TestDir;
+
+/// `ytq` with every location override cleared, so only what a test sets
+/// applies and nothing touches the real config and data.
+fn ytq() -> Command {
+    let mut command = Command::new(env!("CARGO_BIN_EXE_ytq"));
+    command
+        .env_remove("YTQ_HOME")
+        .env_remove("YTQ_CONFIG_DIR")
+        .env_remove("YTQ_DATA_DIR")
+        .env("NO_COLOR", "1");
+    command
+}
+
+fn run(command: &mut Command) -> String {
+    let output = command.output().unwrap();
+    assert!(output.status.success(), "{}", describe(&output));
+    String::from_utf8(output.stdout).unwrap()
+}
+
+fn fail(command: &mut Command) -> String {
+    let output = command.output().unwrap();
+    assert!(!output.status.success(), "{}", describe(&output));
+    String::from_utf8(output.stderr).unwrap()
+}
+
+fn describe(output: &Output) -> String {
+    format!(
+        "stdout:\n{}\nstderr:\n{}",
+        String::from_utf8_lossy(&output.stdout),
+        String::from_utf8_lossy(&output.stderr)
+    )
+}
+
+/// `ytq` keeping everything in `home`.
+fn ytq_in(home: &Path, args: &[&str]) -> String {
+    run(ytq().env("YTQ_HOME", home).args(args))
+}
+
+fn queue_ids(home: &Path, queue: &str) -> Vec<String> {
+    let json = ytq_in(home, &["list", "--queue", queue, "--format", "json"]);
+    let rows: Vec<serde_json::Value> = serde_json::from_str(&json).unwrap();
+    rows.iter()
+        .map(|row| row["id"].as_str().unwrap().to_string())
+        .collect()
+}
+
+#[test]
+fn home_keeps_config_and_data_together() {
+    let home = TestDir::new("home");
+    ytq_in(&home, &["add", "dQw4w9WgXcQ"]);
+    ytq_in(&home, &["add", "https://youtu.be/jNQXAC9IVRw"]);
+    ytq_in(&home, &["config", "mode", "stack"]);
+
+    assert!(home.join("config.json").exists());
+    assert!(home.join("queue.json").exists());
+    assert!(home.join("history").read_dir().unwrap().next().is_some());
+    assert_eq!(
+        queue_ids(&home, "default"),
+        vec!["dQw4w9WgXcQ", "jNQXAC9IVRw"]
+    );
+
+    let info: serde_json::Value =
+        serde_json::from_str(&ytq_in(&home, &["info", "--format", "json"])).unwrap();
+    assert_eq!(info["data_dir"], home.display().to_string());
+    assert_eq!(info["data_dir_source"], "--home / YTQ_HOME");
+}
+
+#[test]
+fn config_and_data_dirs_can_be_moved_separately() {
+    let dir = TestDir::new("separate");
+    let config = dir.join("config");
+    let data = dir.join("data");
+    let ytq_split = |args: &[&str]| {
+        run(ytq()
+            .arg("--config-dir")
+            .arg(&config)
+            .arg("--data-dir")
+            .arg(&data)
+            .args(args))
+    };
+
+    ytq_split(&["config", "mode", "stack"]);
+    ytq_split(&["add", "dQw4w9WgXcQ"]);
+    assert!(config.join("config.json").exists());
+    assert!(!data.join("config.json").exists());
+    assert!(data.join("queue.json").exists());
+    assert!(data.join("queue.json.lock").exists());
+    assert!(!config.join("queue.json").exists());
+
+    // A specific directory wins over YTQ_HOME
+    let home = dir.join("home");
+    run(ytq()
+        .env("YTQ_HOME", &*home)
+        .arg("--data-dir")
+        .arg(&data)
+        .args(["add", "jNQXAC9IVRw"]));
+    assert!(!home.join("queue.json").exists());
+    let csv = ytq_split(&["list", "--format", "csv"]);
+    assert!(
+        csv.contains("dQw4w9WgXcQ") && csv.contains("jNQXAC9IVRw"),
+        "{csv}"
+    );
+}
+
+#[test]
+fn separate_homes_do_not_share_anything() {
+    let dir = TestDir::new("profiles");
+    let (work, personal) = (dir.join("work"), dir.join("personal"));
+    ytq_in(&work, &["add", "dQw4w9WgXcQ"]);
+    ytq_in(&personal, &["queue", "create", "music"]);
+    ytq_in(&personal, &["add", "--queue", "music", "jNQXAC9IVRw"]);
+
+    assert_eq!(queue_ids(&work, "default"), vec!["dQw4w9WgXcQ"]);
+    assert!(queue_ids(&personal, "default").is_empty());
+    assert_eq!(queue_ids(&personal, "music"), vec!["jNQXAC9IVRw"]);
+    let err = fail(
+        ytq()
+            .env("YTQ_HOME", &work)
+            .args(["list", "--queue", "music"]),
+    );
+    assert!(err.contains("music"), "{err}");
+}
+
+#[test]
+fn undo_and_stats_run_against_the_relocated_history() {
+    let home = TestDir::new("undo");
+    ytq_in(&home, &["add", "dQw4w9WgXcQ"]);
+    ytq_in(&home, &["add", "jNQXAC9IVRw"]);
+    ytq_in(&home, &["remove", "dQw4w9WgXcQ"]);
+    assert_eq!(queue_ids(&home, "default"), vec!["jNQXAC9IVRw"]);
+
+    ytq_in(&home, &["undo"]);
+    assert_eq!(
+        queue_ids(&home, "default"),
+        vec!["dQw4w9WgXcQ", "jNQXAC9IVRw"]
+    );
+    let stats = ytq_in(&home, &["stats", "--all"]);
+    assert!(stats.contains("Videos Added:    2"), "{stats}");
+}
+
+#[test]
+fn migrate_storage_and_doctor_end_to_end() {
+    let home = TestDir::new("migrate");
+    ytq_in(&home, &["add", "dQw4w9WgXcQ"]);
+    ytq_in(&home, &["queue", "create", "music"]);
+    ytq_in(&home, &["add", "--queue", "music", "jNQXAC9IVRw"]);
+
+    ytq_in(&home, &["migrate-storage", "sqlite"]);
+    assert!(home.join("ytq.sqlite3").exists());
+    ytq_in(&home, &["add", "9bZkp7q19f0"]);
+    assert_eq!(
+        queue_ids(&home, "default"),
+        vec!["dQw4w9WgXcQ", "9bZkp7q19f0"]
+    );
+    assert_eq!(queue_ids(&home, "music"), vec!["jNQXAC9IVRw"]);
+    assert!(ytq_in(&home, &["doctor"]).contains("no problems found"));
+
+    ytq_in(&home, &["migrate-storage", "json"]);
+    assert_eq!(
+        queue_ids(&home, "default"),
+        vec!["dQw4w9WgXcQ", "9bZkp7q19f0"]
+    );
+    let stats = ytq_in(&home, &["stats", "--all"]);
+    assert!(stats.contains("Videos Added:    3"), "{stats}");
+}
+
+#[test]
+fn export_then_import_merges_or_replaces() {
+    let dir = TestDir::new("archive");
+    let (old, new) = (dir.join("old"), dir.join("new"));
+    let archive = dir.join("ytq-export.json");
+    ytq_in(&old, &["add", "dQw4w9WgXcQ"]);
+    ytq_in(&old, &["queue", "create", "music"]);
+    ytq_in(&old, &["add", "--queue", "music", "jNQXAC9IVRw"]);
+    ytq_in(&old, &["config", "youtube_api_key", "not-for-export"]);
+    ytq_in(&old, &["export", archive.to_str().unwrap()]);
+    assert!(
+        !fs::read_to_string(&archive)
+            .unwrap()
+            .contains("not-for-export")
+    );
+
+    ytq_in(&new, &["add", "9bZkp7q19f0"]);
+    ytq_in(&new, &["import", archive.to_str().unwrap()]);
+    let again = ytq_in(&new, &["import", archive.to_str().unwrap()]);
+    assert!(again.contains("Skipped 2 history event(s)"), "{again}");
+    assert_eq!(
+        queue_ids(&new, "default"),
+        vec!["9bZkp7q19f0", "dQw4w9WgXcQ"]
+    );
+    assert_eq!(queue_ids(&new, "music"), vec!["jNQXAC9IVRw"]);
+    let stats = ytq_in(&new, &["stats", "--all"]);
+    assert!(stats.contains("Videos Added:    3"), "{stats}");
+
+    ytq_in(&new, &["import", "--replace", archive.to_str().unwrap()]);
+    assert_eq!(queue_ids(&new, "default"), vec!["dQw4w9WgXcQ"]);
+    let stats = ytq_in(&new, &["stats", "--all"]);
+    assert!(stats.contains("Videos Added:    2"), "{stats}");
+
+    fs::write(&archive, "{}").unwrap();
+    let err = fail(
+        ytq()
+            .env("YTQ_HOME", &new)
+            .args(["import", archive.to_str().unwrap()]),
+    );
+    assert!(err.contains("not a ytq archive"), "{err}");
+}
+
+#[test]
+fn takeout_import_queues_watch_later_and_backfills_history() {
+    let home = TestDir::new("takeout");
+    let youtube = home.join("Takeout").join("YouTube and YouTube Music");
+    fs::create_dir_all(youtube.join("playlists")).unwrap();
+    fs::create_dir_all(youtube.join("history")).unwrap();
+    fs::write(
+        youtube.join("playlists/Watch later-videos.csv"),
+        "Video ID,Playlist Video Creation Timestamp\n\
+         dQw4w9WgXcQ,2023-01-15T12:34:56+00:00\n\
+         jNQXAC9IVRw,2022-02-01T08:00:00+00:00\n",
+    )
+    .unwrap();
+    fs::write(
+        youtube.join("history/watch-history.json"),
+        r#"[{"header": "YouTube", "titleUrl": "https://www.youtube.com/watch?v=9bZkp7q19f0",
+             "time": "2024-05-01T10:00:00.123Z"},
+            {"header": "YouTube", "titleUrl": "https://www.youtube.com/watch?v=kJQP7kiw5Fk",
+             "time": "2019-12-24T10:00:00Z"}]"#,
+    )
+    .unwrap();
+    let takeout = home.join("Takeout");
+
+    ytq_in(&home, &["import", "--takeout", takeout.to_str().unwrap()]);
+    let again = ytq_in(&home, &["import", "--takeout", takeout.to_str().unwrap()]);
+    assert!(again.contains("2 already imported"), "{again}");
+    assert_eq!(
+        queue_ids(&home, "default"),
+        vec!["jNQXAC9IVRw", "dQw4w9WgXcQ"]
+    );
+    let stats = ytq_in(&home, &["stats", "--all"]);
+    assert!(stats.contains("Videos Watched:  2"), "{stats}");
+    let stats = ytq_in(
+        &home,
+        &["stats", "--from", "2019-01-01", "--to", "2019-12-31"],
+    );
+    assert!(stats.contains("Videos Watched:  1"), "{stats}");
+
+    // Removed since the last import: a newer export must not bring it back
+    ytq_in(&home, &["remove", "jNQXAC9IVRw"]);
+    let again = ytq_in(&home, &["import", "--takeout", takeout.to_str().unwrap()]);
+    assert!(again.contains("Added: 0 video(s)"), "{again}");
+    assert!(
+        again.contains("1 imported before and since watched or removed"),
+        "{again}"
+    );
+    assert_eq!(queue_ids(&home, "default"), vec!["dQw4w9WgXcQ"]);
+    let stats = ytq_in(&home, &["stats", "--all"]);
+    assert!(stats.contains("Videos Added:    2"), "{stats}");
+}
+
+#[test]
+fn bookmarks_import_and_export_round_trip() {
+    let dir = TestDir::new("bookmarks");
+    let (home, other) = (dir.join("home"), dir.join("other"));
+    let pocket = dir.join("pocket.csv");
+    fs::write(
+        &pocket,
+        "title,url,time_added,tags,status\n\
+         Zoo,https://www.youtube.com/watch?v=jNQXAC9IVRw,1673786096,first|zoo,unread\n\
+         Rick,https://youtu.be/dQw4w9WgXcQ?t=42,1600000000,,unread\n\
+         Channel,https://www.youtube.com/@RickAstleyYT,1600000000,,unread\n\
+         Blog,https://example.com/post,1600000000,,archive\n",
+    )
+    .unwrap();
+
+    let out = ytq_in(&home, &["import", "--bookmarks", pocket.to_str().unwrap()]);
+    assert!(out.contains("Added: 2 video(s)"), "{out}");
+    assert!(
+        out.contains("@RickAstleyYT: Channel URLs are not supported"),
+        "{out}"
+    );
+    assert!(out.contains("Ignored 1 link(s) to other sites"), "{out}");
+    // Oldest bookmark first
+    assert_eq!(
+        queue_ids(&home, "default"),
+        vec!["dQw4w9WgXcQ", "jNQXAC9IVRw"]
+    );
+
+    let html = dir.join("queue.html");
+    ytq_in(&home, &["export", "--bookmarks", html.to_str().unwrap()]);
+    ytq_in(&other, &["import", "--bookmarks", html.to_str().unwrap()]);
+    let list = ytq_in(&other, &["list", "--format", "json"]);
+    let rows: Vec<serde_json::Value> = serde_json::from_str(&list).unwrap();
+    assert_eq!(rows.len(), 2);
+    assert_eq!(rows[0]["start_seconds"], 42, "{list}");
+    assert!(
+        rows[0]["added_at"]
+            .as_str()
+            .unwrap()
+            .starts_with("2020-09-13")
+    );
+    assert_eq!(rows[1]["tags"], serde_json::json!(["first", "zoo"]));
+}
+
+#[test]
+fn queue_names_cannot_reach_outside_the_data_dir() {
+    let dir = TestDir::new("traversal");
+    let home = dir.join("out");
+    let outside = dir.join("x.json");
+    fs::write(&outside, "not json").unwrap();
+    let pocket = dir.join("p.csv");
+    fs::write(&pocket, "url\nhttps://youtu.be/dQw4w9WgXcQ\n").unwrap();
+
+    for args in [
+        vec!["import", "--bookmarks", pocket.to_str().unwrap()],
+        vec!["add", "dQw4w9WgXcQ"],
+        vec!["list"],
+        vec!["config", "mode", "stack"],
+    ] {
+        let err = fail(
+            ytq()
+                .env("YTQ_HOME", &*home)
+                .args(["--queue", "../../x"])
+                .args(&args),
+        );
+        assert!(err.contains("invalid queue name"), "{err}");
+    }
+    assert_eq!(fs::read_to_string(&outside).unwrap(), "not json");
+    let quarantined = fs::read_dir(&dir).unwrap().any(|entry| {
+        entry
+            .unwrap()
+            .file_name()
+            .to_string_lossy()
+            .contains("corrupt")
+    });
+    assert!(!quarantined);
+}
+
+#[test]
+fn top_and_bottom_outrank_priorities() {
+    let home = TestDir::new("top");
+    ytq_in(&home, &["add", "--priority", "high", "dQw4w9WgXcQ"]);
+    ytq_in(&home, &["add", "jNQXAC9IVRw"]);
+    ytq_in(&home, &["add", "--priority", "low", "9bZkp7q19f0"]);
+
+    let next = |home: &Path| {
+        let json = ytq_in(home, &["peek", "3", "--format", "json"]);
+        let rows: Vec<serde_json::Value> = serde_json::from_str(&json).unwrap();
+        rows.iter()
+            .map(|row| row["id"].as_str().unwrap().to_string())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(
+        next(&home),
+        vec!["dQw4w9WgXcQ", "jNQXAC9IVRw", "9bZkp7q19f0"]
+    );
+
+    let out = ytq_in(&home, &["top", "9bZkp7q19f0"]);
+    assert!(out.contains("priority now High"), "{out}");
+    assert_eq!(
+        next(&home),
+        vec!["9bZkp7q19f0", "dQw4w9WgXcQ", "jNQXAC9IVRw"]
+    );
+
+    ytq_in(&home, &["bottom", "dQw4w9WgXcQ"]);
+    assert_eq!(
+        next(&home),
+        vec!["9bZkp7q19f0", "jNQXAC9IVRw", "dQw4w9WgXcQ"]
+    );
+
+    // Undo restores the old priority along with the position
+    ytq_in(&home, &["undo"]);
+    assert_eq!(
+        next(&home),
+        vec!["9bZkp7q19f0", "dQw4w9WgXcQ", "jNQXAC9IVRw"]
+    );
+}
+
+#[test]
+fn player_that_fails_to_start_leaves_the_video_queued() {
+    let home = TestDir::new("player");
+    ytq_in(&home, &["add", "dQw4w9WgXcQ"]);
+
+    let err =
+        fail(
+            ytq()
+                .env("YTQ_HOME", &*home)
+                .args(["next", "--player", "ytq-no-such-player {url}"]),
+        );
+    assert!(err.contains("was not found"), "{err}");
+    assert_eq!(queue_ids(&home, "default"), vec!["dQw4w9WgXcQ"]);
+    let stats = ytq_in(&home, &["stats", "--all"]);
+    assert!(stats.contains("Videos Watched:  0"), "{stats}");
+
+    // Nothing to undo either: the failed attempt left no trace
+    ytq_in(&home, &["undo"]);
+    assert!(queue_ids(&home, "default").is_empty());
+}
+
+#[test]
+fn undoing_a_move_and_an_add_counts_the_add_as_undone() {
+    let home = TestDir::new("undo-move");
+    ytq_in(&home, &["add", "dQw4w9WgXcQ"]);
+    ytq_in(&home, &["add", "jNQXAC9IVRw"]);
+    ytq_in(&home, &["add", "9bZkp7q19f0"]);
+    ytq_in(&home, &["move", "dQw4w9WgXcQ", "3"]);
+
+    ytq_in(&home, &["undo", "2"]);
+    assert_eq!(
+        queue_ids(&home, "default"),
+        vec!["dQw4w9WgXcQ", "jNQXAC9IVRw"]
+    );
+    let stats = ytq_in(&home, &["stats", "--all"]);
+    assert!(stats.contains("Videos Added:    2"), "{stats}");
+}
+
+#[test]
+fn top_warns_when_the_mode_does_not_pick_by_position() {
+    let home = TestDir::new("top-mode");
+    ytq_in(&home, &["add", "dQw4w9WgXcQ"]);
+    ytq_in(&home, &["add", "jNQXAC9IVRw"]);
+
+    let out = ytq_in(&home, &["top", "jNQXAC9IVRw"]);
+    assert!(!out.contains("Note:"), "{out}");
+
+    ytq_in(&home, &["config", "mode", "shortest"]);
+    let out = ytq_in(&home, &["top", "dQw4w9WgXcQ"]);
+    assert!(
+        out.contains("'shortest' mode doesn't pick by position"),
+        "{out}"
+    );
+    assert!(out.contains("picked next"), "{out}");
+    // The move itself still happens
+    assert_eq!(
+        queue_ids(&home, "default"),
+        vec!["dQw4w9WgXcQ", "jNQXAC9IVRw"]
+    );
+}